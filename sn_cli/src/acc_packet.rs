@@ -7,8 +7,10 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod change_tracking;
+mod safe_ignore;
 
 use change_tracking::*;
+use safe_ignore::SafeIgnore;
 
 use super::{
     files::{download_file, FilesUploader},
@@ -20,7 +22,8 @@ use sn_client::{
     protocol::storage::{Chunk, RegisterAddress, RetryStrategy},
     registers::EntryHash,
     transfers::{DerivationIndex, MainSecretKey},
-    Client, FilesApi, FolderEntry, FoldersApi, Metadata, UploadCfg, WalletClient,
+    Client, FileAttributes, FilesApi, FolderEntry, FolderItemDiff, FolderVersion, FoldersApi,
+    Metadata, UploadCfg, WalletClient,
 };
 
 use bls::PublicKey;
@@ -31,10 +34,10 @@ use color_eyre::{
 use std::{
     collections::{
         btree_map::{Entry, OccupiedEntry},
-        BTreeMap,
+        BTreeMap, BTreeSet,
     },
     ffi::OsString,
-    fs::{create_dir_all, remove_dir_all, remove_file, File},
+    fs::{create_dir_all, remove_dir_all, remove_file, rename, File},
     io::Write,
     path::{Path, PathBuf},
 };
@@ -71,6 +74,10 @@ const ACC_PACKET_OWNER_DERIVATION_INDEX: DerivationIndex = DerivationIndex([0x1;
 /// content is not uploaded to the network, but only kept locally in order to realise which files/dirs
 /// the user has made changes on compared to their last version retrieved from the network.
 ///
+/// Files/dirs can be excluded from being tracked and synced with the network by adding `.safeignore`
+/// files, which have the same format and semantics as `.gitignore` files: their patterns apply to the
+/// dir they are in and its subdirs, the patterns found in a subdir taking precedence.
+///
 /// A subdirectory called `metadata` is kept under `.safe` directory with the following files:
 /// - A file named `root_folder.addr` which contains the network address where the root Folder is stored,
 /// which is the one holding the entire hierarchy of user's files/dirs to be kept in sync with local changes
@@ -241,9 +248,11 @@ impl AccountPacket {
     }
 
     /// Generate a report with differences found in local files/folders in comparison with their versions stored on the network.
-    pub fn status(&self) -> Result<()> {
+    /// Files changed locally which were also replaced or removed remotely are reported as conflicts.
+    pub async fn status(&self) -> Result<()> {
         println!("Looking for local changes made to files/folders compared to version on network at: {} ...", self.root_folder_addr().to_hex());
-        let changes = self.scan_files_and_folders_for_changes(false)?;
+        let changed_remotely = self.find_files_changed_remotely().await?;
+        let changes = self.scan_files_and_folders_for_changes(false, &changed_remotely)?;
 
         if changes.mutations.is_empty() {
             println!("No local changes made to files/folders.");
@@ -254,14 +263,34 @@ impl AccountPacket {
             let num_of_changes = changes.mutations.len();
             println!("\nChanges found to local files/folders: {num_of_changes}");
         }
+
+        // list the conflicts found when syncing which are still to be resolved by the user
+        let conflicts = read_conflicts(&self.tracking_info_dir)?;
+        let conflicts: Vec<_> = conflicts
+            .iter()
+            .filter(|(conflict_path, _)| self.files_dir.join(conflict_path).exists())
+            .collect();
+        if !conflicts.is_empty() {
+            println!(
+                "\nFiles changed both locally and remotely, with their local versions kept aside:"
+            );
+            for (conflict_path, file_path) in conflicts {
+                println!("{file_path:?}: local version kept at {conflict_path:?}");
+            }
+            println!("Remove or rename the local versions kept once the conflicts are resolved.");
+        }
         Ok(())
     }
 
     /// Sync local changes made to files and folder with their version on the network,
     /// both pushing and pulling changes to/form the network.
+    /// Files changed locally, and either changed or removed remotely since they were last synced, are not overwritten,
+    /// the local version is kept aside as a new file named after the file and the local host,
+    /// e.g. `notes.conflict-<host>.txt`, while the remote version is pulled onto the file.
     pub async fn sync(&mut self, upload_cfg: UploadCfg, make_data_public: bool) -> Result<()> {
+        let changed_remotely = self.find_files_changed_remotely().await?;
         let ChangesToApply { folders, mutations } =
-            self.scan_files_and_folders_for_changes(make_data_public)?;
+            self.scan_files_and_folders_for_changes(make_data_public, &changed_remotely)?;
 
         if mutations.is_empty() {
             println!("No local changes made to files/folders to be pushed to network.");
@@ -286,6 +315,7 @@ impl AccountPacket {
         }
 
         // update tracking information based on mutations detected locally
+        let mut conflicts = read_conflicts(&self.tracking_info_dir)?;
         for mutation in mutations {
             match mutation {
                 Mutation::NewFile(tracking_info) | Mutation::NewFolder(tracking_info) => {
@@ -295,10 +325,21 @@ impl AccountPacket {
                 | Mutation::FolderRemoved((_, meta_xorname)) => {
                     self.remove_tracking_info(meta_xorname);
                 }
-                Mutation::FileContentChanged((meta_xorname, tracking_info)) => {
+                Mutation::FileContentChanged((meta_xorname, tracking_info))
+                | Mutation::FileAttributesChanged((meta_xorname, tracking_info)) => {
                     self.store_tracking_info(tracking_info)?;
                     self.remove_tracking_info(meta_xorname);
                 }
+                Mutation::FileConflict((file_path, tracking_info)) => {
+                    // the local version is moved aside, the remote one is then pulled onto its path
+                    let relative_path = self.get_relative_path(&file_path)?;
+                    rename(&file_path, &tracking_info.file_path)?;
+                    let _ = conflicts.insert(
+                        self.get_relative_path(&tracking_info.file_path)?,
+                        relative_path,
+                    );
+                    self.store_tracking_info(tracking_info)?;
+                }
             }
         }
 
@@ -320,7 +361,7 @@ impl AccountPacket {
 
         // Now let's check if any file/folder was removed remotely so we remove them locally from disk.
         // We do it in two phases, first we get rid of all dirs that were removed, then we go through
-        // the files, this is to make sure we remove files which belong to nested folders being removed.
+        // the files and symlinks, this is to make sure we remove files which belong to nested folders being removed.
         let mut curr_tracking_info = read_tracking_info_from_disk(&self.meta_dir)?;
         curr_tracking_info.retain(|_, tracking_info| {
             if let FolderEntry::Folder(_) = tracking_info.metadata.content {
@@ -330,7 +371,7 @@ impl AccountPacket {
             }
         });
         curr_tracking_info.retain(|_, tracking_info| {
-            if let FolderEntry::File(_) = tracking_info.metadata.content {
+            if !matches!(tracking_info.metadata.content, FolderEntry::Folder(_)) {
                 !self.remove_tracking_if_not_found_in_folders(tracking_info, &mut updated_folders)
             } else {
                 true
@@ -339,18 +380,74 @@ impl AccountPacket {
 
         self.curr_tracking_info = curr_tracking_info;
 
+        // conflicts whose local versions are no longer around are considered resolved
+        conflicts.retain(|conflict_path, _| self.files_dir.join(conflict_path).exists());
+        store_conflicts(&self.tracking_info_dir, &conflicts)?;
+
         Ok(())
     }
 
+    /// Retrieve from the network all the versions of the Folder stored for the given local directory.
+    pub async fn folder_history(&self, path: &Path) -> Result<Vec<FolderVersion>> {
+        let mut folders_api = self.retrieve_folder(path).await?;
+        let history = folders_api.history().await?;
+        Ok(history)
+    }
+
+    /// Report the differences between two versions of the Folder stored for the given local directory.
+    pub async fn folder_diff(
+        &self,
+        path: &Path,
+        from: EntryHash,
+        to: EntryHash,
+    ) -> Result<BTreeMap<String, FolderItemDiff>> {
+        let mut folders_api = self.retrieve_folder(path).await?;
+        let diff = folders_api.diff(from, to).await?;
+        Ok(diff)
+    }
+
+    /// Restore a file/folder item of the Folder stored for the given local directory to an older version
+    /// of it, syncing the Folder with the network. The restored item is brought onto the local directory
+    /// by a subsequent `sync`.
+    pub async fn restore_folder_item(
+        &self,
+        path: &Path,
+        version: EntryHash,
+        upload_cfg: UploadCfg,
+    ) -> Result<Metadata> {
+        let mut folders_api = self.retrieve_folder(path).await?;
+        let (_, _, metadata) = folders_api.restore(version).await?;
+        folders_api.sync(upload_cfg).await?;
+        Ok(metadata)
+    }
+
     // Private helpers
 
-    // Generate the path relative to the user's root folder
+    // Retrieve from the network the Folder stored for the given local directory.
+    async fn retrieve_folder(&self, path: &Path) -> Result<FoldersApi> {
+        let path = path.canonicalize()?;
+        let (folders_api, folder_change) = self.find_folder_in_tracking_info(&path)?;
+        if folder_change.is_new_folder() {
+            bail!("The path {path:?} is not a directory whose Folder was synced with the network");
+        }
+
+        let folders_api = FoldersApi::retrieve(
+            self.client.clone(),
+            &self.wallet_dir,
+            *folders_api.address(),
+        )
+        .await?;
+        Ok(folders_api)
+    }
+
+    // Generate the path relative to the user's root folder.
+    // Only the parent dir of a symlink is canonicalised, so it's not resolved to its target path.
     fn get_relative_path(&self, path: &Path) -> Result<PathBuf> {
-        let relative_path = path
-            .to_path_buf()
-            .canonicalize()?
-            .strip_prefix(&self.files_dir)?
-            .to_path_buf();
+        let abs_path = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) if path.is_symlink() => parent.canonicalize()?.join(name),
+            _ => path.canonicalize()?,
+        };
+        let relative_path = abs_path.strip_prefix(&self.files_dir)?.to_path_buf();
         Ok(relative_path)
     }
 
@@ -422,7 +519,7 @@ impl AccountPacket {
                     }
                 }
             }
-            FolderEntry::File(_) => {
+            FolderEntry::File(_) | FolderEntry::Symlink(_) => {
                 match find_by_name_in_parent_folder(
                     &tracking_info.metadata.name,
                     &abs_path,
@@ -449,10 +546,62 @@ impl AccountPacket {
         removed
     }
 
+    // Retrieve from the network the Folders synced so far, returning the entries of the tracked
+    // files which were either replaced or removed remotely since they were last synced.
+    async fn find_files_changed_remotely(&self) -> Result<BTreeSet<EntryHash>> {
+        let mut addresses = vec![];
+        if self.root_folder_created {
+            addresses.push(self.root_folder_addr);
+        }
+        addresses.extend(
+            self.curr_tracking_info
+                .values()
+                .filter_map(|tracking_info| match tracking_info.metadata.content {
+                    FolderEntry::Folder(addr) => Some(addr),
+                    FolderEntry::File(_) | FolderEntry::Symlink(_) => None,
+                }),
+        );
+
+        let mut tasks = JoinSet::new();
+        for address in addresses {
+            let client = self.client.clone();
+            let wallet_dir = self.wallet_dir.clone();
+            tasks.spawn(async move {
+                FoldersApi::retrieve(client, &wallet_dir, address)
+                    .await
+                    .map_err(|err| eyre!("Failed to retrieve Folder {}: {err}", address.to_hex()))
+            });
+        }
+
+        let mut changed = BTreeSet::new();
+        while let Some(res) = tasks.join_next().await {
+            let folders_api = res??;
+            changed.extend(
+                self.curr_tracking_info
+                    .values()
+                    .filter(|tracking_info| {
+                        matches!(tracking_info.metadata.content, FolderEntry::File(_))
+                            && (folders_api.is_replaced(&tracking_info.entry_hash)
+                                || folders_api.is_removed(&tracking_info.entry_hash))
+                    })
+                    .map(|tracking_info| tracking_info.entry_hash),
+            );
+        }
+
+        Ok(changed)
+    }
+
     // Scan existing files and folders on disk, generating a report of all the detected
     // changes based on the tracking info kept locally.
+    // Files changed locally whose tracked entry is found in changed_remotely are reported as
+    // conflicts, and added as new files instead of replacing their remote versions, or being
+    // brought back if they were removed remotely.
     // If make_data_public is false the metadata chunks are encrypted.
-    fn scan_files_and_folders_for_changes(&self, make_data_public: bool) -> Result<ChangesToApply> {
+    fn scan_files_and_folders_for_changes(
+        &self,
+        make_data_public: bool,
+        changed_remotely: &BTreeSet<EntryHash>,
+    ) -> Result<ChangesToApply> {
         // we don't use the local cache in order to realise of any changes made to files content.
         let mut chunk_manager = ChunkManager::new(&self.tracking_info_dir);
         chunk_manager.chunk_with_iter(self.iter_only_files(), false, false)?;
@@ -474,20 +623,36 @@ impl AccountPacket {
                 .parent()
                 .map(|parent| folders.entry(parent.to_path_buf()))
             {
+                let metadata = Metadata::new(
+                    chunked_file
+                        .file_name
+                        .to_str()
+                        .unwrap_or("unknown")
+                        .to_string(),
+                    FolderEntry::File(chunked_file.data_map.clone()),
+                )
+                .with_attributes(read_file_attributes(file_path)?);
+
                 // try to find the tracking info of the file/folder by its name
                 match self.get_tracking_info(file_path) {
                     Ok(Some(tracking_info)) => match &tracking_info.metadata.content {
-                        FolderEntry::File(chunk) => {
-                            if chunk.address() != &chunked_file.head_chunk_address {
+                        FolderEntry::File(chunk)
+                            if chunk.address() == &chunked_file.head_chunk_address =>
+                        {
+                            // attributes changed locally are dropped if the file changed remotely
+                            if attributes_changed(
+                                &tracking_info.metadata.attributes,
+                                &metadata.attributes,
+                            ) && !changed_remotely.contains(&tracking_info.entry_hash)
+                            {
                                 let (entry_hash, meta_xorname, metadata) = replace_item_in_folder(
                                     &mut parent_folder,
                                     tracking_info.entry_hash,
-                                    chunked_file.file_name.clone(),
-                                    chunked_file.data_map.clone(),
+                                    metadata,
                                     encryption_pk,
                                 )?;
 
-                                changes.mutations.push(Mutation::FileContentChanged((
+                                changes.mutations.push(Mutation::FileAttributesChanged((
                                     tracking_info.meta_xorname,
                                     MetadataTrackingInfo {
                                         file_path: file_path.to_path_buf(),
@@ -498,13 +663,60 @@ impl AccountPacket {
                                 )));
                             }
                         }
+                        FolderEntry::File(_)
+                            if changed_remotely.contains(&tracking_info.entry_hash) =>
+                        {
+                            // File changed locally, and changed or removed remotely, the local
+                            // version is kept as a new file next to it.
+                            let conflict_path = conflict_file_path(file_path);
+                            let metadata = Metadata {
+                                name: conflict_path
+                                    .file_name()
+                                    .and_then(|name| name.to_str())
+                                    .unwrap_or("unknown")
+                                    .to_string(),
+                                ..metadata
+                            };
+                            let (entry_hash, meta_xorname, metadata) = parent_folder
+                                .get_mut()
+                                .0
+                                .add_item(metadata, encryption_pk)?;
+                            parent_folder.get_mut().1.has_new_entries();
+
+                            changes.mutations.push(Mutation::FileConflict((
+                                file_path.to_path_buf(),
+                                MetadataTrackingInfo {
+                                    file_path: conflict_path,
+                                    meta_xorname,
+                                    metadata,
+                                    entry_hash,
+                                },
+                            )));
+                        }
+                        FolderEntry::File(_) | FolderEntry::Symlink(_) => {
+                            let (entry_hash, meta_xorname, metadata) = replace_item_in_folder(
+                                &mut parent_folder,
+                                tracking_info.entry_hash,
+                                metadata,
+                                encryption_pk,
+                            )?;
+
+                            changes.mutations.push(Mutation::FileContentChanged((
+                                tracking_info.meta_xorname,
+                                MetadataTrackingInfo {
+                                    file_path: file_path.to_path_buf(),
+                                    meta_xorname,
+                                    metadata,
+                                    entry_hash,
+                                },
+                            )));
+                        }
                         FolderEntry::Folder(_) => {
                             // New file found where there used to be a folder
                             let (entry_hash, meta_xorname, metadata) = replace_item_in_folder(
                                 &mut parent_folder,
                                 tracking_info.entry_hash,
-                                chunked_file.file_name.clone(),
-                                chunked_file.data_map.clone(),
+                                metadata,
                                 encryption_pk,
                             )?;
                             changes
@@ -518,12 +730,10 @@ impl AccountPacket {
                         }
                     },
                     Ok(None) => {
-                        let (entry_hash, meta_xorname, metadata) =
-                            parent_folder.get_mut().0.add_file(
-                                chunked_file.file_name.clone(),
-                                chunked_file.data_map.clone(),
-                                encryption_pk,
-                            )?;
+                        let (entry_hash, meta_xorname, metadata) = parent_folder
+                            .get_mut()
+                            .0
+                            .add_item(metadata, encryption_pk)?;
                         parent_folder.get_mut().1.has_new_entries();
 
                         changes
@@ -542,9 +752,72 @@ impl AccountPacket {
             }
         }
 
-        // now let's check if any file/folder was removed from disk
+        // add symlinks to the corresponding Folders, they are stored with the path they point to
+        let symlinks = self.read_symlinks_from_disk();
+        for (link_path, target) in symlinks.iter() {
+            if let Some(Entry::Occupied(mut parent_folder)) = link_path
+                .parent()
+                .map(|parent| folders.entry(parent.to_path_buf()))
+            {
+                let name = link_path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("unknown");
+                let metadata =
+                    Metadata::new(name.to_string(), FolderEntry::Symlink(target.clone()))
+                        .with_attributes(read_file_attributes(link_path)?);
+
+                match self.get_tracking_info(link_path) {
+                    Ok(Some(tracking_info)) => {
+                        if tracking_info.metadata.content != metadata.content {
+                            let (entry_hash, meta_xorname, metadata) = replace_item_in_folder(
+                                &mut parent_folder,
+                                tracking_info.entry_hash,
+                                metadata,
+                                encryption_pk,
+                            )?;
+
+                            changes.mutations.push(Mutation::FileContentChanged((
+                                tracking_info.meta_xorname,
+                                MetadataTrackingInfo {
+                                    file_path: link_path.to_path_buf(),
+                                    meta_xorname,
+                                    metadata,
+                                    entry_hash,
+                                },
+                            )));
+                        }
+                    }
+                    Ok(None) => {
+                        let (entry_hash, meta_xorname, metadata) = parent_folder
+                            .get_mut()
+                            .0
+                            .add_item(metadata, encryption_pk)?;
+                        parent_folder.get_mut().1.has_new_entries();
+
+                        changes
+                            .mutations
+                            .push(Mutation::NewFile(MetadataTrackingInfo {
+                                file_path: link_path.to_path_buf(),
+                                meta_xorname,
+                                metadata,
+                                entry_hash,
+                            }));
+                    }
+                    Err(err) => {
+                        println!("Skipping symlink {link_path:?}: {err:?}");
+                    }
+                }
+            }
+        }
+
+        // now let's check if any file/folder was removed from disk, items replaced
+        // by a symlink were already taken care of above.
         for (item_path, tracking_info) in self.curr_tracking_info.iter() {
             let abs_path = self.files_dir.join(item_path);
+            if symlinks.contains_key(&abs_path) {
+                continue;
+            }
             match tracking_info.metadata.content {
                 FolderEntry::Folder(_) => {
                     if !folders.contains_key(&abs_path) {
@@ -555,7 +828,7 @@ impl AccountPacket {
                         )));
                     }
                 }
-                FolderEntry::File(_) => {
+                FolderEntry::File(_) | FolderEntry::Symlink(_) => {
                     if chunk_manager
                         .iter_chunked_files()
                         .all(|chunked_file| chunked_file.file_path != abs_path)
@@ -604,8 +877,13 @@ impl AccountPacket {
                     .or_insert(self.find_folder_in_tracking_info(&parent)?);
 
                 if folder_change.is_new_folder() {
+                    let metadata = Metadata::new(
+                        dir_name.to_str().unwrap_or("unknown").to_string(),
+                        FolderEntry::Folder(curr_folder_addr),
+                    )
+                    .with_attributes(read_file_attributes(&dir_path)?);
                     let (entry_hash, meta_xorname, metadata) =
-                        parent_folder.add_folder(dir_name, curr_folder_addr, encryption_pk)?;
+                        parent_folder.add_item(metadata, encryption_pk)?;
                     parent_folder_change.has_new_entries();
 
                     changes
@@ -644,7 +922,7 @@ impl AccountPacket {
                         folder_change = FolderChange::NoChange;
                         Some(addr)
                     }
-                    FolderEntry::File(_) => None,
+                    FolderEntry::File(_) | FolderEntry::Symlink(_) => None,
                 }
             })
         };
@@ -653,21 +931,47 @@ impl AccountPacket {
         Ok((folders_api, folder_change))
     }
 
-    // Creates an iterator over the user's dirs names, excluding the '.safe' tracking dir
-    fn iter_only_dirs(&self) -> impl Iterator<Item = DirEntry> {
+    // Creates an iterator over the user's files/dirs/symlinks, excluding the tracking files under '.safe' dir,
+    // as well as the items ignored by the patterns found in '.safeignore' files.
+    fn iter_files_dir(&self) -> impl Iterator<Item = DirEntry> {
+        let mut safe_ignore = SafeIgnore::new(&self.files_dir);
         WalkDir::new(&self.files_dir)
             .into_iter()
-            .filter_entry(|e| e.file_type().is_dir() && e.file_name() != SAFE_TRACKING_CHANGES_DIR)
+            .filter_entry(move |e| {
+                (e.file_type().is_file() || e.file_name() != SAFE_TRACKING_CHANGES_DIR)
+                    && !safe_ignore.is_ignored(e.path(), e.file_type().is_dir())
+            })
             .flatten()
     }
 
-    // Creates an iterator over the user's file, excluding the tracking files under '.safe' dir
+    // Creates an iterator over the user's dirs names, excluding the '.safe' tracking dir and ignored dirs
+    fn iter_only_dirs(&self) -> impl Iterator<Item = DirEntry> {
+        self.iter_files_dir().filter(|e| e.file_type().is_dir())
+    }
+
+    // Creates an iterator over the user's file, excluding the tracking files under '.safe' dir and ignored files
     fn iter_only_files(&self) -> impl Iterator<Item = DirEntry> {
-        WalkDir::new(&self.files_dir)
-            .into_iter()
-            .filter_entry(|e| e.file_type().is_file() || e.file_name() != SAFE_TRACKING_CHANGES_DIR)
-            .flatten()
-            .filter(|e| e.file_type().is_file())
+        self.iter_files_dir().filter(|e| e.file_type().is_file())
+    }
+
+    // Read the user's symlinks, along with the path they point to, without following them.
+    fn read_symlinks_from_disk(&self) -> BTreeMap<PathBuf, String> {
+        self.iter_files_dir()
+            .filter(|e| e.file_type().is_symlink())
+            .filter_map(|e| match std::fs::read_link(e.path()) {
+                Ok(target) => match target.to_str() {
+                    Some(target) => Some((e.path().to_path_buf(), target.to_string())),
+                    None => {
+                        println!("Skipping symlink {:?} with a non UTF-8 target", e.path());
+                        None
+                    }
+                },
+                Err(err) => {
+                    println!("Skipping symlink {:?}: {err}", e.path());
+                    None
+                }
+            })
+            .collect()
     }
 
     // Pay and upload all the files and folder.
@@ -760,6 +1064,7 @@ impl AccountPacket {
         retry_strategy: RetryStrategy,
     ) -> Result<Folders> {
         let mut files_to_download = vec![];
+        let mut attributes_to_apply = vec![];
         let mut updated_folders = Folders::new();
         while let Some((name, mut folders_api, target_path)) = folders_to_download.pop() {
             if updated_folders.contains_key(&target_path) {
@@ -776,6 +1081,7 @@ impl AccountPacket {
                 &mut folders_api,
                 &mut files_to_download,
                 &mut folders_to_download,
+                &mut attributes_to_apply,
             )
             .await?;
             updated_folders.insert(target_path, (folders_api, FolderChange::NoChange));
@@ -795,6 +1101,14 @@ impl AccountPacket {
             .await;
         }
 
+        // Attributes are applied once all files were downloaded, and to the items within a dir
+        // before the dir itself, as writing into a dir changes its modification time.
+        for (path, attributes) in attributes_to_apply.iter().rev() {
+            if let Err(err) = apply_file_attributes(path, attributes) {
+                println!("Failed to set the attributes of {path:?}: {err}");
+            }
+        }
+
         Ok(updated_folders)
     }
 
@@ -805,6 +1119,7 @@ impl AccountPacket {
         folders_api: &mut FoldersApi,
         files_to_download: &mut Vec<(OsString, Chunk, PathBuf)>,
         folders_to_download: &mut Vec<(OsString, FoldersApi, PathBuf)>,
+        attributes_to_apply: &mut Vec<(PathBuf, FileAttributes)>,
     ) -> Result<()> {
         for (entry_hash, (meta_xorname, metadata)) in folders_api.entries().await?.into_iter() {
             let name = metadata.name.clone();
@@ -814,6 +1129,22 @@ impl AccountPacket {
                     // thus we already have this same file/folder locally
                     continue;
                 }
+
+                if matches!(metadata.content, FolderEntry::File(_))
+                    && tracking_info.metadata.content == metadata.content
+                {
+                    // only the attributes changed, thus there is no need to download the file again
+                    let old_meta_xorname = tracking_info.meta_xorname;
+                    attributes_to_apply.push((item_path.clone(), metadata.attributes.clone()));
+                    self.store_tracking_info(MetadataTrackingInfo {
+                        file_path: item_path,
+                        meta_xorname,
+                        metadata,
+                        entry_hash,
+                    })?;
+                    self.remove_tracking_info(old_meta_xorname);
+                    continue;
+                }
             }
 
             match &metadata.content {
@@ -823,7 +1154,12 @@ impl AccountPacket {
                         data_map_chunk.clone(),
                         target_path.to_path_buf(),
                     ));
+                    // the existing file could be a symlink, or read-only, thus we recreate it
+                    if item_path.is_symlink() || item_path.is_file() {
+                        remove_file(&item_path)?;
+                    }
                     let _ = File::create(&item_path)?;
+                    attributes_to_apply.push((item_path.clone(), metadata.attributes.clone()));
                 }
                 FolderEntry::Folder(subfolder_addr) => {
                     let folders_api = FoldersApi::retrieve(
@@ -835,6 +1171,16 @@ impl AccountPacket {
 
                     folders_to_download.push((name.clone().into(), folders_api, item_path.clone()));
                     create_dir_all(&item_path)?;
+                    attributes_to_apply.push((item_path.clone(), metadata.attributes.clone()));
+                }
+                FolderEntry::Symlink(target) => {
+                    if !symlink_target_within_root(&self.files_dir, &item_path, target) {
+                        println!(
+                            "Skipping symlink {item_path:?} as it points outside of the folder: {target:?}"
+                        );
+                        continue;
+                    }
+                    create_symlink(&self.files_dir, &item_path, target)?;
                 }
             };
 
@@ -860,22 +1206,16 @@ fn remove_from_parent(folders: &mut Folders, path: &Path, entry_hash: EntryHash)
 }
 
 // Replace a file/folder item from a given Folder (passed in as a container's OccupiedEntry').
-// The metadata chunk of the new item (folder/file) will be encrpyted if a key has been provided.
+// The metadata chunk of the new item (folder/file/symlink) will be encrpyted if a key has been provided.
 fn replace_item_in_folder(
     folder: &mut OccupiedEntry<'_, PathBuf, (FoldersApi, FolderChange)>,
     entry_hash: EntryHash,
-    file_name: OsString,
-    data_map: Chunk,
+    metadata: Metadata,
     encryption_pk: Option<PublicKey>,
 ) -> Result<(EntryHash, XorName, Metadata)> {
     let (ref mut folders_api, ref mut folder_change) = folder.get_mut();
     folder_change.has_new_entries();
-    let res = folders_api.replace_file(
-        entry_hash,
-        file_name.clone(),
-        data_map.clone(),
-        encryption_pk,
-    )?;
+    let res = folders_api.replace_item(entry_hash, metadata, encryption_pk)?;
     Ok(res)
 }
 
@@ -924,8 +1264,8 @@ mod tests {
     };
 
     use super::{
-        read_root_folder_addr, read_tracking_info_from_disk, AccountPacket, Metadata,
-        MetadataTrackingInfo, Mutation, ACC_PACKET_ADDR_DERIVATION_INDEX,
+        conflict_file_path, read_conflicts, read_root_folder_addr, read_tracking_info_from_disk,
+        AccountPacket, Metadata, MetadataTrackingInfo, Mutation, ACC_PACKET_ADDR_DERIVATION_INDEX,
         ACC_PACKET_OWNER_DERIVATION_INDEX,
     };
     use rand::{thread_rng, Rng};
@@ -956,6 +1296,7 @@ mod tests {
             show_holders: false,
             max_repayments_for_failed_data: 1,
             collect_registers: false,
+            journal: None,
         };
         let make_data_public = false;
         (cfg, make_data_public)
@@ -994,10 +1335,10 @@ mod tests {
 
         let mut test_files = create_test_files_on_disk(&files_path)?;
         let mut rng = rand::thread_rng();
-        let dummy_metadata = Metadata {
-            name: "dummy".to_string(),
-            content: FolderEntry::File(Chunk::new(Bytes::new())),
-        };
+        let dummy_metadata = Metadata::new(
+            "dummy".to_string(),
+            FolderEntry::File(Chunk::new(Bytes::new())),
+        );
         for (relative_path, _) in test_files.iter() {
             let abs_path = files_path.join(relative_path);
 
@@ -1107,6 +1448,111 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_acc_packet_file_attributes() -> Result<()> {
+        use std::{
+            os::unix::fs::{symlink, PermissionsExt},
+            time::{Duration, UNIX_EPOCH},
+        };
+
+        let client = get_new_client(SecretKey::random()).await?;
+        let root_sk = MainSecretKey::random();
+
+        let tmp_dir = tempfile::tempdir()?;
+        let wallet_dir = tmp_dir.path();
+        let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+        let src_files_path = tmp_dir.path().join("myaccpacket");
+        let _ = create_test_files_on_disk(&src_files_path)?;
+        let modified = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        File::options()
+            .write(true)
+            .open(src_files_path.join("file0.txt"))?
+            .set_modified(modified)?;
+        std::fs::set_permissions(
+            src_files_path.join("file0.txt"),
+            std::fs::Permissions::from_mode(0o640),
+        )?;
+        symlink("file0.txt", src_files_path.join("link0"))?;
+        symlink("../outside.txt", src_files_path.join("escaping-link0"))?;
+        symlink("/etc/hostname", src_files_path.join("escaping-link1"))?;
+
+        let mut acc_packet =
+            AccountPacket::init(client.clone(), wallet_dir, &src_files_path, &root_sk, None)?;
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        let download_files_path = tmp_dir.path().join("myaccpacket-downloaded");
+        let mut downloaded_acc_packet = AccountPacket::retrieve_folders(
+            &client,
+            wallet_dir,
+            &root_sk,
+            None,
+            &download_files_path,
+            BATCH_SIZE,
+            RetryStrategy::Quick,
+        )
+        .await?;
+
+        let file_metadata = std::fs::metadata(download_files_path.join("file0.txt"))?;
+        assert_eq!(file_metadata.modified()?, modified);
+        assert_eq!(file_metadata.permissions().mode() & 0o7777, 0o640);
+        assert_eq!(
+            std::fs::read_link(download_files_path.join("link0"))?,
+            PathBuf::from("file0.txt")
+        );
+
+        let tracking_info = read_tracking_info_from_disk(&acc_packet.meta_dir)?;
+        let file_tracking_info = tracking_info
+            .get(Path::new("file0.txt"))
+            .ok_or_else(|| eyre!("Tracking info not found for file0.txt"))?;
+        assert_eq!(
+            file_tracking_info
+                .metadata
+                .attributes
+                .content_type
+                .as_deref(),
+            Some("text/plain")
+        );
+        assert_eq!(
+            file_tracking_info.metadata.attributes.size,
+            Some(file_metadata.len())
+        );
+        assert!(matches!(
+            &tracking_info
+                .get(Path::new("link0"))
+                .ok_or_else(|| eyre!("Tracking info not found for link0"))?
+                .metadata
+                .content,
+            FolderEntry::Symlink(target) if target == "file0.txt"
+        ));
+
+        // symlinks pointing outside of the folder are not created
+        assert!(std::fs::symlink_metadata(download_files_path.join("escaping-link0")).is_err());
+        assert!(std::fs::symlink_metadata(download_files_path.join("escaping-link1")).is_err());
+
+        // changing only the permissions of a file is synced too
+        std::fs::set_permissions(
+            src_files_path.join("file0.txt"),
+            std::fs::Permissions::from_mode(0o600),
+        )?;
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
+        assert_eq!(changes.mutations.len(), 1);
+        assert!(matches!(
+            &changes.mutations[0],
+            Mutation::FileAttributesChanged((_, tracking_info))
+                if tracking_info.file_path == src_files_path.join("file0.txt")
+        ));
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        downloaded_acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        let file_metadata = std::fs::metadata(download_files_path.join("file0.txt"))?;
+        assert_eq!(file_metadata.modified()?, modified);
+        assert_eq!(file_metadata.permissions().mode() & 0o7777, 0o600);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_acc_packet_scan_files_and_folders_changes() -> Result<()> {
         let client = get_new_client(SecretKey::random()).await?;
@@ -1123,7 +1569,7 @@ mod tests {
         let mut acc_packet =
             AccountPacket::init(client.clone(), wallet_dir, &files_path, &root_sk, None)?;
 
-        let changes = acc_packet.scan_files_and_folders_for_changes(false)?;
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
         // verify changes detected
         assert_eq!(changes.mutations.len(), 4);
         assert!(changes.mutations.iter().all(|mutation| {
@@ -1138,7 +1584,7 @@ mod tests {
         // let's make some mutations/changes
         mutate_test_files_on_disk(&files_path, &mut test_files)?;
 
-        let changes = acc_packet.scan_files_and_folders_for_changes(false)?;
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
         // verify new changes detected
         assert_eq!(changes.mutations.len(), 8);
         assert!(changes.mutations.iter().all(|mutation| {
@@ -1155,6 +1601,118 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_acc_packet_safeignore_scan_files_and_folders_changes() -> Result<()> {
+        let client = get_new_client(SecretKey::random()).await?;
+        let root_sk = MainSecretKey::random();
+
+        let tmp_dir = tempfile::tempdir()?;
+        let wallet_dir = tmp_dir.path();
+        let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+        let files_path = tmp_dir.path().join("myaccpacket-to-scan-ignoring");
+        let mut test_files = create_test_files_on_disk(&files_path)?;
+        create_test_ignored_files_on_disk(&files_path, &mut test_files)?;
+        let files_path = files_path.canonicalize()?;
+
+        let acc_packet =
+            AccountPacket::init(client.clone(), wallet_dir, &files_path, &root_sk, None)?;
+
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
+        // verify changes detected, none of them for the ignored files/dirs
+        assert_eq!(changes.mutations.len(), 7);
+        assert!(changes.mutations.iter().all(|mutation| {
+            matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join("file0.txt"))
+            || matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join("dir1").join("file1.txt"))
+            || matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join(".safeignore"))
+            || matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join("dir1").join(".safeignore"))
+            || matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join("dir1").join("keep.swp"))
+            || matches!(mutation, Mutation::NewFolder(i) if i.file_path == files_path.join("dir1"))
+            || matches!(mutation, Mutation::NewFolder(i) if i.file_path == files_path.join("dir2"))
+        }), "at least one of the mutations detected was unexpected/incorrect");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_acc_packet_safeignore_upload_download() -> Result<()> {
+        let client = get_new_client(SecretKey::random()).await?;
+        let root_sk = MainSecretKey::random();
+
+        let tmp_dir = tempfile::tempdir()?;
+        let wallet_dir = tmp_dir.path();
+        let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+        let src_files_path = tmp_dir.path().join("myaccpacket-ignoring");
+        let mut expected_files = create_test_files_on_disk(&src_files_path)?;
+        create_test_ignored_files_on_disk(&src_files_path, &mut expected_files)?;
+
+        let mut acc_packet =
+            AccountPacket::init(client.clone(), wallet_dir, &src_files_path, &root_sk, None)?;
+
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        let download_files_path = tmp_dir.path().join("myaccpacket-ignoring-downloaded");
+
+        let downloaded_acc_packet = AccountPacket::retrieve_folders(
+            &client,
+            wallet_dir,
+            &root_sk,
+            None,
+            &download_files_path,
+            BATCH_SIZE,
+            RetryStrategy::Quick,
+        )
+        .await?;
+
+        // the ignored files/dirs were not uploaded, thus not downloaded either
+        assert!(!download_files_path.join("file0.txt.swp").exists());
+        assert!(!download_files_path.join("build").exists());
+
+        check_files_and_dirs_match(&acc_packet, &downloaded_acc_packet, expected_files.clone())?;
+        check_tracking_info_match(&acc_packet, &downloaded_acc_packet, expected_files)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_acc_packet_safeignore_tracked_file() -> Result<()> {
+        let client = get_new_client(SecretKey::random()).await?;
+        let root_sk = MainSecretKey::random();
+
+        let tmp_dir = tempfile::tempdir()?;
+        let wallet_dir = tmp_dir.path();
+        let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+        let files_path = tmp_dir.path().join("myaccpacket-ignoring-tracked");
+        let _ = create_test_files_on_disk(&files_path)?;
+        let files_path = files_path.canonicalize()?;
+
+        let mut acc_packet =
+            AccountPacket::init(client.clone(), wallet_dir, &files_path, &root_sk, None)?;
+
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        // let's now ignore a file which was already synced
+        let mut safeignore = File::create(files_path.join(".safeignore"))?;
+        safeignore.write_all(b"file0.txt\n")?;
+
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
+        // it's removed from the network, but kept on disk
+        assert_eq!(changes.mutations.len(), 2);
+        assert!(changes.mutations.iter().all(|mutation| {
+            matches!(mutation, Mutation::FileRemoved((p, _)) if p == &files_path.join("file0.txt"))
+            || matches!(mutation, Mutation::NewFile(i) if i.file_path == files_path.join(".safeignore"))
+        }), "at least one of the mutations detected was unexpected/incorrect");
+
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        assert!(files_path.join("file0.txt").exists());
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
+        assert!(changes.mutations.is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_acc_packet_sync_mutations() -> Result<()> {
         let client = get_new_client(SecretKey::random()).await?;
@@ -1201,6 +1759,105 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_acc_packet_sync_conflicts() -> Result<()> {
+        let client = get_new_client(SecretKey::random()).await?;
+        let root_sk = MainSecretKey::random();
+
+        let tmp_dir = tempfile::tempdir()?;
+        let wallet_dir = tmp_dir.path();
+        let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+        let src_files_path = tmp_dir.path().join("myaccpacketconflicts");
+        let mut expected_files = create_test_files_on_disk(&src_files_path)?;
+
+        let mut acc_packet =
+            AccountPacket::init(client.clone(), wallet_dir, &src_files_path, &root_sk, None)?;
+
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        let clone_files_path = tmp_dir.path().join("myaccpacketconflicts-clone");
+        let mut cloned_acc_packet = AccountPacket::retrieve_folders(
+            &client,
+            wallet_dir,
+            &root_sk,
+            None,
+            &clone_files_path,
+            BATCH_SIZE,
+            RetryStrategy::Quick,
+        )
+        .await?;
+
+        // let's modify the same file in both the original and the cloned packets
+        let file2modify = Path::new("file0.txt");
+        let local_chunk = random_file_chunk();
+        OpenOptions::new()
+            .write(true)
+            .open(src_files_path.join(file2modify))?
+            .write_all(local_chunk.value())?;
+        let remote_chunk = random_file_chunk();
+        OpenOptions::new()
+            .write(true)
+            .open(clone_files_path.join(file2modify))?
+            .write_all(remote_chunk.value())?;
+
+        cloned_acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        // the original packet pulls the remote version, keeping the local one aside
+        let conflict_path = conflict_file_path(&src_files_path.join(file2modify));
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+
+        let conflict_file = acc_packet.get_relative_path(&conflict_path)?;
+        assert_eq!(
+            read_conflicts(&acc_packet.tracking_info_dir)?,
+            BTreeMap::from([(conflict_file.clone(), file2modify.to_path_buf())])
+        );
+        expected_files.insert(file2modify.to_path_buf(), Some(remote_chunk));
+        expected_files.insert(conflict_file, Some(local_chunk));
+
+        // and the clone gets both versions once synced
+        cloned_acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        check_files_and_dirs_match(&acc_packet, &cloned_acc_packet, expected_files.clone())?;
+        check_tracking_info_match(&acc_packet, &cloned_acc_packet, expected_files)?;
+
+        // removing the local version kept resolves the conflict
+        remove_file(&conflict_path)?;
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        assert!(read_conflicts(&acc_packet.tracking_info_dir)?.is_empty());
+
+        // a file modified locally but removed remotely is a conflict too
+        let file2remove = Path::new("dir1").join("file1.txt");
+        remove_file(clone_files_path.join(&file2remove))?;
+        cloned_acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        let local_chunk = random_file_chunk();
+        OpenOptions::new()
+            .write(true)
+            .open(src_files_path.join(&file2remove))?
+            .write_all(local_chunk.value())?;
+
+        let changed_remotely = acc_packet.find_files_changed_remotely().await?;
+        let changes = acc_packet.scan_files_and_folders_for_changes(false, &changed_remotely)?;
+        assert_eq!(changes.mutations.len(), 1);
+        assert!(matches!(
+            &changes.mutations[0],
+            Mutation::FileConflict((path, _)) if path == &src_files_path.join(&file2remove)
+        ));
+
+        // the removal is pulled, keeping the local version aside
+        let conflict_path = conflict_file_path(&src_files_path.join(&file2remove));
+        acc_packet.sync(SYNC_OPTS.0, SYNC_OPTS.1).await?;
+        assert!(!src_files_path.join(&file2remove).exists());
+        let mut content = vec![];
+        File::open(&conflict_path)?.read_to_end(&mut content)?;
+        assert_eq!(content.as_slice(), local_chunk.value().as_ref());
+        assert_eq!(
+            read_conflicts(&acc_packet.tracking_info_dir)?,
+            BTreeMap::from([(acc_packet.get_relative_path(&conflict_path)?, file2remove)])
+        );
+
+        Ok(())
+    }
+
     // Acc-packets can be moved to different locations on local disk without affecting their tracking info.
     // We disable this test for Windows since in CI the use of std::fs::rename gives a permissions issue.
     #[cfg(any(target_os = "linux", target_os = "linux"))]
@@ -1240,7 +1897,8 @@ mod tests {
             AccountPacket::from_path(client.clone(), wallet_dir, &moved_files_path, None)?;
 
         // verify only one change is detected still after moved to another location on disk
-        let changes = moved_acc_packet.scan_files_and_folders_for_changes(false)?;
+        let changes =
+            moved_acc_packet.scan_files_and_folders_for_changes(false, &BTreeSet::new())?;
         assert_eq!(changes.mutations.len(), 1);
         assert_eq!(changes.mutations.first().map(|mutation| {
             matches!(mutation, Mutation::FileContentChanged((_,i)) if i.file_path == moved_files_path.join("dir1").join("file1.txt"))
@@ -1396,6 +2054,42 @@ mod tests {
         Ok(files)
     }
 
+    // Create a hard-coded set of '.safeignore' files, along with files and dirs to be ignored, on disk.
+    // The '.safeignore' files, and any file re-included by them, are added to the list of expected files.
+    fn create_test_ignored_files_on_disk(
+        base_path: &Path,
+        test_files: &mut BTreeMap<PathBuf, Option<Chunk>>,
+    ) -> Result<()> {
+        let safeignore_files = [
+            (Path::new(".safeignore").to_path_buf(), "*.swp\nbuild/\n"),
+            (Path::new("dir1").join(".safeignore"), "!keep.swp\n"),
+        ];
+        for (path, patterns) in safeignore_files {
+            let mut file = File::create(base_path.join(&path))?;
+            file.write_all(patterns.as_bytes())?;
+            test_files.insert(path, Some(Chunk::new(Bytes::from(patterns))));
+        }
+
+        let ignored_files = [
+            Path::new("file0.txt.swp").to_path_buf(),
+            Path::new("build").join("output.bin"),
+        ];
+        for path in ignored_files {
+            let full_path = base_path.join(path);
+            create_dir_all(full_path.parent().expect("invalid path for test file"))?;
+            let mut file = File::create(full_path)?;
+            file.write_all(random_file_chunk().value())?;
+        }
+
+        let chunk = random_file_chunk();
+        let reincluded_file = Path::new("dir1").join("keep.swp");
+        let mut file = File::create(base_path.join(&reincluded_file))?;
+        file.write_all(chunk.value())?;
+        test_files.insert(reincluded_file, Some(chunk));
+
+        Ok(())
+    }
+
     // Apply a hard-coded set of mutations to test files and dirs on disk
     fn mutate_test_files_on_disk(
         path: &Path,
@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use sn_client::{
-    protocol::storage::RegisterAddress, registers::EntryHash, transfers::MainSecretKey, FoldersApi,
-    Metadata,
+    protocol::storage::RegisterAddress, registers::EntryHash, transfers::MainSecretKey,
+    FileAttributes, FoldersApi, Metadata,
 };
 
 use aes::Aes256;
@@ -23,9 +23,10 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt,
-    fs::{create_dir_all, File},
+    fs::{create_dir_all, File, OpenOptions},
     io::Write,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
 };
 use tiny_keccak::{Hasher, Sha3};
 use walkdir::WalkDir;
@@ -49,6 +50,9 @@ pub(super) const METADATA_CACHE_DIR: &str = "metadata";
 // Name of the file where metadata about root folder is locally cached.
 pub(super) const ROOT_FOLDER_METADATA_FILENAME: &str = "root_folder.addr";
 
+// Name of the file where the conflicting versions of files kept when syncing are recorded.
+pub(super) const CONFLICTS_FILENAME: &str = "conflicts";
+
 // Name of the file where the recovery secret/seed is locally cached.
 pub(crate) const RECOVERY_SEED_FILENAME: &str = "recovery_seed";
 
@@ -93,6 +97,10 @@ pub(super) enum Mutation {
     FileContentChanged((XorName, MetadataTrackingInfo)),
     NewFolder(MetadataTrackingInfo),
     FolderRemoved((PathBuf, XorName)),
+    // File changed both locally and remotely, the local version is kept as a new file.
+    FileConflict((PathBuf, MetadataTrackingInfo)),
+    // Only the file's attributes changed, its content is still the same.
+    FileAttributesChanged((XorName, MetadataTrackingInfo)),
 }
 
 impl fmt::Display for Mutation {
@@ -109,6 +117,14 @@ impl fmt::Display for Mutation {
                 write!(f, "New folder: {:?}", tracking_info.file_path)
             }
             Self::FolderRemoved((path, _)) => write!(f, "Folder removed: {path:?}"),
+            Self::FileConflict((path, tracking_info)) => write!(
+                f,
+                "File changed both locally and remotely: {path:?}, local version kept at {:?}",
+                tracking_info.file_path
+            ),
+            Self::FileAttributesChanged((_, tracking_info)) => {
+                write!(f, "File attributes changed: {:?}", tracking_info.file_path)
+            }
         }
     }
 }
@@ -156,6 +172,61 @@ pub(super) fn read_tracking_info_from_disk(
     Ok(curr_tracking_info)
 }
 
+// Read the conflicting versions of files kept when syncing, mapping the relative path of each
+// local version kept to the relative path of the file it conflicted with.
+pub(super) fn read_conflicts(tracking_info_dir: &Path) -> Result<BTreeMap<PathBuf, PathBuf>> {
+    let path = tracking_info_dir.join(CONFLICTS_FILENAME);
+    match std::fs::read(&path) {
+        Ok(bytes) => rmp_serde::from_slice(&bytes)
+            .map_err(|err| eyre!("Error while deserializing conflicts from {path:?}: {err}")),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(err) => bail!("Error while reading conflicts from {path:?}: {err}"),
+    }
+}
+
+// Store the conflicting versions of files kept when syncing
+pub(super) fn store_conflicts(
+    tracking_info_dir: &Path,
+    conflicts: &BTreeMap<PathBuf, PathBuf>,
+) -> Result<()> {
+    let path = tracking_info_dir.join(CONFLICTS_FILENAME);
+    let mut conflicts_file = File::create(path)?;
+    conflicts_file.write_all(&rmp_serde::to_vec(conflicts)?)?;
+
+    Ok(())
+}
+
+// Path to keep the local version of a file at when it was also changed remotely, named after the
+// file and the local host, e.g. `notes.conflict-<host>.txt` for `notes.txt`.
+pub(super) fn conflict_file_path(path: &Path) -> PathBuf {
+    let host = hostname::get()
+        .ok()
+        .and_then(|host| host.into_string().ok())
+        .unwrap_or_else(|| "unknown".to_string());
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    // a previous conflict of the same file could still be around
+    let mut suffix = String::new();
+    let mut count = 1;
+    loop {
+        let conflict_path =
+            path.with_file_name(format!("{stem}.conflict-{host}{suffix}{extension}"));
+        if std::fs::symlink_metadata(&conflict_path).is_err() {
+            return conflict_path;
+        }
+        count += 1;
+        suffix = format!("-{count}");
+    }
+}
+
 // Store tracking info about the root folder in a file to keep track of any changes made
 pub(super) fn store_root_folder_tracking_info(
     meta_dir: &Path,
@@ -263,3 +334,237 @@ pub(super) fn read_root_folder_addr(meta_dir: &Path) -> Result<(RegisterAddress,
 
     Ok(rmp_serde::from_slice(&bytes)?)
 }
+
+// Read the attributes of a file/dir/symlink from disk, without following symlinks.
+pub(super) fn read_file_attributes(path: &Path) -> Result<FileAttributes> {
+    let metadata = std::fs::symlink_metadata(path)?;
+
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o7777)
+    };
+    #[cfg(not(unix))]
+    let mode = None;
+
+    let (size, content_type) = if metadata.is_file() {
+        let content_type = mime_guess::from_path(path)
+            .first()
+            .map(|mime| mime.essence_str().to_string());
+        (Some(metadata.len()), content_type)
+    } else {
+        (None, None)
+    };
+
+    Ok(FileAttributes {
+        size,
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok()),
+        mode,
+        content_type,
+    })
+}
+
+// Set the modification time and permissions of a downloaded file/dir as they were stored.
+pub(super) fn apply_file_attributes(path: &Path, attributes: &FileAttributes) -> Result<()> {
+    if let Some(modified) = attributes.modified {
+        let file = if path.is_dir() {
+            File::open(path)?
+        } else {
+            OpenOptions::new().write(true).open(path)?
+        };
+        file.set_modified(UNIX_EPOCH + modified)?;
+    }
+
+    // permissions are set last, as they could make the file read-only
+    #[cfg(unix)]
+    if let Some(mode) = attributes.mode {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok(())
+}
+
+// Whether the attributes worth syncing differ, i.e. permissions and modification time.
+// The modification time is compared in whole seconds, as not all filesystems keep sub-second precision.
+pub(super) fn attributes_changed(stored: &FileAttributes, current: &FileAttributes) -> bool {
+    stored.mode != current.mode
+        || stored.modified.map(|t| t.as_secs()) != current.modified.map(|t| t.as_secs())
+}
+
+// Whether a symlink at the given path, pointing to the given target, resolves to a path within
+// the root dir. The target is resolved lexically, thus absolute targets are never within the root.
+// As `..` after a symlink steps out of wherever the symlink points to, a target going back up
+// out of one of its own components is only accepted if that component is a real directory
+// already on disk, i.e. not a symlink, nor anything which could still become one.
+pub(super) fn symlink_target_within_root(root: &Path, link_path: &Path, target: &str) -> bool {
+    let Some(parent) = link_path.parent() else {
+        return false;
+    };
+    let Some(mut depth) = parent
+        .strip_prefix(root)
+        .ok()
+        .map(|parent| parent.components().count())
+    else {
+        return false;
+    };
+
+    let mut resolved = parent.to_path_buf();
+    // number of components of the target currently at the end of the resolved path
+    let mut target_depth = 0;
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(name) => {
+                resolved.push(name);
+                depth += 1;
+                target_depth += 1;
+            }
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => {
+                if target_depth > 0 {
+                    let is_real_dir = std::fs::symlink_metadata(&resolved)
+                        .map(|metadata| metadata.is_dir())
+                        .unwrap_or(false);
+                    if !is_real_dir {
+                        return false;
+                    }
+                    target_depth -= 1;
+                }
+                let _ = resolved.pop();
+                depth -= 1;
+            }
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+
+    true
+}
+
+// Create a symlink pointing to the given target, replacing any file found at its path.
+// Targets which resolve to a path outside of the root dir are rejected.
+pub(super) fn create_symlink(root: &Path, path: &Path, target: &str) -> Result<()> {
+    if !symlink_target_within_root(root, path, target) {
+        bail!("Symlink {path:?} points outside of {root:?}: {target:?}");
+    }
+
+    if std::fs::symlink_metadata(path).is_ok() {
+        std::fs::remove_file(path)?;
+    }
+
+    #[cfg(unix)]
+    return Ok(std::os::unix::fs::symlink(target, path)?);
+    #[cfg(not(unix))]
+    bail!("Symlink {path:?} pointing to {target:?} cannot be created on this platform");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{attributes_changed, symlink_target_within_root};
+    use eyre::Result;
+    use sn_client::FileAttributes;
+    use std::{fs::create_dir_all, path::Path, time::Duration};
+
+    #[test]
+    fn symlink_targets_escaping_the_root_are_detected() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let root = &tmp_dir.path().join("accpacket");
+        let link = root.join("dir").join("link");
+        create_dir_all(root.join("dir").join("sub"))?;
+
+        assert!(symlink_target_within_root(root, &link, "file.txt"));
+        assert!(symlink_target_within_root(root, &link, "./sub/../file.txt"));
+        assert!(symlink_target_within_root(root, &link, "../file.txt"));
+        assert!(symlink_target_within_root(
+            root,
+            &link,
+            "../dir/../other/file.txt"
+        ));
+
+        assert!(!symlink_target_within_root(root, &link, "../../file.txt"));
+        assert!(!symlink_target_within_root(
+            root,
+            &link,
+            "../../accpacket/file.txt"
+        ));
+        assert!(!symlink_target_within_root(
+            root,
+            &link,
+            "sub/../../../file.txt"
+        ));
+        assert!(!symlink_target_within_root(root, &link, "/etc/passwd"));
+        assert!(!symlink_target_within_root(
+            root,
+            &link,
+            &root.join("file.txt").to_string_lossy()
+        ));
+        assert!(!symlink_target_within_root(
+            root,
+            Path::new("/elsewhere/link"),
+            "file.txt"
+        ));
+
+        // going back up out of something not known to be a real directory
+        assert!(!symlink_target_within_root(
+            root,
+            &link,
+            "missing/../file.txt"
+        ));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_targets_escaping_the_root_through_other_symlinks_are_detected() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let root = &tmp_dir.path().join("accpacket");
+        create_dir_all(root)?;
+
+        // `d -> .` stays within the root, but then `d/..` is the parent of the root
+        assert!(symlink_target_within_root(root, &root.join("d"), "."));
+        std::os::unix::fs::symlink(".", root.join("d"))?;
+        assert!(!symlink_target_within_root(root, &root.join("e"), "d/.."));
+        assert!(!symlink_target_within_root(
+            root,
+            &root.join("e"),
+            "d/../file.txt"
+        ));
+        // whereas going down through it is fine
+        assert!(symlink_target_within_root(
+            root,
+            &root.join("e"),
+            "d/file.txt"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn attribute_changes_ignore_sub_second_precision() {
+        let stored = FileAttributes {
+            size: Some(10),
+            modified: Some(Duration::new(1_600_000_000, 123)),
+            mode: Some(0o640),
+            content_type: None,
+        };
+
+        let current = FileAttributes {
+            modified: Some(Duration::from_secs(1_600_000_000)),
+            ..stored.clone()
+        };
+        assert!(!attributes_changed(&stored, &current));
+
+        let current = FileAttributes {
+            mode: Some(0o600),
+            ..stored.clone()
+        };
+        assert!(attributes_changed(&stored, &current));
+
+        let current = FileAttributes {
+            modified: Some(Duration::from_secs(1_600_000_001)),
+            ..stored.clone()
+        };
+        assert!(attributes_changed(&stored, &current));
+    }
+}
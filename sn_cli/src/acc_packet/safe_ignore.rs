@@ -0,0 +1,112 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use ignore::{gitignore::Gitignore, Match};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Name of the files holding the patterns of the files/dirs not to be synced with the network.
+pub(super) const SAFE_IGNORE_FILENAME: &str = ".safeignore";
+
+/// Patterns of files/dirs to be ignored, read from the `.safeignore` files found in the root dir
+/// of an account packet and its subdirs, with the same semantics as `.gitignore` files: patterns
+/// are relative to the dir of the file they are in, and the patterns in a subdir take precedence
+/// over the ones of its parent dirs.
+pub(super) struct SafeIgnore {
+    root_dir: PathBuf,
+    // The patterns read from each dir, loaded the first time a path within the dir is checked.
+    matchers: BTreeMap<PathBuf, Option<Gitignore>>,
+}
+
+impl SafeIgnore {
+    pub(super) fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            matchers: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if the path is ignored by the patterns of its parent dirs.
+    /// The items within an ignored dir are expected to be skipped by the caller, as they cannot be
+    /// re-included by a pattern once their dir is ignored.
+    pub(super) fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let parent_dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root_dir))
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+
+        // the closest dir with a pattern matching the path decides whether it's ignored or not
+        for dir in parent_dirs {
+            if let Some(matcher) = self.matcher(dir) {
+                match matcher.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+        false
+    }
+
+    // Load the patterns of the given dir, if it has a `.safeignore` file.
+    fn matcher(&mut self, dir: PathBuf) -> Option<&Gitignore> {
+        self.matchers
+            .entry(dir)
+            .or_insert_with_key(|dir| {
+                let path = dir.join(SAFE_IGNORE_FILENAME);
+                if !path.is_file() {
+                    return None;
+                }
+
+                let (matcher, err) = Gitignore::new(&path);
+                if let Some(err) = err {
+                    println!(
+                        "Some of the patterns in {path:?} are invalid and were skipped: {err}"
+                    );
+                }
+                Some(matcher)
+            })
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn patterns_of_subdirs_take_precedence() -> Result<()> {
+        let tmp_dir = tempfile::tempdir()?;
+        let root = tmp_dir.path();
+        create_dir_all(root.join("src").join("generated"))?;
+        write(root.join(SAFE_IGNORE_FILENAME), "target/\n*.swp\n*.log\n")?;
+        write(
+            root.join("src").join(SAFE_IGNORE_FILENAME),
+            "!keep.log\ngenerated\n",
+        )?;
+
+        let mut safe_ignore = SafeIgnore::new(root);
+        assert!(safe_ignore.is_ignored(&root.join("target"), true));
+        assert!(!safe_ignore.is_ignored(&root.join("target"), false));
+        assert!(safe_ignore.is_ignored(&root.join("notes.txt.swp"), false));
+        assert!(safe_ignore.is_ignored(&root.join("src").join("main.rs.swp"), false));
+        assert!(safe_ignore.is_ignored(&root.join("src").join("build.log"), false));
+        assert!(!safe_ignore.is_ignored(&root.join("src").join("keep.log"), false));
+        assert!(safe_ignore.is_ignored(&root.join("src").join("generated"), true));
+        assert!(!safe_ignore.is_ignored(&root.join("generated"), true));
+        assert!(!safe_ignore.is_ignored(&root.join("src").join("main.rs"), false));
+        assert!(!safe_ignore.is_ignored(&root.join(SAFE_IGNORE_FILENAME), false));
+        Ok(())
+    }
+}
@@ -7,9 +7,10 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use autonomi::{
-    download_file, download_files, ChunkManager, Estimator, FilesUploader, UploadedFile,
-    UPLOADED_FILES,
+    download_file, download_files, verify_file, ChunkManager, Estimator, FilesUploader,
+    UploadedFile, UPLOADED_FILES,
 };
+use bls::PublicKey;
 use clap::Parser;
 use color_eyre::{
     eyre::{bail, eyre},
@@ -56,6 +57,9 @@ pub enum FilesCmds {
         /// to 'persistent' (most effort).
         #[clap(long, default_value_t = RetryStrategy::Balanced, short = 'r', help = "Sets the retry strategy on upload failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
         retry_strategy: RetryStrategy,
+        /// Resume the interrupted upload of the same path, without paying again for what was already paid for.
+        #[clap(long, name = "resume", default_value = "false")]
+        resume: bool,
     },
     Download {
         /// The name to apply to the downloaded file.
@@ -86,6 +90,53 @@ pub enum FilesCmds {
         #[clap(long, default_value_t = RetryStrategy::Quick, short = 'r', help = "Sets the retry strategy on download failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
         retry_strategy: RetryStrategy,
     },
+    /// Verify that the chunks of an uploaded file are held by the network.
+    Verify {
+        /// The hex address of a file.
+        #[clap(name = "address")]
+        file_addr: String,
+        /// Only ask the close group of each chunk for the metadata of their copies,
+        /// rather than fetching the chunks.
+        #[clap(long, name = "metadata_only", default_value = "false")]
+        metadata_only: bool,
+        /// The batch_size for parallel verification
+        #[clap(long, default_value_t = BATCH_SIZE , short='b')]
+        batch_size: usize,
+        /// Set the strategy to use on chunk fetch failure.
+        ///
+        /// Choose a retry strategy based on effort level, from 'quick' (least effort), through 'balanced',
+        /// to 'persistent' (most effort).
+        #[clap(long, default_value_t = RetryStrategy::Quick, short = 'r', help = "Sets the retry strategy on chunk fetch failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
+        retry_strategy: RetryStrategy,
+    },
+    /// Share a privately uploaded file with someone, by storing its data map encrypted to their public key.
+    ///
+    /// Prints the address of the share, which the recipient can then use with 'files receive'.
+    Share {
+        /// The hex address of the privately uploaded file.
+        #[clap(name = "address")]
+        file_addr: String,
+        /// The hex-encoded public key of the recipient, as printed by 'files receive' when no address is given.
+        #[clap(long, name = "to")]
+        recipient: String,
+    },
+    /// Download a file shared with us through 'files share'.
+    Receive {
+        /// The hex address of the share.
+        ///
+        /// If not provided, the public key to share files with us to is printed instead.
+        #[clap(name = "address")]
+        share_addr: Option<String>,
+        /// The batch_size for parallel downloading
+        #[clap(long, default_value_t = BATCH_SIZE , short='b')]
+        batch_size: usize,
+        /// Set the strategy to use on downloads failure.
+        ///
+        /// Choose a retry strategy based on effort level, from 'quick' (least effort), through 'balanced',
+        /// to 'persistent' (most effort).
+        #[clap(long, default_value_t = RetryStrategy::Quick, short = 'r', help = "Sets the retry strategy on download failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
+        retry_strategy: RetryStrategy,
+    },
 }
 
 pub(crate) async fn files_cmds(
@@ -110,6 +161,7 @@ pub(crate) async fn files_cmds(
             batch_size,
             retry_strategy,
             make_data_public,
+            resume,
         } => {
             let files_count = count_files_in_path_recursively(&file_path);
 
@@ -123,14 +175,21 @@ pub(crate) async fn files_cmds(
                     bail!("The provided file path is invalid. Please verify the path.");
                 }
             }
+            // Journaled under the uploaded path, for `--resume` to pick up the interrupted upload
+            // of that path, without clashing with the uploads of other paths.
+            let journaled_path = file_path.canonicalize().unwrap_or(file_path.clone());
             let upload_cfg = UploadCfg {
                 batch_size,
                 verify_store,
                 retry_strategy,
+                journal: Some(XorName::from_content(
+                    journaled_path.to_string_lossy().as_bytes(),
+                )),
                 ..Default::default()
             };
             let files_uploader = FilesUploader::new(client.clone(), root_dir.to_path_buf())
                 .set_make_data_public(make_data_public)
+                .set_resume(resume)
                 .set_upload_cfg(upload_cfg)
                 .insert_path(&file_path);
 
@@ -245,10 +304,97 @@ pub(crate) async fn files_cmds(
                 }
             }
         }
+        FilesCmds::Verify {
+            file_addr,
+            metadata_only,
+            batch_size,
+            retry_strategy,
+        } => {
+            let xor_name = parse_xor_name(&file_addr)?;
+            let files_api = FilesApi::new(client.clone(), root_dir.to_path_buf());
+            verify_file(
+                files_api,
+                xor_name,
+                metadata_only,
+                batch_size,
+                retry_strategy,
+            )
+            .await?
+        }
+        FilesCmds::Share {
+            file_addr,
+            recipient,
+        } => {
+            let xor_name = parse_xor_name(&file_addr)?;
+            let recipient = PublicKey::from_hex(&recipient)
+                .map_err(|err| eyre!("Failed to parse hex-encoded PK: {err:?}"))?;
+
+            let uploaded_file_path = root_dir.join(UPLOADED_FILES).join(&file_addr);
+            if !uploaded_file_path.exists() {
+                bail!("The file at {file_addr} was not uploaded from this client.");
+            }
+            let uploaded_file = UploadedFile::read(&uploaded_file_path)?;
+            let Some(data_map) = uploaded_file.data_map else {
+                bail!("The file at {file_addr} was uploaded publicly, its address can be shared as it is.");
+            };
+            let data_map = Chunk {
+                address: ChunkAddress::new(xor_name),
+                value: data_map,
+            };
+
+            let files_api = FilesApi::build(client.clone(), root_dir.to_path_buf())?;
+            let share_address = files_api
+                .share_file(uploaded_file.filename, data_map, recipient, verify_store)
+                .await?;
+            println!(
+                "Shared {file_addr} at {}, which only the holder of {} can receive.",
+                share_address.to_hex(),
+                recipient.to_hex()
+            );
+        }
+        FilesCmds::Receive {
+            share_addr,
+            batch_size,
+            retry_strategy,
+        } => {
+            let Some(share_addr) = share_addr else {
+                println!(
+                    "Files can be shared with us to the public key: {}",
+                    client.signer_pk().to_hex()
+                );
+                return Ok(());
+            };
+            let xor_name = parse_xor_name(&share_addr)?;
+
+            let files_api = FilesApi::new(client.clone(), root_dir.to_path_buf());
+            let shared_file = files_api
+                .receive_shared_file(ChunkAddress::new(xor_name))
+                .await?;
+            let download_dir = std::env::current_dir().unwrap_or(root_dir.to_path_buf());
+
+            download_file(
+                files_api,
+                *shared_file.address().xorname(),
+                (shared_file.file_name(), Some(shared_file.data_map)),
+                &download_dir,
+                false,
+                batch_size,
+                retry_strategy,
+            )
+            .await
+        }
     }
     Ok(())
 }
 
+fn parse_xor_name(hex_address: &str) -> Result<XorName> {
+    let bytes = hex::decode(hex_address)
+        .map_err(|err| eyre!("Input address is not a hex string: {err}"))?;
+    Ok(XorName(bytes.try_into().map_err(|_| {
+        eyre!("Failed to parse XorName from hex string")
+    })?))
+}
+
 fn count_files_in_path_recursively(file_path: &PathBuf) -> u32 {
     let entries_iterator = WalkDir::new(file_path).into_iter().flatten();
     let mut count = 0;
@@ -9,7 +9,8 @@
 use autonomi::AccountPacket;
 
 use sn_client::{
-    protocol::storage::RetryStrategy, transfers::MainSecretKey, Client, UploadCfg, BATCH_SIZE,
+    protocol::storage::RetryStrategy, registers::EntryHash, transfers::MainSecretKey, Client,
+    FolderItemDiff, UploadCfg, BATCH_SIZE,
 };
 
 use bls::{SecretKey, SK_SIZE};
@@ -75,6 +76,40 @@ pub enum FoldersCmds {
         #[clap(long, default_value_t = RetryStrategy::Balanced, short = 'r', help = "Sets the retry strategy on upload failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
         retry_strategy: RetryStrategy,
     },
+    /// List the versions of a Folder stored on the network, each item's version listed after the ones it replaced.
+    History {
+        /// Path of the directory whose Folder versions to list. By default the current path is assumed.
+        #[clap(name = "path", value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+    /// Report the differences between two versions of a Folder stored on the network.
+    Diff {
+        /// The hex-encoded version (entry hash) to compare from, as listed by the 'history' command.
+        #[clap(name = "from")]
+        from: String,
+        /// The hex-encoded version (entry hash) to compare to, as listed by the 'history' command.
+        #[clap(name = "to")]
+        to: String,
+        /// Path of the directory whose Folder versions to compare. By default the current path is assumed.
+        #[clap(name = "path", value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+    /// Restore a file/folder to an older version of it, in the Folder stored on the network.
+    /// Run the 'sync' command afterwards to bring the restored version onto the local directory.
+    Restore {
+        /// The hex-encoded version (entry hash) to restore, as listed by the 'history' command.
+        #[clap(name = "version")]
+        version: String,
+        /// Path of the directory whose Folder holds the file/folder. By default the current path is assumed.
+        #[clap(name = "path", value_name = "PATH")]
+        path: Option<PathBuf>,
+        /// Set the strategy to use on upload failure.
+        ///
+        /// Choose a retry strategy based on effort level, from 'quick' (least effort), through 'balanced',
+        /// to 'persistent' (most effort).
+        #[clap(long, default_value_t = RetryStrategy::Balanced, short = 'r', help = "Sets the retry strategy on upload failure. Options: 'quick' for minimal effort, 'balanced' for moderate effort, or 'persistent' for maximum effort.")]
+        retry_strategy: RetryStrategy,
+    },
 }
 
 pub(crate) async fn folders_cmds(
@@ -122,7 +157,7 @@ pub(crate) async fn folders_cmds(
         FoldersCmds::Status { path } => {
             let path = get_path(path, None)?;
             let acc_packet = AccountPacket::from_path(client.clone(), root_dir, &path, None)?;
-            acc_packet.status()?;
+            acc_packet.status().await?;
         }
         FoldersCmds::Sync {
             path,
@@ -141,10 +176,83 @@ pub(crate) async fn folders_cmds(
             };
             acc_packet.sync(options, make_data_public).await?;
         }
+        FoldersCmds::History { path } => {
+            let path = get_path(path, None)?;
+            let acc_packet = AccountPacket::from_path(client.clone(), root_dir, &path, None)?;
+            let history = acc_packet.folder_history(&path).await?;
+
+            for version in history.iter() {
+                let (change, name) = match &version.item {
+                    Some((_, metadata)) if version.previous.is_empty() => ("added", &metadata.name),
+                    Some((_, metadata)) => ("replaced", &metadata.name),
+                    None => {
+                        // the name of a removed item is the one of the versions it was removed from
+                        let name = history
+                            .iter()
+                            .filter(|v| version.previous.contains(&v.entry_hash))
+                            .find_map(|v| v.item.as_ref().map(|(_, metadata)| &metadata.name));
+                        match name {
+                            Some(name) => ("removed", name),
+                            None => continue,
+                        }
+                    }
+                };
+                println!("{}  {change:<8}  {name}", hex::encode(version.entry_hash.0));
+            }
+            println!("\nVersions found: {}", history.len());
+        }
+        FoldersCmds::Diff { from, to, path } => {
+            let from = parse_entry_hash(&from)?;
+            let to = parse_entry_hash(&to)?;
+            let path = get_path(path, None)?;
+            let acc_packet = AccountPacket::from_path(client.clone(), root_dir, &path, None)?;
+            let diff = acc_packet.folder_diff(&path, from, to).await?;
+
+            if diff.is_empty() {
+                println!("No differences found between the two versions.");
+            }
+            for (name, item_diff) in diff {
+                match item_diff {
+                    FolderItemDiff::Added(_) => println!("added:    {name}"),
+                    FolderItemDiff::Removed(_) => println!("removed:  {name}"),
+                    FolderItemDiff::Modified { .. } => println!("modified: {name}"),
+                }
+            }
+        }
+        FoldersCmds::Restore {
+            version,
+            path,
+            retry_strategy,
+        } => {
+            let version = parse_entry_hash(&version)?;
+            let path = get_path(path, None)?;
+            let acc_packet = AccountPacket::from_path(client.clone(), root_dir, &path, None)?;
+
+            let options = UploadCfg {
+                verify_store,
+                retry_strategy,
+                ..Default::default()
+            };
+            let metadata = acc_packet
+                .restore_folder_item(&path, version, options)
+                .await?;
+            println!(
+                "{:?} restored on the network, run 'sync' to bring it onto {path:?}",
+                metadata.name
+            );
+        }
     }
     Ok(())
 }
 
+// Parse a hex-encoded Folder version, i.e. a Register entry hash.
+fn parse_entry_hash(hex_str: &str) -> Result<EntryHash> {
+    match hex::decode(hex_str).map(<[u8; 32]>::try_from) {
+        Ok(Ok(hash)) => Ok(EntryHash(hash)),
+        _ => bail!("Failed to decode the version {hex_str:?}, it must be a hex-encoded 32 bytes entry hash"),
+    }
+}
+
 // Unwrap provided path, or return the current path if none was provided.
 // It can optionally be provided a string to adjoin when the current dir is returned.
 fn get_path(path: Option<PathBuf>, to_join: Option<&str>) -> Result<PathBuf> {
@@ -6,15 +6,20 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::path::Path;
 use std::str::FromStr;
 
 use bls::SecretKey;
 use color_eyre::eyre::bail;
 use color_eyre::Result;
+use serde_json::json;
 use sn_client::acc_packet::load_account_wallet_or_create_with_mnemonic;
-use sn_client::transfers::{CashNoteRedemption, SpendAddress, Transfer, GENESIS_SPEND_UNIQUE_KEY};
-use sn_client::{Client, SpendDag};
+use sn_client::transfers::{
+    CashNoteRedemption, NanoTokens, SpendAddress, Transfer, GENESIS_SPEND_UNIQUE_KEY,
+};
+use sn_client::{Client, SpendDag, TaintReport};
 
 const SPEND_DAG_FILENAME: &str = "spend_dag";
 const SPENDS_PROCESSING_BUFFER_SIZE: usize = 4096;
@@ -53,15 +58,33 @@ async fn step_by_step_spend_dag_gathering(client: &Client, mut dag: SpendDag) ->
     Ok(dag)
 }
 
+/// Print a status message, on stderr if stdout is kept for a machine readable report
+fn print_status(msg: impl Display, to_stderr: bool) {
+    if to_stderr {
+        eprintln!("{msg}");
+    } else {
+        println!("{msg}");
+    }
+}
+
 /// Gather the Spend DAG from the Network and store it on disk
 /// If a DAG is found on disk, it will continue from it
 /// If fast_mode is true, gathers in a silent and fast way
 /// else enjoy a step by step slow narrated gathering
-async fn gather_spend_dag(client: &Client, root_dir: &Path, fast_mode: bool) -> Result<SpendDag> {
+/// If status_to_stderr is true, status messages are printed on stderr instead of stdout
+async fn gather_spend_dag(
+    client: &Client,
+    root_dir: &Path,
+    fast_mode: bool,
+    status_to_stderr: bool,
+) -> Result<SpendDag> {
     let dag_path = root_dir.join(SPEND_DAG_FILENAME);
     let inital_dag = match SpendDag::load_from_file(&dag_path) {
         Ok(mut dag) => {
-            println!("Found a local spend dag on disk, continuing from it...");
+            print_status(
+                "Found a local spend dag on disk, continuing from it...",
+                status_to_stderr,
+            );
             if fast_mode {
                 client
                     .spend_dag_continue_from_utxos(&mut dag, Default::default(), false)
@@ -70,7 +93,10 @@ async fn gather_spend_dag(client: &Client, root_dir: &Path, fast_mode: bool) ->
             dag
         }
         Err(err) => {
-            println!("Starting from Genesis as found no local spend dag on disk...");
+            print_status(
+                "Starting from Genesis as found no local spend dag on disk...",
+                status_to_stderr,
+            );
             info!("Starting from Genesis as failed to load spend dag from disk: {err}");
             let genesis_addr = SpendAddress::from_unique_pubkey(&GENESIS_SPEND_UNIQUE_KEY);
             if fast_mode {
@@ -88,7 +114,10 @@ async fn gather_spend_dag(client: &Client, root_dir: &Path, fast_mode: bool) ->
         false => step_by_step_spend_dag_gathering(client, inital_dag).await?,
     };
 
-    println!("Saving DAG to disk at: {dag_path:?}");
+    print_status(
+        format!("Saving DAG to disk at: {dag_path:?}"),
+        status_to_stderr,
+    );
     dag.dump_to_file(dag_path)?;
 
     Ok(dag)
@@ -98,16 +127,24 @@ pub async fn audit(
     client: &Client,
     to_dot: bool,
     royalties: bool,
+    taint_report: bool,
     root_dir: &Path,
     foundation_sk: Option<SecretKey>,
 ) -> Result<()> {
-    let fast_mode = to_dot || royalties || foundation_sk.is_some();
-    let dag = gather_spend_dag(client, root_dir, fast_mode).await?;
+    let fast_mode = to_dot || royalties || taint_report || foundation_sk.is_some();
+    // the taint report is the only thing written to stdout, to be piped to other tools
+    let dag = gather_spend_dag(client, root_dir, fast_mode, taint_report).await?;
 
     if to_dot {
         println!("==========================   spends DAG digraph   ==========================");
         println!("{}", dag.dump_dot_format());
     }
+    if taint_report {
+        eprintln!(
+            "==========================   double spends taint report   =========================="
+        );
+        println!("{}", taint_report_to_json(&dag.taint_report()?)?);
+    }
     if let Some(sk) = foundation_sk {
         println!(
             "==========================   payment forward statistics  =========================="
@@ -119,10 +156,40 @@ pub async fn audit(
         redeem_royalties(royalties, client, root_dir).await?;
     }
 
-    println!("Audit completed successfully.");
+    print_status("Audit completed successfully.", taint_report);
     Ok(())
 }
 
+/// Format the taint report in JSON, with addresses in hex and amounts in nanos
+fn taint_report_to_json(report: &TaintReport) -> Result<String> {
+    let utxos_to_json = |utxos: &BTreeMap<SpendAddress, NanoTokens>| {
+        utxos
+            .iter()
+            .map(|(addr, amount)| json!({ "address": addr.to_hex(), "amount": amount.as_nano() }))
+            .collect::<Vec<_>>()
+    };
+
+    let double_spends = report
+        .double_spends
+        .iter()
+        .map(|taint| {
+            json!({
+                "address": taint.addr.to_hex(),
+                "tainted_spends": taint.spends.iter().map(|addr| addr.to_hex()).collect::<Vec<_>>(),
+                "tainted_utxos": utxos_to_json(&taint.utxos),
+                "tainted_amount": taint.amount.as_nano(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let json = json!({
+        "double_spends": double_spends,
+        "tainted_utxos": utxos_to_json(&report.utxos),
+        "tainted_amount": report.amount.as_nano(),
+    });
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
 /// Redeem royalties from the Network and deposit them into the wallet
 /// Only works if the wallet has the private key for the royalties
 async fn redeem_royalties(
@@ -123,6 +123,10 @@ pub enum WalletCmds {
         /// EXPERIMENTAL redeem all royalties
         #[clap(long, default_value = "false")]
         royalties: bool,
+        /// Dump on stdout, in JSON format, the UTXOs and amounts tainted by each double spend
+        /// found in the DAG, i.e. descending from it. Other messages are printed on stderr.
+        #[clap(long, default_value = "false", conflicts_with_all = ["dot", "royalties", "sk_str"])]
+        taint_report: bool,
         /// Hex string of the Foundation SK.
         /// Providing this key allow displaying rewards statistics gathered from the DAG.
         #[clap(long, name = "sk_str")]
@@ -215,6 +219,7 @@ pub(crate) async fn wallet_cmds(
         WalletCmds::Audit {
             dot,
             royalties,
+            taint_report,
             sk_str,
         } => {
             let sk_key = if let Some(s) = sk_str {
@@ -229,7 +234,7 @@ pub(crate) async fn wallet_cmds(
             } else {
                 None
             };
-            audit(client, dot, royalties, root_dir, sk_key).await
+            audit(client, dot, royalties, taint_report, root_dir, sk_key).await
         }
         WalletCmds::Verify {
             spend_address,
@@ -79,6 +79,7 @@ pub struct FilesUploader {
     status_notifier: Option<Box<dyn FilesUploadStatusNotifier>>,
     /// config
     make_data_public: bool,
+    resume: bool,
     upload_cfg: UploadCfg,
 }
 
@@ -93,6 +94,7 @@ impl FilesUploader {
             entries_to_upload: Default::default(),
             status_notifier: Some(status_notifier),
             make_data_public: false,
+            resume: false,
             upload_cfg: Default::default(),
         }
     }
@@ -107,6 +109,13 @@ impl FilesUploader {
         self
     }
 
+    /// Resume the upload that got interrupted the last time, if any, so that nothing gets paid for twice.
+    /// The upload has to have been journaled through `UploadCfg::journal`, under the same id.
+    pub fn set_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
     /// Override the default status notifier. By default we print things to stdout.
     pub fn set_status_notifier(
         mut self,
@@ -145,7 +154,23 @@ impl FilesUploader {
         }
 
         let now = Instant::now();
-        let mut uploader = Uploader::new(self.client, self.root_dir);
+        let mut uploader = if let Some(journal_id) = self.upload_cfg.journal.filter(|_| self.resume)
+        {
+            match Uploader::resume(self.client.clone(), self.root_dir.clone(), journal_id) {
+                Ok(uploader) => uploader,
+                Err(ClientError::NoUploadToResume(_)) => {
+                    println!("No interrupted upload was found, starting a new one.");
+                    info!("No interrupted upload was found, starting a new one.");
+                    Uploader::new(self.client, self.root_dir)
+                }
+                Err(err) => return Err(eyre!("Failed to resume the upload: {err}")),
+            }
+        } else {
+            if self.resume {
+                warn!("The upload is not journaled, hence cannot be resumed.");
+            }
+            Uploader::new(self.client, self.root_dir)
+        };
         uploader.set_upload_cfg(self.upload_cfg);
         uploader.insert_chunk_paths(chunks_to_upload);
 
@@ -0,0 +1,150 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::get_progress_bar;
+
+use color_eyre::{eyre::eyre, Result};
+use futures::StreamExt;
+use sn_client::{
+    networking::close_group_majority,
+    protocol::{
+        messages::RecordMetadata,
+        storage::{ChunkAddress, RecordKind, RetryStrategy},
+        NetworkAddress,
+    },
+    FilesApi, FilesDownload,
+};
+use tracing::{info, warn};
+use xor_name::XorName;
+
+/// What the close group of a chunk reported about it.
+enum ChunkVerdict {
+    Healthy,
+    Unhealthy(String),
+}
+
+/// Verify that the chunks of the file at `address` are held by the network.
+///
+/// With `metadata_only`, the close group of each chunk is asked for the metadata of its copy,
+/// and a chunk passes if a majority of them report the same copy. Otherwise each chunk gets
+/// fetched in full.
+pub async fn verify_file(
+    files_api: FilesApi,
+    address: XorName,
+    metadata_only: bool,
+    batch_size: usize,
+    retry_strategy: RetryStrategy,
+) -> Result<()> {
+    let head_address = ChunkAddress::new(address);
+    let mut chunk_addresses = FilesDownload::new(files_api.clone())
+        .set_retry_strategy(retry_strategy)
+        .chunk_addresses(head_address)
+        .await?;
+    chunk_addresses.insert(0, head_address);
+    let chunks_count = chunk_addresses.len();
+
+    println!(
+        "Verifying {} chunks of file {:64x}{}...",
+        chunks_count,
+        address,
+        if metadata_only {
+            " from their metadata only"
+        } else {
+            ""
+        }
+    );
+    let progress_bar = get_progress_bar(chunks_count as u64)?;
+
+    let client = files_api.client().clone();
+    let mut stream = futures::stream::iter(chunk_addresses)
+        .map(|chunk_address| {
+            let client = client.clone();
+            async move {
+                let verdict = if metadata_only {
+                    let network_address = NetworkAddress::from_chunk_address(chunk_address);
+                    match client
+                        .get_record_metadata_from_close_group(&network_address)
+                        .await
+                    {
+                        Ok(metadata) => chunk_verdict_from_metadata(
+                            metadata
+                                .into_values()
+                                .filter_map(|metadata| metadata.ok())
+                                .collect(),
+                        ),
+                        Err(err) => ChunkVerdict::Unhealthy(format!(
+                            "failed to query its close group: {err}"
+                        )),
+                    }
+                } else {
+                    match client
+                        .get_chunk(chunk_address, false, Some(retry_strategy))
+                        .await
+                    {
+                        Ok(_chunk) => ChunkVerdict::Healthy,
+                        Err(err) => ChunkVerdict::Unhealthy(format!("failed to fetch it: {err}")),
+                    }
+                };
+                (chunk_address, verdict)
+            }
+        })
+        .buffer_unordered(batch_size);
+
+    let mut unhealthy_chunks = vec![];
+    while let Some((chunk_address, verdict)) = stream.next().await {
+        progress_bar.inc(1);
+        if let ChunkVerdict::Unhealthy(reason) = verdict {
+            warn!("Chunk {chunk_address:?} failed verification: {reason}");
+            unhealthy_chunks.push((chunk_address, reason));
+        }
+    }
+    progress_bar.finish_and_clear();
+
+    if unhealthy_chunks.is_empty() {
+        println!("All chunks of file {address:64x} have been verified.");
+        info!("All chunks of file {address:64x} have been verified.");
+        return Ok(());
+    }
+
+    for (chunk_address, reason) in unhealthy_chunks.iter() {
+        println!("Chunk {:64x} {reason}", chunk_address.xorname());
+    }
+    Err(eyre!(
+        "{} out of {chunks_count} chunks of file {address:64x} failed verification",
+        unhealthy_chunks.len()
+    ))
+}
+
+/// A chunk is healthy when a majority of its close group report the same copy of it.
+fn chunk_verdict_from_metadata(metadata: Vec<RecordMetadata>) -> ChunkVerdict {
+    let mut copies: Vec<(&RecordMetadata, usize)> = vec![];
+    for metadata in metadata.iter() {
+        match copies
+            .iter_mut()
+            .find(|(copy, _holders)| copy.same_content(metadata))
+        {
+            Some((_copy, holders)) => *holders += 1,
+            None => copies.push((metadata, 1)),
+        }
+    }
+
+    let Some((copy, holders)) = copies.into_iter().max_by_key(|(_copy, holders)| *holders) else {
+        return ChunkVerdict::Unhealthy("is not held by any of its close group".to_string());
+    };
+
+    if copy.kind != RecordKind::Chunk {
+        ChunkVerdict::Unhealthy(format!("is held as a record of kind {}", copy.kind))
+    } else if holders < close_group_majority() {
+        ChunkVerdict::Unhealthy(format!(
+            "is only held by {holders} of its close group, {} others hold diverging copies",
+            metadata.len() - holders
+        ))
+    } else {
+        ChunkVerdict::Healthy
+    }
+}
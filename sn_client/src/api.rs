@@ -24,11 +24,11 @@ use sn_networking::{
     get_signed_spend_from_record, multiaddr_is_global,
     target_arch::{interval, spawn, timeout, Instant},
     GetRecordCfg, GetRecordError, NetworkBuilder, NetworkError, NetworkEvent, PutRecordCfg,
-    VerificationKind, CLOSE_GROUP_SIZE,
+    RecordCacheConfig, RecordFetchStrategy, VerificationKind, CLOSE_GROUP_SIZE,
 };
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::ChunkProof,
+    messages::{ChunkProof, RecordMetadata},
     storage::{
         try_deserialize_record, try_serialize_record, Chunk, ChunkAddress, RecordHeader,
         RecordKind, RegisterAddress, RetryStrategy, SpendAddress,
@@ -42,7 +42,7 @@ use sn_transfers::{
 #[cfg(target_arch = "wasm32")]
 use std::path::PathBuf;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     num::NonZeroUsize,
     sync::Arc,
 };
@@ -89,6 +89,53 @@ impl Client {
         peers: Option<Vec<Multiaddr>>,
         connection_timeout: Option<Duration>,
         client_event_broadcaster: Option<ClientEventsBroadcaster>,
+    ) -> Result<Self> {
+        Self::new_with_record_cache(
+            signer,
+            peers,
+            connection_timeout,
+            client_event_broadcaster,
+            None,
+        )
+        .await
+    }
+
+    /// Instantiate a new client, caching the records it fetches.
+    ///
+    /// Chunks are served from the cache until evicted, registers and spends only for the
+    /// `mutable_record_ttl` of the config. No record gets cached if `record_cache` is `None`.
+    ///
+    /// # Arguments
+    /// * 'signer' - [SecretKey]
+    /// * 'peers' - [Option]<[Vec]<[Multiaddr]>>
+    /// * 'connection_timeout' - [Option]<[Duration]> : Specification for client connection timeout set via Optional
+    /// * 'client_event_broadcaster' - [Option]<[ClientEventsBroadcaster]>
+    /// * 'record_cache' - [Option]<[RecordCacheConfig]>
+    ///
+    /// # Example
+    /// ```no_run
+    /// use sn_client::{Client, Error};
+    /// use sn_client::networking::RecordCacheConfig;
+    /// use bls::SecretKey;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let client = Client::new_with_record_cache(
+    ///     SecretKey::random(),
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     Some(RecordCacheConfig::default()),
+    /// )
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_with_record_cache(
+        signer: SecretKey,
+        peers: Option<Vec<Multiaddr>>,
+        connection_timeout: Option<Duration>,
+        client_event_broadcaster: Option<ClientEventsBroadcaster>,
+        record_cache: Option<RecordCacheConfig>,
     ) -> Result<Self> {
         // If any of our contact peers has a global address, we'll assume we're in a global network.
         let local = match peers {
@@ -105,11 +152,8 @@ impl Client {
         let root_dir = std::env::temp_dir();
         trace!("Starting Kad swarm in client mode..{root_dir:?}.");
 
-        #[cfg(not(feature = "open-metrics"))]
-        let network_builder = NetworkBuilder::new(Keypair::generate_ed25519(), local, root_dir);
-
-        #[cfg(feature = "open-metrics")]
         let mut network_builder = NetworkBuilder::new(Keypair::generate_ed25519(), local, root_dir);
+        network_builder.record_cache(record_cache);
         #[cfg(feature = "open-metrics")]
         network_builder.metrics_registry(Some(Registry::default()));
 
@@ -463,6 +507,7 @@ impl Client {
             retry_strategy,
             target_record: None,
             expected_holders: Default::default(),
+            fetch_strategy: RecordFetchStrategy::Kademlia,
         };
 
         let maybe_record = self.network.get_record_from_network(key, &get_cfg).await;
@@ -656,6 +701,7 @@ impl Client {
                 retry_strategy,
                 target_record: None, // Not used since we use ChunkProof
                 expected_holders: Default::default(),
+                fetch_strategy: RecordFetchStrategy::Kademlia,
             };
             // The `ChunkWithPayment` is only used to send out via PutRecord.
             // The holders shall only hold the `Chunk` copies.
@@ -741,6 +787,7 @@ impl Client {
             retry_strategy: Some(retry_strategy.unwrap_or(RetryStrategy::Quick)),
             target_record: None,
             expected_holders,
+            fetch_strategy: RecordFetchStrategy::Hedged,
         };
         let record = self.network.get_record_from_network(key, &get_cfg).await?;
         let header = RecordHeader::from_record(&record)?;
@@ -779,6 +826,26 @@ impl Client {
         Ok(())
     }
 
+    /// Get the metadata of the record at `address` from each of its close group peers,
+    /// without fetching the record itself.
+    ///
+    /// Peers that do not hold the record, or fail to respond, carry an `Err`.
+    pub async fn get_record_metadata_from_close_group(
+        &self,
+        address: &NetworkAddress,
+    ) -> Result<BTreeMap<PeerId, Result<RecordMetadata>>> {
+        info!("Getting record metadata from the close group of {address:?}");
+        let metadata = self
+            .network
+            .get_record_metadata_from_close_group(address)
+            .await?
+            .into_iter()
+            .map(|(peer, metadata)| (peer, metadata.map_err(Error::from)))
+            .collect();
+
+        Ok(metadata)
+    }
+
     /// Verify if a `Register` is stored by expected nodes on the network.
     ///
     /// # Arguments
@@ -890,6 +957,7 @@ impl Client {
             retry_strategy: Some(RetryStrategy::Balanced),
             target_record: record_to_verify,
             expected_holders,
+            fetch_strategy: RecordFetchStrategy::Kademlia,
         };
         let put_cfg = PutRecordCfg {
             put_quorum: Quorum::All,
@@ -937,6 +1005,7 @@ impl Client {
                 retry_strategy: Some(RetryStrategy::Balanced),
                 target_record: None,
                 expected_holders: Default::default(),
+                fetch_strategy: RecordFetchStrategy::Kademlia,
             },
         )
         .await
@@ -952,6 +1021,7 @@ impl Client {
                 retry_strategy: None,
                 target_record: None,
                 expected_holders: Default::default(),
+                fetch_strategy: RecordFetchStrategy::Kademlia,
             },
         )
         .await
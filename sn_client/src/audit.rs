@@ -14,4 +14,4 @@ mod spend_dag;
 mod tests;
 
 pub use dag_error::{DagError, SpendFault};
-pub use spend_dag::{SpendDag, SpendDagGet};
+pub use spend_dag::{DoubleSpendTaint, SpendDag, SpendDagGet, TaintReport};
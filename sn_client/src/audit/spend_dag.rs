@@ -88,6 +88,31 @@ pub enum SpendDagGet {
     Spend(Box<SignedSpend>),
 }
 
+/// The value tainted by a double spend, i.e. the spends and UTXOs descending from any of the
+/// spends made at its address.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DoubleSpendTaint {
+    /// The address of the double spend
+    pub addr: SpendAddress,
+    /// The spends descending from the double spend
+    pub spends: BTreeSet<SpendAddress>,
+    /// The UTXOs descending from the double spend, along with their amount
+    pub utxos: BTreeMap<SpendAddress, NanoTokens>,
+    /// The total amount held by the tainted UTXOs
+    pub amount: NanoTokens,
+}
+
+/// The UTXOs and value tainted by the double spends found in the DAG
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TaintReport {
+    /// The taint of each of the double spends
+    pub double_spends: Vec<DoubleSpendTaint>,
+    /// The UTXOs tainted by any of the double spends, along with their amount
+    pub utxos: BTreeMap<SpendAddress, NanoTokens>,
+    /// The total amount held by the tainted UTXOs
+    pub amount: NanoTokens,
+}
+
 impl SpendDag {
     /// Create a new DAG with a given source
     pub fn new(source: SpendAddress) -> Self {
@@ -412,6 +437,62 @@ impl SpendDag {
         Ok(royalties)
     }
 
+    /// Propagate the taint of each double spend in the DAG forward through its descendants,
+    /// returning the spends and UTXOs it reaches along with the amounts they hold.
+    /// Any value descending from a double spend is tainted, even when mixed further down
+    /// the DAG with value which isn't.
+    pub fn taint_report(&self) -> Result<TaintReport, DagError> {
+        let mut double_spends = vec![];
+        let mut tainted_utxos = BTreeMap::new();
+        for (addr, entry) in self.spends.iter() {
+            if !matches!(entry, DagEntry::DoubleSpend(_)) {
+                continue;
+            }
+
+            let mut spends = BTreeSet::new();
+            let mut utxos = BTreeMap::new();
+            for descendant in self.all_descendants(addr)? {
+                match self.spends.get(descendant) {
+                    Some(DagEntry::NotGatheredYet(idx)) => {
+                        utxos.insert(*descendant, self.utxo_amount(*idx));
+                    }
+                    Some(_) => {
+                        spends.insert(*descendant);
+                    }
+                    None => {}
+                }
+            }
+            debug!(
+                "Double spend at {addr:?} taints {} spends and {} UTXOs",
+                spends.len(),
+                utxos.len()
+            );
+
+            tainted_utxos.extend(utxos.clone());
+            double_spends.push(DoubleSpendTaint {
+                addr: *addr,
+                spends,
+                amount: total_amount(addr, &utxos)?,
+                utxos,
+            });
+        }
+
+        Ok(TaintReport {
+            double_spends,
+            amount: total_amount(&self.source, &tainted_utxos)?,
+            utxos: tainted_utxos,
+        })
+    }
+
+    /// helper that returns the amount given to a UTXO by the spend it comes from
+    fn utxo_amount(&self, idx: DagIndex) -> NanoTokens {
+        self.dag
+            .edges_directed(NodeIndex::new(idx), petgraph::Direction::Incoming)
+            .map(|edge| *edge.weight())
+            .max()
+            .unwrap_or(NanoTokens::zero())
+    }
+
     /// Remove all edges from a Node in the DAG
     fn remove_all_edges(&mut self, node: NodeIndex) {
         let incoming: Vec<_> = self
@@ -788,6 +869,24 @@ impl SpendDag {
     }
 }
 
+/// Sum of the amounts held by the given UTXOs, descending from the spend at `addr`
+fn total_amount(
+    addr: &SpendAddress,
+    utxos: &BTreeMap<SpendAddress, NanoTokens>,
+) -> Result<NanoTokens, DagError> {
+    utxos
+        .values()
+        .try_fold(NanoTokens::zero(), |total, amount| {
+            total.checked_add(*amount)
+        })
+        .ok_or_else(|| {
+            DagError::IncoherentDag(
+                *addr,
+                "the amounts of the UTXOs descending from it overflow".to_string(),
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use xor_name::XorName;
@@ -806,4 +905,25 @@ mod tests {
             rmp_serde::to_vec(&deserialized_instance).expect("Serialization failed");
         assert_eq!(reserialized_data, serialized_data);
     }
+
+    #[test]
+    fn test_total_amount_overflow() {
+        let mut rng = rand::thread_rng();
+        let addr = SpendAddress::new(XorName::random(&mut rng));
+        let mut utxos = BTreeMap::new();
+        utxos.insert(
+            SpendAddress::new(XorName::random(&mut rng)),
+            NanoTokens::from(u64::MAX),
+        );
+        assert_eq!(total_amount(&addr, &utxos), Ok(NanoTokens::from(u64::MAX)));
+
+        utxos.insert(
+            SpendAddress::new(XorName::random(&mut rng)),
+            NanoTokens::from(1),
+        );
+        assert!(matches!(
+            total_amount(&addr, &utxos),
+            Err(DagError::IncoherentDag(a, _)) if a == addr
+        ));
+    }
 }
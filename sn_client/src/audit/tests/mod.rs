@@ -12,8 +12,8 @@ use std::collections::BTreeSet;
 
 use setup::MockNetwork;
 
-use eyre::Result;
-use sn_transfers::SpendAddress;
+use eyre::{eyre, Result};
+use sn_transfers::{NanoTokens, SpendAddress};
 
 use crate::{SpendDag, SpendFault};
 
@@ -127,6 +127,85 @@ fn test_spend_dag_double_spend_poisonning() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_spend_dag_double_spend_taint_report() -> Result<()> {
+    let mut net = MockNetwork::genesis()?;
+    let genesis = net.genesis_spend;
+
+    let owner1 = net.new_pk_with_balance(100)?;
+    let owner2 = net.new_pk_with_balance(0)?;
+    let owner3 = net.new_pk_with_balance(0)?;
+    let owner4 = net.new_pk_with_balance(0)?;
+    let owner_clean = net.new_pk_with_balance(50)?;
+    let owner_cheat = net.new_pk_with_balance(0)?;
+
+    // spend normaly and save a cashnote to reuse later
+    net.send(&owner1, &owner2, 100)?;
+    let cn_to_reuse_later = net
+        .wallets
+        .get(&owner2)
+        .expect("owner2 wallet to exist")
+        .cn
+        .clone();
+    let spend1 = net.send(&owner2, &owner3, 100)?;
+    let spend_ko3 = net.send(&owner3, &owner4, 60)?;
+
+    // reuse that cashnote to perform a double spend
+    net.wallets
+        .get_mut(&owner2)
+        .expect("owner2 wallet to still exist")
+        .cn = cn_to_reuse_later;
+    let _ = net.send(&owner2, &owner_cheat, 100)?;
+
+    // create dag
+    let mut dag = SpendDag::new(genesis);
+    for spend in net.spends {
+        dag.insert(spend.address(), spend.clone());
+    }
+    assert!(dag.record_faults(&genesis).is_ok());
+
+    let utxo_of = |owner| -> Result<SpendAddress> {
+        let upk = net
+            .wallets
+            .get(owner)
+            .ok_or_else(|| eyre!("wallet to exist for {owner:?}"))?
+            .cn
+            .first()
+            .ok_or_else(|| eyre!("wallet of {owner:?} to have a cashnote"))?
+            .unique_pubkey();
+        Ok(SpendAddress::from_unique_pubkey(&upk))
+    };
+
+    // the value of both branches of the double spend is tainted, including owner3's change
+    let double_spent = spend1.first().expect("spend1 to have an element");
+    let report = dag.taint_report()?;
+    assert_eq!(report.double_spends.len(), 1);
+    let taint = &report.double_spends[0];
+    assert_eq!(&taint.addr, double_spent);
+    assert!(taint
+        .spends
+        .contains(spend_ko3.first().expect("spend_ko3 to have an element")));
+    assert_eq!(
+        taint.utxos.get(&utxo_of(&owner4)?),
+        Some(&NanoTokens::from(60))
+    );
+    assert_eq!(
+        taint.utxos.get(&utxo_of(&owner3)?),
+        Some(&NanoTokens::from(40))
+    );
+    assert_eq!(
+        taint.utxos.get(&utxo_of(&owner_cheat)?),
+        Some(&NanoTokens::from(100))
+    );
+    assert_eq!(taint.amount, NanoTokens::from(200));
+
+    // while the rest of the DAG is not
+    assert!(!report.utxos.contains_key(&utxo_of(&owner_clean)?));
+    assert_eq!(report.utxos, taint.utxos);
+    assert_eq!(report.amount, NanoTokens::from(200));
+    Ok(())
+}
+
 #[test]
 fn test_spend_dag_double_spend_branches() -> Result<()> {
     let mut net = MockNetwork::genesis()?;
@@ -8,6 +8,8 @@
 
 mod error;
 mod pac_man;
+mod stream_encryptor;
 
 pub(crate) use self::error::{Error, Result};
-pub(crate) use pac_man::{encrypt_large, DataMapLevel};
+pub(crate) use pac_man::{encrypt_large, pack_data_map, DataMapLevel};
+pub(crate) use stream_encryptor::StreamEncryptor;
@@ -97,7 +97,7 @@ pub(crate) fn to_chunk(chunk_content: Bytes) -> Chunk {
 // In other words: If the chunk content is too big, it will be
 // self encrypted into additional chunks, and now we have a new `DataMap`
 // which points to all of those additional chunks.. and so on.
-fn pack_data_map(data_map: DataMap) -> Result<(Chunk, Vec<Chunk>)> {
+pub(crate) fn pack_data_map(data_map: DataMap) -> Result<(Chunk, Vec<Chunk>)> {
     let mut chunks = vec![];
     let mut chunk_content = wrap_data_map(&DataMapLevel::First(data_map))?;
 
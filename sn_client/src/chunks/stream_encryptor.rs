@@ -0,0 +1,226 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Error, Result};
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use brotli::enc::BrotliEncoderParams;
+use bytes::Bytes;
+use self_encryption::{
+    ChunkInfo, DataMap, EncryptedChunk, COMPRESSION_QUALITY, MAX_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES,
+};
+use std::io::Cursor;
+use xor_name::XorName;
+
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+
+/// Self-encrypts content of an unknown size as it is produced, holding at most three chunks of
+/// the source content in memory, and yielding the same chunks and `DataMap` as
+/// `self_encryption::encrypt` would over the whole content.
+///
+/// The content is fed in pieces of `MAX_CHUNK_SIZE`, except for the last piece which can be
+/// shorter. Each chunk is encrypted with the source hashes of the two chunks before it, so all
+/// chunks but the first two are encrypted as soon as they are fed. The first two need the source
+/// hashes of the last two chunks, and are only encrypted on `finish`.
+///
+/// Content smaller than three full chunks is split into three equal chunks instead, hence is held
+/// until `finish` and encrypted as a whole.
+#[derive(Default)]
+pub(crate) struct StreamEncryptor {
+    /// The source content of the chunks not encrypted yet.
+    held: Vec<Bytes>,
+    /// The source hashes of all the chunks fed so far, by index.
+    src_hashes: Vec<XorName>,
+    infos: Vec<ChunkInfo>,
+}
+
+impl StreamEncryptor {
+    /// Feed the next piece of content, returning its encrypted chunk if it could be encrypted
+    /// right away.
+    pub(crate) fn next_piece(&mut self, content: Bytes) -> Result<Option<EncryptedChunk>> {
+        let index = self.src_hashes.len();
+        let src_hash = XorName::from_content(&content);
+        self.src_hashes.push(src_hash);
+
+        // A short third piece means the whole content is smaller than three full chunks.
+        if index < 2 || (index == 2 && content.len() < MAX_CHUNK_SIZE) {
+            self.held.push(content);
+            return Ok(None);
+        }
+
+        let n_1_src_hash = self.src_hashes[index - 1];
+        let n_2_src_hash = self.src_hashes[index - 2];
+        Ok(Some(self.encrypt_chunk(
+            index,
+            content,
+            n_1_src_hash,
+            n_2_src_hash,
+        )?))
+    }
+
+    /// Encrypt the held chunks, returning them along with the `DataMap` of the whole content.
+    pub(crate) fn finish(mut self) -> Result<(DataMap, Vec<EncryptedChunk>)> {
+        let num_chunks = self.src_hashes.len();
+        if num_chunks < 3 || self.held.len() == 3 {
+            let content: Bytes = self.held.concat().into();
+            if content.is_empty() {
+                return Err(Error::EmptyFileProvided);
+            } else if content.len() < MIN_ENCRYPTABLE_BYTES {
+                return Err(Error::FileTooSmall);
+            }
+            return Ok(self_encryption::encrypt(content)?);
+        }
+
+        let last_src_hash = self.src_hashes[num_chunks - 1];
+        let penultimate_src_hash = self.src_hashes[num_chunks - 2];
+        let first_src_hash = self.src_hashes[0];
+        let mut held = std::mem::take(&mut self.held).into_iter();
+        let (Some(first), Some(second)) = (held.next(), held.next()) else {
+            return Err(Error::NotAllDataWasChunked {
+                expected: num_chunks,
+                chunked: self.infos.len(),
+            });
+        };
+
+        let encrypted_chunks = vec![
+            self.encrypt_chunk(0, first, last_src_hash, penultimate_src_hash)?,
+            self.encrypt_chunk(1, second, first_src_hash, last_src_hash)?,
+        ];
+
+        Ok((DataMap::new(self.infos), encrypted_chunks))
+    }
+
+    fn encrypt_chunk(
+        &mut self,
+        index: usize,
+        content: Bytes,
+        n_1_src_hash: XorName,
+        n_2_src_hash: XorName,
+    ) -> Result<EncryptedChunk> {
+        let src_hash = self.src_hashes[index];
+        let encrypted_content = encrypt_chunk(&content, &src_hash, &n_1_src_hash, &n_2_src_hash)?;
+        self.infos.push(ChunkInfo {
+            index,
+            dst_hash: XorName::from_content(&encrypted_content),
+            src_hash,
+            src_size: content.len(),
+        });
+        Ok(EncryptedChunk {
+            index,
+            content: encrypted_content,
+        })
+    }
+}
+
+/// Compresses, encrypts and obfuscates a chunk, as `self_encryption` does. The key and IV are
+/// taken from the source hash of the previous chunk, the pad from the source hashes of the chunk
+/// itself and of the one before the previous.
+///
+/// This mirrors private code of the `self_encryption` version pinned in `Cargo.toml`, bumping it
+/// requires `stream_encryption_matches_whole_content_encryption` to keep passing.
+fn encrypt_chunk(
+    content: &[u8],
+    src_hash: &XorName,
+    n_1_src_hash: &XorName,
+    n_2_src_hash: &XorName,
+) -> Result<Bytes> {
+    let mut compressed = vec![];
+    let params = BrotliEncoderParams {
+        quality: COMPRESSION_QUALITY,
+        ..Default::default()
+    };
+    let _size = brotli::BrotliCompress(&mut Cursor::new(content), &mut compressed, &params)
+        .map_err(|_| self_encryption::Error::Compression)?;
+
+    let (key, iv) = n_1_src_hash.0.split_at(16);
+    let encrypted =
+        Aes128CbcEnc::new(key.into(), iv.into()).encrypt_padded_vec_mut::<Pkcs7>(&compressed);
+
+    let pad: Vec<u8> = src_hash
+        .0
+        .iter()
+        .chain(n_2_src_hash.0.iter())
+        .copied()
+        .collect();
+    Ok(encrypted
+        .iter()
+        .zip(pad.iter().cycle())
+        .map(|(byte, pad_byte)| byte ^ pad_byte)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use rand::{thread_rng, Rng};
+    use std::collections::BTreeMap;
+
+    fn stream_encrypt(content: &Bytes) -> super::Result<(DataMap, Vec<EncryptedChunk>)> {
+        let mut encryptor = StreamEncryptor::default();
+        let mut encrypted_chunks = vec![];
+        for piece in content.chunks(MAX_CHUNK_SIZE) {
+            encrypted_chunks.extend(encryptor.next_piece(Bytes::copy_from_slice(piece))?);
+        }
+        let (data_map, held_chunks) = encryptor.finish()?;
+        encrypted_chunks.extend(held_chunks);
+        Ok((data_map, encrypted_chunks))
+    }
+
+    fn by_index(chunks: Vec<EncryptedChunk>) -> BTreeMap<usize, Bytes> {
+        chunks
+            .into_iter()
+            .map(|chunk| (chunk.index, chunk.content))
+            .collect()
+    }
+
+    #[test]
+    fn stream_encryption_matches_whole_content_encryption() -> Result<()> {
+        let mut rng = thread_rng();
+        let sizes = [
+            MIN_ENCRYPTABLE_BYTES,
+            10_000,
+            2 * MAX_CHUNK_SIZE,
+            3 * MAX_CHUNK_SIZE - 1,
+            3 * MAX_CHUNK_SIZE,
+            3 * MAX_CHUNK_SIZE + 1,
+            5 * MAX_CHUNK_SIZE + 123,
+            6 * MAX_CHUNK_SIZE,
+        ];
+        for size in sizes {
+            let content: Bytes = (0..size)
+                .map(|_| rng.gen::<u8>())
+                .collect::<Vec<_>>()
+                .into();
+
+            let (expected_data_map, expected_chunks) = self_encryption::encrypt(content.clone())?;
+            let (data_map, chunks) = stream_encrypt(&content)?;
+
+            assert_eq!(data_map, expected_data_map, "data map of {size} bytes");
+            assert!(
+                by_index(chunks) == by_index(expected_chunks),
+                "chunks of {size} bytes"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn too_small_content_is_rejected() {
+        assert!(matches!(
+            stream_encrypt(&Bytes::new()),
+            Err(Error::EmptyFileProvided)
+        ));
+        assert!(matches!(
+            stream_encrypt(&Bytes::from_static(b"ab")),
+            Err(Error::FileTooSmall)
+        ));
+    }
+}
@@ -11,9 +11,9 @@ pub(crate) type Result<T> = std::result::Result<T, Error>;
 use crate::UploadSummary;
 
 use super::ClientEvent;
-use sn_protocol::NetworkAddress;
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
 use sn_registers::{Entry, EntryHash};
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, path::PathBuf};
 use thiserror::Error;
 use tokio::time::Duration;
 use xor_name::XorName;
@@ -49,6 +49,15 @@ pub enum Error {
     #[error("Decrypting a Folder's item failed: {0}")]
     FolderEntryDecryption(EntryHash),
 
+    #[error("Folder version not found: {0}")]
+    FolderVersionNotFound(EntryHash),
+
+    #[error("Folder version {0} is the removal of an item, there is nothing to restore from it")]
+    FolderVersionIsRemoval(EntryHash),
+
+    #[error("Decrypting the shared file at {0:?} failed")]
+    SharedFileDecryption(ChunkAddress),
+
     #[error("SelfEncryption Error {0}.")]
     SelfEncryptionIO(#[from] self_encryption::Error),
 
@@ -114,6 +123,12 @@ pub enum Error {
     #[error("Could not find register after batch sync: {0:?}")]
     RegisterNotFoundAfterUpload(XorName),
 
+    #[error(
+        "The RegisterMap holds the maximum number of entries ({0}), \
+        its owner has to compact it with a checkpoint"
+    )]
+    RegisterMapFull(usize),
+
     #[error("Could not connect due to incompatible network protocols. Our protocol: {0} Network protocol: {1}")]
     UnsupportedProtocol(String, String),
 
@@ -148,6 +163,9 @@ pub enum Error {
         summary: UploadSummary,
     },
 
+    #[error("No interrupted upload to resume was found under {0:?}")]
+    NoUploadToResume(PathBuf),
+
     #[error("Error occurred when access wallet file")]
     FailedToAccessWallet,
 
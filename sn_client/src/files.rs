@@ -7,24 +7,34 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 pub(crate) mod download;
+pub(crate) mod reader;
+pub(crate) mod share;
 
+use self::share::SharedFile;
 use crate::{
-    acc_packet::load_account_wallet_or_create_with_mnemonic, chunks::Error as ChunksError,
-    error::Result, wallet::StoragePaymentResult, Client, Error, WalletClient,
+    acc_packet::load_account_wallet_or_create_with_mnemonic,
+    chunks::{pack_data_map, Error as ChunksError, StreamEncryptor},
+    error::Result,
+    wallet::StoragePaymentResult,
+    Client, Error, WalletClient,
 };
+use bls::PublicKey;
 use bytes::Bytes;
-use self_encryption::{self, MIN_ENCRYPTABLE_BYTES};
+use futures::StreamExt;
+use self_encryption::{self, MAX_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
 use sn_protocol::{
     storage::{Chunk, ChunkAddress, RetryStrategy},
     NetworkAddress,
 };
 
 use std::{
+    ffi::OsString,
     fs::{self, create_dir_all, File},
     io::Write,
     path::{Path, PathBuf},
 };
 use tempfile::tempdir;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use tracing::trace;
 use xor_name::XorName;
 
@@ -158,10 +168,116 @@ impl FilesApi {
         Ok(res)
     }
 
+    /// Self-encrypts the content read from `reader` as it is produced, and pays for and uploads
+    /// the chunks in batches of `BATCH_SIZE` along the way. No chunk gets written to disk, and
+    /// only the current batch and the first two chunks of the content are held in memory.
+    ///
+    /// Returns the address of the data map chunk, which gets uploaded as well.
+    ///
+    /// * 'retry_strategy' - [Option]<[RetryStrategy]> : Uses Balanced by default
+    pub async fn upload_from_reader(
+        &self,
+        mut reader: impl AsyncRead + Unpin,
+        verify_store: bool,
+        retry_strategy: Option<RetryStrategy>,
+    ) -> Result<ChunkAddress> {
+        let mut encryptor = StreamEncryptor::default();
+        let mut batch = vec![];
+        loop {
+            let piece = read_piece(&mut reader).await?;
+            let reached_end = piece.len() < MAX_CHUNK_SIZE;
+            if !piece.is_empty() {
+                if let Some(encrypted_chunk) = encryptor.next_piece(piece)? {
+                    batch.push(Chunk::new(encrypted_chunk.content));
+                }
+            }
+            if batch.len() >= BATCH_SIZE || (reached_end && !batch.is_empty()) {
+                self.pay_and_upload_chunks(
+                    std::mem::take(&mut batch),
+                    verify_store,
+                    retry_strategy,
+                )
+                .await?;
+            }
+            if reached_end {
+                break;
+            }
+        }
+
+        let (data_map, held_chunks) = encryptor.finish()?;
+        let (data_map_chunk, additional_chunks) = pack_data_map(data_map)?;
+        let head_address = *data_map_chunk.address();
+
+        let mut remaining: Vec<Chunk> = held_chunks
+            .into_iter()
+            .map(|encrypted_chunk| Chunk::new(encrypted_chunk.content))
+            .chain(additional_chunks)
+            .collect();
+        remaining.push(data_map_chunk);
+        for batch in remaining.chunks(BATCH_SIZE) {
+            self.pay_and_upload_chunks(batch.to_vec(), verify_store, retry_strategy)
+                .await?;
+        }
+
+        info!("Uploaded the streamed content to {head_address:?}");
+        Ok(head_address)
+    }
+
+    /// Shares a privately uploaded file with the holder of the `recipient` key, by storing its
+    /// name and data map chunk in a chunk encrypted to the `recipient`. The data map chunk itself
+    /// is never stored on the network in the clear.
+    ///
+    /// Returns the address of the share, to hand over to the recipient, who can then get the
+    /// file with `FilesApi::receive_shared_file`.
+    pub async fn share_file(
+        &self,
+        name: OsString,
+        data_map: Chunk,
+        recipient: PublicKey,
+        verify_store: bool,
+    ) -> Result<ChunkAddress> {
+        let share_chunk = SharedFile { name, data_map }.encrypt(&recipient)?;
+        let share_address = *share_chunk.address();
+        self.pay_and_upload_chunks(vec![share_chunk], verify_store, None)
+            .await?;
+
+        info!("Shared file to {recipient:?} at {share_address:?}");
+        Ok(share_address)
+    }
+
+    /// Fetches the file shared at `share_address` and decrypts it with the client's key. The
+    /// file can then be downloaded along with its data map chunk through `FilesDownload`, or
+    /// straight away with `FilesDownload::download_shared_file`.
+    pub async fn receive_shared_file(&self, share_address: ChunkAddress) -> Result<SharedFile> {
+        let share_chunk = self.client.get_chunk(share_address, false, None).await?;
+        SharedFile::decrypt(&share_chunk, self.client.signer())
+    }
+
     // --------------------------------------------
     // ---------- Private helpers -----------------
     // --------------------------------------------
 
+    async fn pay_and_upload_chunks(
+        &self,
+        chunks: Vec<Chunk>,
+        verify_store: bool,
+        retry_strategy: Option<RetryStrategy>,
+    ) -> Result<()> {
+        let _ = self
+            .pay_for_chunks(chunks.iter().map(|chunk| *chunk.name()).collect())
+            .await?;
+
+        let mut uploads = futures::stream::iter(chunks)
+            .map(|chunk| {
+                self.get_local_payment_and_upload_chunk(chunk, verify_store, retry_strategy)
+            })
+            .buffer_unordered(BATCH_SIZE);
+        while let Some(result) = uploads.next().await {
+            result?;
+        }
+        Ok(())
+    }
+
     /// Used for testing
     pub async fn upload_test_bytes(&self, bytes: Bytes, verify: bool) -> Result<NetworkAddress> {
         let temp_dir = tempdir()?;
@@ -185,6 +301,16 @@ impl FilesApi {
     }
 }
 
+/// Reads the next `MAX_CHUNK_SIZE` bytes, or less if the end of the content gets reached.
+async fn read_piece(reader: &mut (impl AsyncRead + Unpin)) -> Result<Bytes> {
+    let mut piece = Vec::with_capacity(MAX_CHUNK_SIZE);
+    let _ = reader
+        .take(MAX_CHUNK_SIZE as u64)
+        .read_to_end(&mut piece)
+        .await?;
+    Ok(piece.into())
+}
+
 /// Encrypts a [`LargeFile`] and returns the resulting address and all chunk names.
 /// Correspondent encrypted chunks are written in the specified output folder.
 /// Does not store anything to the network.
@@ -9,6 +9,7 @@
 use crate::{
     chunks::{DataMapLevel, Error as ChunksError},
     error::{Error as ClientError, Result},
+    files::reader::FileReader,
     Client, FilesApi, BATCH_SIZE,
 };
 use bytes::Bytes;
@@ -18,7 +19,11 @@ use self_encryption::{decrypt_full_set, DataMap, EncryptedChunk, StreamSelfDecry
 use sn_networking::target_arch::Instant;
 use sn_protocol::storage::{Chunk, ChunkAddress, RetryStrategy};
 
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use tokio::sync::mpsc::{self};
 use xor_name::XorName;
 
@@ -193,6 +198,31 @@ impl FilesDownload {
         Ok(bytes)
     }
 
+    /// Open a reader over the file at `address`, implementing `AsyncRead` and `AsyncSeek`.
+    /// Only the data map gets fetched here, the chunks are fetched and decrypted as the bytes
+    /// covered by them get read.
+    pub async fn open_reader(&mut self, address: ChunkAddress) -> Result<FileReader> {
+        let head_chunk = self
+            .api
+            .client
+            .get_chunk(address, self.show_holders, Some(self.retry_strategy))
+            .await?;
+        let client = self.api.client.clone();
+
+        let reader = match self.unpack_chunk(head_chunk.clone()).await {
+            Ok(data_map) => FileReader::large_file(client, data_map),
+            Err(ClientError::Chunks(ChunksError::Deserialisation(_))) => {
+                warn!("Consider head chunk {address:?} as an SmallFile");
+                FileReader::small_file(client, head_chunk.value().clone())
+            }
+            Err(err) => {
+                error!("Encounter error when unpack head_chunk {address:?} : {err:?}");
+                return Err(err);
+            }
+        };
+        Ok(reader.set_retry_strategy(self.retry_strategy))
+    }
+
     /// Download a file from the network and get the decrypted bytes.
     /// If the data_map_chunk is not provided, the DataMap is fetched from the network using the provided address.
     pub async fn download_file(
@@ -233,6 +263,26 @@ impl FilesDownload {
         }
     }
 
+    /// Download the file shared at `share_address` into the `download_dir`, under the name it was shared with.
+    /// The share has to have been encrypted to the client's key, see `FilesApi::share_file`.
+    ///
+    /// Returns the path the file was written to.
+    pub async fn download_shared_file(
+        &mut self,
+        share_address: ChunkAddress,
+        download_dir: &Path,
+    ) -> Result<PathBuf> {
+        let shared_file = self.api.receive_shared_file(share_address).await?;
+        let path = download_dir.join(shared_file.file_name());
+        self.download_file_to_path(
+            *shared_file.address(),
+            Some(shared_file.data_map),
+            path.clone(),
+        )
+        .await?;
+        Ok(path)
+    }
+
     /// Download a file from the network.
     /// If you want to track the download progress, use the `get_events` method.
     async fn download_entire_file(
@@ -471,6 +521,23 @@ impl FilesDownload {
         }
     }
 
+    /// Returns the addresses of the chunks holding the contents of the file at `address`,
+    /// without downloading the contents. Only the data map chunks get fetched.
+    pub async fn chunk_addresses(&mut self, address: ChunkAddress) -> Result<Vec<ChunkAddress>> {
+        let chunk = self
+            .api
+            .client
+            .get_chunk(address, false, Some(self.retry_strategy))
+            .await?;
+        let data_map = self.unpack_chunk(chunk).await?;
+
+        Ok(data_map
+            .infos()
+            .into_iter()
+            .map(|info| ChunkAddress::new(info.dst_hash))
+            .collect())
+    }
+
     /// Extracts a file DataMapLevel from a chunk.
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
@@ -0,0 +1,319 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{chunks::Error as ChunksError, error::Result, Client};
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
+use self_encryption::{ChunkInfo, DataMap, EncryptedChunk};
+use sn_protocol::storage::{ChunkAddress, RetryStrategy};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+
+/// The number of chunks fetched ahead of the one being read, by default.
+pub const DEFAULT_READ_AHEAD_CHUNKS: usize = 4;
+
+type ChunkFetch = BoxFuture<'static, (usize, Result<Bytes>)>;
+
+enum Contents {
+    /// The file was small enough to be stored in the head chunk itself.
+    SmallFile(Bytes),
+    LargeFile {
+        data_map: Arc<DataMap>,
+        /// The start position of each chunk within the file.
+        offsets: Vec<usize>,
+    },
+}
+
+/// A reader over a file stored on the network, implementing `AsyncRead` and `AsyncSeek`.
+///
+/// Only the chunks covering the bytes being read get fetched and decrypted, with the next few
+/// chunks fetched ahead of time, so byte ranges of a large file can be read without downloading
+/// all of it. Use `FilesDownload::open_reader` to obtain one.
+pub struct FileReader {
+    client: Client,
+    retry_strategy: RetryStrategy,
+    read_ahead: usize,
+    contents: Contents,
+    file_size: usize,
+    position: usize,
+    /// The decrypted chunks within the read-ahead window, by index.
+    decrypted: BTreeMap<usize, Bytes>,
+    fetching: BTreeSet<usize>,
+    fetches: FuturesUnordered<ChunkFetch>,
+}
+
+impl FileReader {
+    pub(crate) fn small_file(client: Client, bytes: Bytes) -> Self {
+        Self::new(client, bytes.len(), Contents::SmallFile(bytes))
+    }
+
+    pub(crate) fn large_file(client: Client, data_map: DataMap) -> Self {
+        let offsets = chunk_offsets(&data_map);
+        let file_size = data_map.file_size();
+        let contents = Contents::LargeFile {
+            data_map: Arc::new(data_map),
+            offsets,
+        };
+        Self::new(client, file_size, contents)
+    }
+
+    fn new(client: Client, file_size: usize, contents: Contents) -> Self {
+        Self {
+            client,
+            retry_strategy: RetryStrategy::Quick,
+            read_ahead: DEFAULT_READ_AHEAD_CHUNKS,
+            contents,
+            file_size,
+            position: 0,
+            decrypted: Default::default(),
+            fetching: Default::default(),
+            fetches: Default::default(),
+        }
+    }
+
+    /// Sets the number of chunks fetched ahead of the one being read.
+    ///
+    /// By default, this option is set to the constant `DEFAULT_READ_AHEAD_CHUNKS: usize = 4`.
+    pub fn set_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    /// Sets the RetryStrategy used when fetching the chunks.
+    ///
+    /// By default, this option is set to RetryStrategy::Quick
+    pub fn set_retry_strategy(mut self, retry_strategy: RetryStrategy) -> Self {
+        self.retry_strategy = retry_strategy;
+        self
+    }
+
+    /// The size of the file, in bytes.
+    pub fn file_size(&self) -> usize {
+        self.file_size
+    }
+
+    /// Drops the decrypted chunks outside of the window starting at `index`, and starts fetching
+    /// the chunks of the window that are neither decrypted nor being fetched.
+    fn fill_window(&mut self, index: usize) {
+        let Contents::LargeFile { data_map, offsets } = &self.contents else {
+            return;
+        };
+        let window_end = (index + self.read_ahead + 1).min(offsets.len());
+        self.decrypted
+            .retain(|chunk_index, _| (index..window_end).contains(chunk_index));
+
+        for chunk_index in index..window_end {
+            if self.decrypted.contains_key(&chunk_index) || self.fetching.contains(&chunk_index) {
+                continue;
+            }
+            let Some(info) = data_map.infos().get(chunk_index).cloned() else {
+                continue;
+            };
+            let _ = self.fetching.insert(chunk_index);
+            let fetch = fetch_chunk(
+                self.client.clone(),
+                Arc::clone(data_map),
+                info,
+                self.retry_strategy,
+            );
+            self.fetches
+                .push(async move { (chunk_index, fetch.await) }.boxed());
+        }
+    }
+}
+
+impl AsyncRead for FileReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.position >= this.file_size || buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let (index, chunk_start) = match &this.contents {
+            Contents::SmallFile(bytes) => {
+                let end = (this.position + buf.remaining()).min(bytes.len());
+                buf.put_slice(&bytes[this.position..end]);
+                this.position = end;
+                return Poll::Ready(Ok(()));
+            }
+            Contents::LargeFile { offsets, .. } => {
+                let index = chunk_index(offsets, this.position);
+                (index, offsets[index])
+            }
+        };
+
+        this.fill_window(index);
+        while !this.decrypted.contains_key(&index) {
+            match this.fetches.poll_next_unpin(cx) {
+                Poll::Ready(Some((fetched_index, result))) => {
+                    let _ = this.fetching.remove(&fetched_index);
+                    match result {
+                        Ok(bytes) => {
+                            let _ = this.decrypted.insert(fetched_index, bytes);
+                        }
+                        Err(err) if fetched_index == index => {
+                            error!("Failed to fetch chunk {index} of the file: {err:?}");
+                            return Poll::Ready(Err(io::Error::other(err)));
+                        }
+                        // A failed read-ahead is retried once the chunk gets read.
+                        Err(err) => {
+                            warn!("Failed to fetch chunk {fetched_index} ahead of time: {err:?}");
+                        }
+                    }
+                }
+                // The chunk is being fetched if not decrypted already, this should not happen.
+                Poll::Ready(None) => {
+                    error!("Chunk {index} of the file is neither decrypted nor being fetched");
+                    return Poll::Ready(Err(io::Error::other("chunk not being fetched")));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let bytes = &this.decrypted[&index];
+        let start = this.position - chunk_start;
+        let end = (start + buf.remaining()).min(bytes.len());
+        buf.put_slice(&bytes[start..end]);
+        this.position += end - start;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for FileReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        let new_position = match position {
+            SeekFrom::Start(offset) => Some(offset as i128),
+            SeekFrom::End(offset) => Some(this.file_size as i128 + offset as i128),
+            SeekFrom::Current(offset) => Some(this.position as i128 + offset as i128),
+        }
+        .filter(|new_position| *new_position >= 0)
+        .and_then(|new_position| usize::try_from(new_position).ok());
+
+        match new_position {
+            Some(new_position) => {
+                this.position = new_position;
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.position as u64))
+    }
+}
+
+async fn fetch_chunk(
+    client: Client,
+    data_map: Arc<DataMap>,
+    info: ChunkInfo,
+    retry_strategy: RetryStrategy,
+) -> Result<Bytes> {
+    let chunk = client
+        .get_chunk(
+            ChunkAddress::new(info.dst_hash),
+            false,
+            Some(retry_strategy),
+        )
+        .await?;
+    let encrypted_chunk = EncryptedChunk {
+        index: info.index,
+        content: chunk.value,
+    };
+    decrypt_chunk(&data_map, encrypted_chunk, info.src_size)
+}
+
+/// Decrypts a single chunk of the file.
+fn decrypt_chunk(
+    data_map: &DataMap,
+    encrypted_chunk: EncryptedChunk,
+    src_size: usize,
+) -> Result<Bytes> {
+    let bytes = self_encryption::decrypt_range(data_map, &[encrypted_chunk], 0, src_size)
+        .map_err(ChunksError::SelfEncryption)?;
+    Ok(bytes)
+}
+
+/// The start position of each chunk within the file.
+fn chunk_offsets(data_map: &DataMap) -> Vec<usize> {
+    data_map
+        .infos()
+        .iter()
+        .scan(0, |offset, info| {
+            let start = *offset;
+            *offset += info.src_size;
+            Some(start)
+        })
+        .collect()
+}
+
+/// The index of the chunk holding the byte at `position`, which must be within the file.
+fn chunk_index(offsets: &[usize], position: usize) -> usize {
+    offsets
+        .partition_point(|offset| *offset <= position)
+        .saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn any_range_is_decrypted_from_the_chunks_covering_it() -> Result<()> {
+        let mut rng = thread_rng();
+        let content: Bytes = (0..5 * self_encryption::MAX_CHUNK_SIZE + 100)
+            .map(|_| rng.gen::<u8>())
+            .collect::<Vec<u8>>()
+            .into();
+        let (data_map, encrypted_chunks) = self_encryption::encrypt(content.clone())?;
+        let offsets = chunk_offsets(&data_map);
+        assert_eq!(offsets.len(), encrypted_chunks.len());
+
+        for _ in 0..20 {
+            let start = rng.gen_range(0..content.len());
+            let len = rng.gen_range(1..2 * self_encryption::MAX_CHUNK_SIZE);
+            let end = (start + len).min(content.len());
+
+            let mut read = Vec::new();
+            let mut position = start;
+            while position < end {
+                let index = chunk_index(&offsets, position);
+                let info = &data_map.infos()[index];
+                let encrypted_chunk = encrypted_chunks
+                    .iter()
+                    .find(|chunk| chunk.index == index)
+                    .cloned()
+                    .expect("a chunk for every index");
+                let bytes = decrypt_chunk(&data_map, encrypted_chunk, info.src_size)?;
+                let chunk_end = (end - offsets[index]).min(bytes.len());
+                read.extend_from_slice(&bytes[position - offsets[index]..chunk_end]);
+                position = offsets[index] + chunk_end;
+            }
+
+            assert_eq!(read.as_slice(), &content[start..end]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Error};
+use bls::{Ciphertext, PublicKey, SecretKey};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sn_protocol::storage::{Chunk, ChunkAddress};
+use std::{ffi::OsString, path::Path};
+
+/// A privately uploaded file shared with a recipient. It is stored on the network in a chunk,
+/// encrypted to the public key of the recipient, so that only they can get the data map of the
+/// file out of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SharedFile {
+    /// The name of the file, as given by the sender.
+    pub name: OsString,
+    /// The data map chunk of the file, which is not stored on the network.
+    pub data_map: Chunk,
+}
+
+impl SharedFile {
+    /// The address of the file, to download it from along with its data map chunk.
+    pub fn address(&self) -> &ChunkAddress {
+        self.data_map.address()
+    }
+
+    /// The name to save the file under, stripped of any directories in it, so that the sender
+    /// cannot have the file written outside of the download dir.
+    pub fn file_name(&self) -> OsString {
+        Path::new(&self.name)
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| OsString::from(self.address().to_hex()))
+    }
+
+    /// Encrypts the shared file to the `recipient`, returning the chunk to store it in.
+    pub(crate) fn encrypt(&self, recipient: &PublicKey) -> Result<Chunk> {
+        let serialised = rmp_serde::to_vec(self)?;
+        let cipher = recipient.encrypt(serialised.as_slice());
+        Ok(Chunk::new(Bytes::from(cipher.to_bytes())))
+    }
+
+    /// Decrypts the shared file stored in the `chunk` with the secret key of the recipient.
+    pub(crate) fn decrypt(chunk: &Chunk, secret_key: &SecretKey) -> Result<Self> {
+        let address = *chunk.address();
+        let cipher = Ciphertext::from_bytes(chunk.value())
+            .map_err(|_| Error::SharedFileDecryption(address))?;
+        let serialised = secret_key
+            .decrypt(&cipher)
+            .ok_or(Error::SharedFileDecryption(address))?;
+
+        // if this fails, it's either the wrong key or unexpected data
+        rmp_serde::from_slice(&serialised).map_err(|_| Error::SharedFileDecryption(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+
+    #[test]
+    fn only_the_recipient_can_decrypt_a_shared_file() -> Result<()> {
+        let recipient = SecretKey::random();
+        let shared_file = SharedFile {
+            name: OsString::from("../../report.pdf"),
+            data_map: Chunk::new(Bytes::from_static(b"a private data map")),
+        };
+
+        let chunk = shared_file.encrypt(&recipient.public_key())?;
+        let received = SharedFile::decrypt(&chunk, &recipient)?;
+        assert_eq!(received, shared_file);
+        assert_eq!(received.file_name(), OsString::from("report.pdf"));
+
+        assert!(matches!(
+            SharedFile::decrypt(&chunk, &SecretKey::random()),
+            Err(Error::SharedFileDecryption(_))
+        ));
+        Ok(())
+    }
+}
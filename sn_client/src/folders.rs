@@ -10,6 +10,7 @@ use super::{error::Result, Client, ClientRegister, WalletClient};
 use crate::{acc_packet::load_account_wallet_or_create_with_mnemonic, Error, FilesApi, UploadCfg};
 use bls::{Ciphertext, PublicKey};
 use bytes::{BufMut, BytesMut};
+use crdts::merkle_reg::MerkleReg;
 use self_encryption::MAX_CHUNK_SIZE;
 use serde::{Deserialize, Serialize};
 use sn_protocol::{
@@ -22,14 +23,34 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     ffi::OsString,
     path::{Path, PathBuf},
+    time::Duration,
 };
 use xor_name::{XorName, XOR_NAME_LEN};
 
-/// Folder Entry representing either a file or subfolder.
+/// Version of the Metadata format stored by this client. Metadata stored before the format was
+/// versioned is read as version 0, without any attributes.
+pub const METADATA_VERSION: u16 = 1;
+
+/// Folder Entry representing either a file, a subfolder, or a symbolic link.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum FolderEntry {
     File(Chunk),
     Folder(RegisterAddress),
+    /// A symbolic link, with the path it points to.
+    Symlink(String),
+}
+
+/// Attributes of a file/folder, as found on the disk it was stored from.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAttributes {
+    /// Size of the file, in bytes.
+    pub size: Option<u64>,
+    /// Last modification time, as the duration since the unix epoch.
+    pub modified: Option<Duration>,
+    /// Unix permission bits.
+    pub mode: Option<u32>,
+    /// MIME type of the file content.
+    pub content_type: Option<String>,
 }
 
 /// Metadata to be stored on a Chunk, linked from and belonging to Registers' entries.
@@ -37,6 +58,48 @@ pub enum FolderEntry {
 pub struct Metadata {
     pub name: String,
     pub content: FolderEntry,
+    // The fields below are appended to the original format, defaulting when they are missing,
+    // so Metadata stored by previous versions can still be read.
+    #[serde(default)]
+    pub version: u16,
+    #[serde(default)]
+    pub attributes: FileAttributes,
+}
+
+impl Metadata {
+    /// Create Metadata in the current format, without any attributes.
+    pub fn new(name: String, content: FolderEntry) -> Self {
+        Self {
+            name,
+            content,
+            version: METADATA_VERSION,
+            attributes: FileAttributes::default(),
+        }
+    }
+
+    /// Set the attributes of the file/folder.
+    pub fn with_attributes(mut self, attributes: FileAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+}
+
+/// A version of a Folder, i.e. an entry of its Register adding, replacing or removing an item.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FolderVersion {
+    pub entry_hash: EntryHash,
+    /// The previous versions of the item, i.e. the entries this one was written atop of.
+    pub previous: BTreeSet<EntryHash>,
+    /// The metadata chunk xorname and metadata of the item, or None if the item was removed.
+    pub item: Option<(XorName, Metadata)>,
+}
+
+/// A difference found for an item, by its name, between two versions of a Folder.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FolderItemDiff {
+    Added(Metadata),
+    Removed(Metadata),
+    Modified { from: Metadata, to: Metadata },
 }
 
 // This is the entry value used in Folders to mark a removed file/folder.
@@ -123,10 +186,10 @@ impl FoldersApi {
         encryption_pk: Option<PublicKey>,
     ) -> Result<(EntryHash, XorName, Metadata)> {
         // create metadata Chunk for this entry
-        let metadata = Metadata {
-            name: file_name.to_str().unwrap_or("unknown").to_string(),
-            content: FolderEntry::File(data_map_chunk),
-        };
+        let metadata = Metadata::new(
+            file_name.to_str().unwrap_or("unknown").to_string(),
+            FolderEntry::File(data_map_chunk),
+        );
 
         self.add_entry(metadata, &BTreeSet::default(), encryption_pk)
     }
@@ -140,14 +203,24 @@ impl FoldersApi {
         encryption_pk: Option<PublicKey>,
     ) -> Result<(EntryHash, XorName, Metadata)> {
         // create metadata Chunk for this entry
-        let metadata = Metadata {
-            name: folder_name.to_str().unwrap_or("unknown").to_string(),
-            content: FolderEntry::Folder(address),
-        };
+        let metadata = Metadata::new(
+            folder_name.to_str().unwrap_or("unknown").to_string(),
+            FolderEntry::Folder(address),
+        );
 
         self.add_entry(metadata, &BTreeSet::default(), encryption_pk)
     }
 
+    /// Add a file/folder/symlink item, with the given metadata, as entry of this Folder (locally).
+    /// The new item's metadata chunk will be encrypted if a key has been provided.
+    pub fn add_item(
+        &mut self,
+        metadata: Metadata,
+        encryption_pk: Option<PublicKey>,
+    ) -> Result<(EntryHash, XorName, Metadata)> {
+        self.add_entry(metadata, &BTreeSet::default(), encryption_pk)
+    }
+
     /// Replace an existing file with the provided one (locally).
     /// The new file's metadata chunk will be encrypted if a key has been provided.
     pub fn replace_file(
@@ -158,11 +231,22 @@ impl FoldersApi {
         encryption_pk: Option<PublicKey>,
     ) -> Result<(EntryHash, XorName, Metadata)> {
         // create metadata Chunk for this entry
-        let metadata = Metadata {
-            name: file_name.to_str().unwrap_or("unknown").to_string(),
-            content: FolderEntry::File(data_map_chunk),
-        };
+        let metadata = Metadata::new(
+            file_name.to_str().unwrap_or("unknown").to_string(),
+            FolderEntry::File(data_map_chunk),
+        );
+
+        self.replace_item(existing_entry, metadata, encryption_pk)
+    }
 
+    /// Replace an existing item with a file/folder/symlink item with the given metadata (locally).
+    /// The new item's metadata chunk will be encrypted if a key has been provided.
+    pub fn replace_item(
+        &mut self,
+        existing_entry: EntryHash,
+        metadata: Metadata,
+        encryption_pk: Option<PublicKey>,
+    ) -> Result<(EntryHash, XorName, Metadata)> {
         self.add_entry(
             metadata,
             &vec![existing_entry].into_iter().collect(),
@@ -227,6 +311,25 @@ impl FoldersApi {
             .any(|(hash, _)| hash == entry_hash)
     }
 
+    /// Returns true if another version of the file/folder item was written atop the given entry,
+    /// i.e. the item was replaced since that version of it, rather than just removed.
+    pub fn is_replaced(&self, entry_hash: &EntryHash) -> bool {
+        self.register
+            .merkle_reg()
+            .parents(entry_hash.0)
+            .values()
+            .any(|entry| xorname_from_entry(entry) != REMOVED_ENTRY_MARK)
+    }
+
+    /// Returns true if the file/folder item was removed atop the given entry.
+    pub fn is_removed(&self, entry_hash: &EntryHash) -> bool {
+        self.register
+            .merkle_reg()
+            .parents(entry_hash.0)
+            .values()
+            .any(|entry| xorname_from_entry(entry) == REMOVED_ENTRY_MARK)
+    }
+
     /// Find file/folder in this Folder by its name, returning metadata chunk xorname and metadata itself.
     pub fn find_by_name(&self, name: &str) -> Option<(&XorName, &Metadata)> {
         // let's get the list of metadata xornames of non-removed entries
@@ -251,46 +354,135 @@ impl FoldersApi {
     /// Returns the list of entries of this Folder, including their entry hash,
     /// metadata chunk xorname, and metadata itself.
     pub async fn entries(&mut self) -> Result<BTreeMap<EntryHash, (XorName, Metadata)>> {
-        let mut entries = BTreeMap::new();
-        for (entry_hash, entry) in self.register.read() {
-            let meta_xorname = xorname_from_entry(&entry);
-            if meta_xorname == REMOVED_ENTRY_MARK {
+        let heads = self
+            .register
+            .read()
+            .into_iter()
+            .map(|(entry_hash, _)| entry_hash)
+            .collect();
+        self.entries_of(heads).await
+    }
+
+    /// Returns all the versions of this Folder, each item's version coming after the versions
+    /// it replaced.
+    ///
+    /// Register entries carry no timestamp, hence the versions of different items are not
+    /// ordered by the time they were made, but by their entry hash, so the order is the same on
+    /// every replica of the Folder.
+    pub async fn history(&mut self) -> Result<Vec<FolderVersion>> {
+        let mut history = vec![];
+        for entry_hash in versions_order(self.register.merkle_reg()) {
+            let Some(node) = self.register.merkle_reg().node(entry_hash.0) else {
                 continue;
-            }
+            };
+            let previous = node.children.iter().copied().map(EntryHash).collect();
+            let meta_xorname = xorname_from_entry(&node.value);
+            let item = if meta_xorname == REMOVED_ENTRY_MARK {
+                None
+            } else {
+                let metadata = self.get_metadata(entry_hash, meta_xorname).await?;
+                Some((meta_xorname, metadata))
+            };
+            history.push(FolderVersion {
+                entry_hash,
+                previous,
+                item,
+            });
+        }
+        Ok(history)
+    }
+
+    /// Returns the list of entries this Folder had right after the given version was made,
+    /// i.e. the latest version of each item at or before the given one, as ordered by `history`.
+    /// The versions the given one replaced are never part of it.
+    pub async fn entries_at(
+        &mut self,
+        version: EntryHash,
+    ) -> Result<BTreeMap<EntryHash, (XorName, Metadata)>> {
+        let heads = heads_at(self.register.merkle_reg(), version)
+            .ok_or(Error::FolderVersionNotFound(version))?;
+        self.entries_of(heads).await
+    }
+
+    /// Returns the differences between the items of this Folder at two of its versions,
+    /// by the name of the items.
+    pub async fn diff(
+        &mut self,
+        from: EntryHash,
+        to: EntryHash,
+    ) -> Result<BTreeMap<String, FolderItemDiff>> {
+        let by_name = |entries: BTreeMap<EntryHash, (XorName, Metadata)>| {
+            entries
+                .into_values()
+                .map(|(_, metadata)| (metadata.name.clone(), metadata))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let mut from_items = by_name(self.entries_at(from).await?);
+        let to_items = by_name(self.entries_at(to).await?);
 
-            let metadata = match self.metadata.get(&meta_xorname) {
-                Some((metadata, _)) => metadata.clone(),
+        let mut diff = BTreeMap::new();
+        for (name, to_metadata) in to_items {
+            match from_items.remove(&name) {
                 None => {
-                    // retrieve metadata Chunk from network
-                    let chunk = self
-                        .client
-                        .get_chunk(ChunkAddress::new(meta_xorname), false, None)
-                        .await?;
-
-                    // let's first assume it's unencrypted
-                    let metadata: Metadata = match rmp_serde::from_slice(chunk.value()) {
-                        Ok(metadata) => metadata,
-                        Err(err) => {
-                            // let's try to decrypt it then
-                            let cipher = Ciphertext::from_bytes(chunk.value()).map_err(|_| err)?;
-                            let data = self
-                                .client
-                                .signer()
-                                .decrypt(&cipher)
-                                .ok_or(Error::FolderEntryDecryption(entry_hash))?;
-
-                            // if this fails, it's either the wrong key or unexpected data
-                            rmp_serde::from_slice(&data)
-                                .map_err(|_| Error::FolderEntryDecryption(entry_hash))?
-                        }
+                    diff.insert(name, FolderItemDiff::Added(to_metadata));
+                }
+                Some(from_metadata) if from_metadata != to_metadata => {
+                    let modified = FolderItemDiff::Modified {
+                        from: from_metadata,
+                        to: to_metadata,
                     };
-                    self.metadata.insert(meta_xorname, (metadata.clone(), None));
-                    metadata
+                    diff.insert(name, modified);
                 }
-            };
-            entries.insert(entry_hash, (meta_xorname, metadata));
+                Some(_) => {}
+            }
         }
-        Ok(entries)
+        for (name, from_metadata) in from_items {
+            diff.insert(name, FolderItemDiff::Removed(from_metadata));
+        }
+        Ok(diff)
+    }
+
+    /// Restore an item of this Folder to the given older version of it (locally).
+    /// The version is written atop the current versions of the item, including any item
+    /// added since with the same name. Its metadata chunk is already stored on the network,
+    /// thus nothing but the Folder needs to be synced afterwards.
+    pub async fn restore(&mut self, version: EntryHash) -> Result<(EntryHash, XorName, Metadata)> {
+        let node = self
+            .register
+            .merkle_reg()
+            .node(version.0)
+            .ok_or(Error::FolderVersionNotFound(version))?;
+        let meta_xorname = xorname_from_entry(&node.value);
+        if meta_xorname == REMOVED_ENTRY_MARK {
+            return Err(Error::FolderVersionIsRemoval(version));
+        }
+        let metadata = self.get_metadata(version, meta_xorname).await?;
+
+        let current_entries = self.entries().await?;
+        if current_entries.contains_key(&version) {
+            // it's already the current version of the item
+            return Ok((version, meta_xorname, metadata));
+        }
+
+        // the current versions of the item are the heads among the versions made atop of it
+        let mut children = BTreeSet::new();
+        let mut to_visit = vec![version.0];
+        while let Some(hash) = to_visit.pop() {
+            let successors = self.register.merkle_reg().parents(hash).hashes();
+            if successors.is_empty() {
+                let _ = children.insert(EntryHash(hash));
+            }
+            to_visit.extend(successors);
+        }
+        children.extend(
+            current_entries
+                .into_iter()
+                .filter(|(_, (_, current))| current.name == metadata.name)
+                .map(|(entry_hash, _)| entry_hash),
+        );
+
+        let entry_hash = self.register.write_atop(&meta_xorname, &children)?;
+        Ok((entry_hash, meta_xorname, metadata))
     }
 
     // Private helpers
@@ -308,6 +500,63 @@ impl FoldersApi {
         })
     }
 
+    // Returns the entries for the given Register entry hashes, skipping removed items.
+    async fn entries_of(
+        &mut self,
+        entry_hashes: BTreeSet<EntryHash>,
+    ) -> Result<BTreeMap<EntryHash, (XorName, Metadata)>> {
+        let mut entries = BTreeMap::new();
+        for entry_hash in entry_hashes {
+            let entry = self.register.get(entry_hash)?;
+            let meta_xorname = xorname_from_entry(entry);
+            if meta_xorname == REMOVED_ENTRY_MARK {
+                continue;
+            }
+
+            let metadata = self.get_metadata(entry_hash, meta_xorname).await?;
+            entries.insert(entry_hash, (meta_xorname, metadata));
+        }
+        Ok(entries)
+    }
+
+    // Get the metadata of an entry from the local cache, or else retrieve it from the network,
+    // decrypting it if it was stored encrypted.
+    async fn get_metadata(
+        &mut self,
+        entry_hash: EntryHash,
+        meta_xorname: XorName,
+    ) -> Result<Metadata> {
+        if let Some((metadata, _)) = self.metadata.get(&meta_xorname) {
+            return Ok(metadata.clone());
+        }
+
+        // retrieve metadata Chunk from network
+        let chunk = self
+            .client
+            .get_chunk(ChunkAddress::new(meta_xorname), false, None)
+            .await?;
+
+        // let's first assume it's unencrypted
+        let metadata: Metadata = match rmp_serde::from_slice(chunk.value()) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                // let's try to decrypt it then
+                let cipher = Ciphertext::from_bytes(chunk.value()).map_err(|_| err)?;
+                let data = self
+                    .client
+                    .signer()
+                    .decrypt(&cipher)
+                    .ok_or(Error::FolderEntryDecryption(entry_hash))?;
+
+                // if this fails, it's either the wrong key or unexpected data
+                rmp_serde::from_slice(&data)
+                    .map_err(|_| Error::FolderEntryDecryption(entry_hash))?
+            }
+        };
+        self.metadata.insert(meta_xorname, (metadata.clone(), None));
+        Ok(metadata)
+    }
+
     // Add the given entry to the underlying Register as well as creating the metadata Chunk.
     // If an encryption key is given, the metadata chunk will be encrpyted with it.
     fn add_entry(
@@ -338,9 +587,148 @@ impl FoldersApi {
     }
 }
 
+// Order all the entries of the Register so each of them comes after the entries it was written
+// atop of, breaking ties by entry hash so the order is the same on every replica.
+fn versions_order(reg: &MerkleReg<Entry>) -> Vec<EntryHash> {
+    let mut pending: BTreeMap<EntryHash, usize> = reg
+        .all_nodes()
+        .map(|node| {
+            let present = node
+                .children
+                .iter()
+                .filter(|child| reg.node(**child).is_some())
+                .count();
+            (EntryHash(node.hash()), present)
+        })
+        .collect();
+    let mut ready: BTreeSet<EntryHash> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(entry_hash, _)| *entry_hash)
+        .collect();
+
+    let mut order = vec![];
+    while let Some(entry_hash) = ready.pop_first() {
+        order.push(entry_hash);
+        for successor in reg.parents(entry_hash.0).hashes() {
+            let successor = EntryHash(successor);
+            if let Some(count) = pending.get_mut(&successor) {
+                *count -= 1;
+                if *count == 0 {
+                    let _ = ready.insert(successor);
+                }
+            }
+        }
+    }
+    order
+}
+
+// The heads of the Register right after the given version, as ordered by `versions_order`, i.e.
+// the latest version of each item at or before the given one. The versions the given one was
+// written atop of, recursively, always come before it, hence they are never among the heads.
+fn heads_at(reg: &MerkleReg<Entry>, version: EntryHash) -> Option<BTreeSet<EntryHash>> {
+    let order = versions_order(reg);
+    let position = order.iter().position(|entry_hash| *entry_hash == version)?;
+    let mut heads: BTreeSet<EntryHash> = order[..=position].iter().copied().collect();
+    for entry_hash in &order[..=position] {
+        if let Some(node) = reg.node(entry_hash.0) {
+            for child in &node.children {
+                let _ = heads.remove(&EntryHash(*child));
+            }
+        }
+    }
+    Some(heads)
+}
+
 // Helper to convert a Register/Folder entry into a XorName
 fn xorname_from_entry(entry: &Entry) -> XorName {
     let mut xorname = [0; XOR_NAME_LEN];
     xorname.copy_from_slice(entry);
     XorName(xorname)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use crdts::CmRDT;
+    use eyre::Result;
+
+    #[test]
+    fn metadata_stored_before_versioning_can_be_read() -> Result<()> {
+        // the format Metadata was stored in before it was versioned
+        #[derive(Serialize)]
+        struct MetadataV0 {
+            name: String,
+            content: FolderEntry,
+        }
+        let content = FolderEntry::File(Chunk::new(Bytes::from_static(b"a data map")));
+        let stored = rmp_serde::to_vec(&MetadataV0 {
+            name: "file.txt".to_string(),
+            content: content.clone(),
+        })?;
+
+        let metadata: Metadata = rmp_serde::from_slice(&stored)?;
+        assert_eq!(metadata.name, "file.txt");
+        assert_eq!(metadata.content, content);
+        assert_eq!(metadata.version, 0);
+        assert_eq!(metadata.attributes, FileAttributes::default());
+
+        let metadata =
+            Metadata::new("file.txt".to_string(), content).with_attributes(FileAttributes {
+                size: Some(10),
+                modified: Some(Duration::from_secs(1_700_000_000)),
+                mode: Some(0o640),
+                content_type: Some("text/plain".to_string()),
+            });
+        let stored = rmp_serde::to_vec(&metadata)?;
+        assert_eq!(rmp_serde::from_slice::<Metadata>(&stored)?, metadata);
+        Ok(())
+    }
+
+    #[test]
+    fn versions_come_after_the_versions_they_replace() {
+        let mut rng = rand::thread_rng();
+        let mut reg = MerkleReg::<Entry>::new();
+        let mut write = |value: XorName, children: &[EntryHash]| {
+            let children = children.iter().map(|entry_hash| entry_hash.0).collect();
+            let node = reg.write(value.to_vec(), children);
+            let entry_hash = EntryHash(node.hash());
+            reg.apply(node);
+            entry_hash
+        };
+
+        let file_v1 = write(XorName::random(&mut rng), &[]);
+        let other_file = write(XorName::random(&mut rng), &[]);
+        let file_v2 = write(XorName::random(&mut rng), &[file_v1]);
+        let removal = write(REMOVED_ENTRY_MARK, &[other_file]);
+        let file_v3 = write(XorName::random(&mut rng), &[file_v2]);
+
+        let order = versions_order(&reg);
+        let position = |entry_hash| order.iter().position(|e| *e == entry_hash);
+        assert_eq!(order.len(), 5);
+        assert!(position(file_v1) < position(file_v2));
+        assert!(position(file_v2) < position(file_v3));
+        assert!(position(other_file) < position(removal));
+
+        let heads: BTreeSet<_> = reg.read().hashes().into_iter().map(EntryHash).collect();
+        assert_eq!(heads_at(&reg, order[4]), Some(heads));
+        assert_eq!(heads_at(&reg, order[0]), Some(BTreeSet::from([order[0]])));
+        // each item is at its latest version at or before the given one
+        for (i, version) in order.iter().enumerate() {
+            let heads = heads_at(&reg, *version).unwrap_or_default();
+            assert!(heads.contains(version));
+            assert!(heads.iter().all(|head| order[..=i].contains(head)));
+            let at_or_before = |entry_hash| position(entry_hash) <= Some(i);
+            assert_eq!(
+                heads.contains(&file_v2),
+                at_or_before(file_v2) && !at_or_before(file_v3)
+            );
+            assert_eq!(
+                heads.contains(&other_file),
+                at_or_before(other_file) && !at_or_before(removal)
+            );
+        }
+        assert_eq!(heads_at(&reg, EntryHash::default()), None);
+    }
+}
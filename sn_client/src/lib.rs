@@ -19,6 +19,7 @@ mod faucet;
 mod files;
 mod folders;
 mod register;
+mod register_map;
 mod uploader;
 mod wallet;
 
@@ -37,16 +38,22 @@ pub use sn_transfers as transfers;
 const MAX_CONCURRENT_TASKS: usize = 4096;
 
 pub use self::{
-    audit::{DagError, SpendDag, SpendDagGet, SpendFault},
+    audit::{DagError, DoubleSpendTaint, SpendDag, SpendDagGet, SpendFault, TaintReport},
     error::Error,
     event::{ClientEvent, ClientEventsBroadcaster, ClientEventsReceiver},
     faucet::fund_faucet_from_genesis_wallet,
     files::{
         download::{FilesDownload, FilesDownloadEvent},
+        reader::{FileReader, DEFAULT_READ_AHEAD_CHUNKS},
+        share::SharedFile,
         FilesApi, BATCH_SIZE,
     },
-    folders::{FolderEntry, FoldersApi, Metadata},
+    folders::{
+        FileAttributes, FolderEntry, FolderItemDiff, FolderVersion, FoldersApi, Metadata,
+        METADATA_VERSION,
+    },
     register::ClientRegister,
+    register_map::RegisterMap,
     uploader::{UploadCfg, UploadEvent, UploadSummary, Uploader},
     wallet::{broadcast_signed_spends, send, StoragePaymentResult, WalletClient},
 };
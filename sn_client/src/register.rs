@@ -13,14 +13,20 @@ use libp2p::{
     kad::{Quorum, Record},
     PeerId,
 };
-use sn_networking::{GetRecordCfg, PutRecordCfg, VerificationKind};
+use sn_networking::{
+    sleep, GetRecordCfg, PutRecordCfg, RecordFetchStrategy, VerificationKind,
+    MIN_WAIT_BEFORE_READING_A_PUT,
+};
 use sn_protocol::{
     error::Error as ProtocolError,
     messages::RegisterCmd,
     storage::{try_serialize_record, RecordKind, RetryStrategy},
     NetworkAddress,
 };
-use sn_registers::{Entry, EntryHash, Permissions, Register, RegisterAddress, SignedRegister};
+use sn_registers::{
+    Entry, EntryHash, Permissions, PermissionsChange, Register, RegisterAddress, RegisterOp,
+    SignedRegister,
+};
 use sn_transfers::{NanoTokens, Payment};
 use std::collections::{BTreeSet, HashSet, LinkedList};
 use xor_name::XorName;
@@ -156,6 +162,25 @@ impl ClientRegister {
         Ok((reg, storage_cost, royalties_fees))
     }
 
+    /// Rebuild a Register from its replica and the operations cached on it, as journaled by the
+    /// `Uploader`.
+    pub(crate) fn from_cached_ops(
+        client: Client,
+        register: Register,
+        ops: impl IntoIterator<Item = RegisterCmd>,
+    ) -> Self {
+        Self {
+            client,
+            register,
+            ops: ops.into_iter().collect(),
+        }
+    }
+
+    /// The operations cached on this Register, not yet pushed to the network.
+    pub(crate) fn cached_ops(&self) -> impl Iterator<Item = &RegisterCmd> {
+        self.ops.iter()
+    }
+
     /// Retrieve a Register from the network to work on it offline.
     pub(super) async fn retrieve(client: Client, address: RegisterAddress) -> Result<Self> {
         let register = Self::get_register_from_network(&client, address).await?;
@@ -271,6 +296,14 @@ impl ClientRegister {
         self.register.permissions()
     }
 
+    /// Return the current permissions of the register, i.e. the ones it was created with
+    /// after applying all the changes made to them, e.g. when revoking a writer.
+    ///
+    /// Return type: [Permissions]
+    pub fn current_permissions(&self) -> Permissions {
+        self.register.current_permissions()
+    }
+
     /// Return the number of items held in the register.
     ///
     /// Return type: u64
@@ -450,6 +483,93 @@ impl ClientRegister {
         Ok(entry_hash)
     }
 
+    /// Change the permissions of the Register, e.g. to revoke a writer.
+    /// Only the owner can change the permissions. The entries written by a revoked writer
+    /// before this change was made are kept, the ones it writes afterwards are ignored.
+    ///
+    /// # Arguments
+    /// * 'change' - [PermissionsChange]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use sn_registers::PermissionsChange;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let address = XorName::random(&mut rng);
+    /// let mut mutable_register = ClientRegister::create(client.clone(), address);
+    /// let leaked_key = SecretKey::random().public_key();
+    /// mutable_register.change_permissions(PermissionsChange::RemoveWriter(leaked_key))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn change_permissions(&mut self, change: PermissionsChange) -> Result<()> {
+        let op = self
+            .register
+            .change_permissions(change, self.client.signer())?;
+        self.ops.push_front(RegisterCmd::EditPermissions(op));
+        Ok(())
+    }
+
+    /// Transfer the ownership of the Register to a new owner, e.g. when rotating the owner's key.
+    /// Once transferred, only the new owner can change the permissions, and the current owner
+    /// can no longer write, unless the new owner adds it back as a writer.
+    /// The address of the Register remains the one it was created with.
+    ///
+    /// # Arguments
+    /// * 'new_owner' - [PublicKey]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let address = XorName::random(&mut rng);
+    /// let mut mutable_register = ClientRegister::create(client.clone(), address);
+    /// let new_owner = SecretKey::random().public_key();
+    /// mutable_register.transfer_ownership(new_owner)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transfer_ownership(&mut self, new_owner: PublicKey) -> Result<()> {
+        self.change_permissions(PermissionsChange::TransferOwnership(new_owner))
+    }
+
+    /// Make a checkpoint of the Register, collapsing its history below its latest entries so the
+    /// replicas on the network no longer hold it. Only the owner can make a checkpoint.
+    /// Entries written by others atop the collapsed history and not yet synced are dropped,
+    /// hence the Register should be synced before making a checkpoint.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use sn_client::{Client, ClientRegister, Error};
+    /// # use bls::SecretKey;
+    /// # use xor_name::XorName;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(),Error>{
+    /// let mut rng = rand::thread_rng();
+    /// let client = Client::new(SecretKey::random(), None, None, None).await?;
+    /// let address = XorName::random(&mut rng);
+    /// let mut mutable_register = ClientRegister::create(client.clone(), address);
+    /// mutable_register.write_merging_branches(b"Register entry")?;
+    /// mutable_register.checkpoint()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let op = self.register.checkpoint(self.client.signer())?;
+        self.ops.push_front(RegisterCmd::Checkpoint(op));
+        Ok(())
+    }
+
     // ********* Online methods  *********
 
     /// Sync this Register with the replicas on the network.
@@ -587,16 +707,44 @@ impl ClientRegister {
 
             // TODO: send them all concurrently
             while let Some(cmd) = self.ops.pop_back() {
-                // We don't need to send the payment proofs here since
-                // these are all Register mutation cmds which don't require payment.
-                let result =
+                // There is no need to send the ops collapsed by a checkpoint, the checkpoint holds
+                // the latest entries.
+                if matches!(&cmd, RegisterCmd::Edit(op) if self.register.is_collapsed(op)) {
+                    continue;
+                }
+
+                // Consecutive edits are sent at once, as a delta over the Register held by the
+                // nodes, rather than sending the whole Register for each of them.
+                let result = if let RegisterCmd::Edit(op) = cmd {
+                    let mut ops = vec![op];
+                    while let Some(RegisterCmd::Edit(op)) = self.ops.back() {
+                        if !self.register.is_collapsed(op) {
+                            ops.push(op.clone());
+                        }
+                        let _ = self.ops.pop_back();
+                    }
+                    Self::publish_register_ops(
+                        self.client.clone(),
+                        address,
+                        ops.iter().cloned().collect(),
+                        verify_store,
+                    )
+                    .await
+                    .map_err(|err| (err, ops.into_iter().map(RegisterCmd::Edit).collect()))
+                } else {
+                    // We don't need to send the payment proofs here since
+                    // these are all Register mutation cmds which don't require payment.
                     Self::publish_register(self.client.clone(), cmd.clone(), None, verify_store)
-                        .await;
+                        .await
+                        .map_err(|err| (err, vec![cmd]))
+                };
 
-                if let Err(err) = result {
-                    warn!("Did not push Register cmd on all nodes in the close group!: {err}");
-                    // We keep the cmd for next sync to retry
-                    self.ops.push_back(cmd);
+                if let Err((err, cmds)) = result {
+                    warn!("Did not push Register cmds on all nodes in the close group!: {err}");
+                    // We keep the cmds for next sync to retry, in the order they were made
+                    for cmd in cmds.into_iter().rev() {
+                        self.ops.push_back(cmd);
+                    }
                     return Err(err);
                 }
             }
@@ -791,8 +939,29 @@ impl ClientRegister {
                 reg.add_op(op)?;
                 reg
             }
+            RegisterCmd::EditPermissions(op) => {
+                let mut reg = network_reg?;
+                reg.add_permissions_op(op)?;
+                reg
+            }
+            RegisterCmd::Checkpoint(op) => {
+                let mut reg = network_reg?;
+                reg.add_checkpoint_op(op)?;
+                reg
+            }
         };
 
+        Self::put_signed_register(client, register, payment, verify_store).await
+    }
+
+    // Private helper to put the whole SignedRegister on the network, along with the payment for
+    // storing it if it's a new one.
+    async fn put_signed_register(
+        client: Client,
+        register: SignedRegister,
+        payment: Option<(Payment, PeerId)>,
+        verify_store: bool,
+    ) -> Result<()> {
         let network_address = NetworkAddress::from_register_address(*register.address());
         let key = network_address.to_record_key();
         let (record, payee) = match payment {
@@ -846,6 +1015,7 @@ impl ClientRegister {
             retry_strategy: Some(RetryStrategy::Balanced),
             target_record: record_to_verify,
             expected_holders,
+            fetch_strategy: RecordFetchStrategy::Kademlia,
         };
         let put_cfg = PutRecordCfg {
             put_quorum: Quorum::All,
@@ -858,6 +1028,57 @@ impl ClientRegister {
         Ok(client.network.put_record(record, &put_cfg).await?)
     }
 
+    /// Publish `RegisterOp`s on the network, sending only the ops rather than the whole Register,
+    /// which the nodes apply atop the Register they hold.
+    /// If `verify_store` is true, it will verify the ops were applied, publishing the Register
+    /// fetched to verify them with all the ops added instead if they were not, e.g. as they were
+    /// written atop entries some of the nodes don't hold. Otherwise, the nodes rejecting the ops
+    /// go unnoticed.
+    pub(crate) async fn publish_register_ops(
+        client: Client,
+        address: RegisterAddress,
+        ops: BTreeSet<RegisterOp>,
+        verify_store: bool,
+    ) -> Result<()> {
+        debug!("Publishing {} ops for Register: {address:?}", ops.len());
+        let record = Record {
+            key: NetworkAddress::from_register_address(address).to_record_key(),
+            value: try_serialize_record(&(address, &ops), RecordKind::RegisterOps)?.to_vec(),
+            publisher: None,
+            expires: None,
+        };
+        // The record isn't stored as is, hence cannot be verified by fetching it back
+        let put_cfg = PutRecordCfg {
+            put_quorum: Quorum::All,
+            retry_strategy: Some(RetryStrategy::Balanced),
+            use_put_record_to: None,
+            verification: None,
+        };
+        client.network.put_record(record, &put_cfg).await?;
+        if !verify_store {
+            return Ok(());
+        }
+
+        // Small wait before we attempt to verify, giving the nodes time to apply the ops
+        sleep(MIN_WAIT_BEFORE_READING_A_PUT).await;
+        let mut register = match client.get_signed_register_from_network(address, true).await {
+            Ok(register) if ops.iter().all(|op| register.contains_op(op)) => return Ok(()),
+            Ok(register) => register,
+            Err(err) => {
+                debug!("Failed to get Register {address:?} to verify ops were applied: {err:?}");
+                client
+                    .get_signed_register_from_network(address, false)
+                    .await?
+            }
+        };
+
+        warn!("Ops were not applied to Register {address:?}, publishing the whole Register");
+        for op in ops {
+            register.add_op(op)?;
+        }
+        Self::put_signed_register(client, register, None, verify_store).await
+    }
+
     /// Retrieve a `Register` from the Network.
     async fn get_register_from_network(
         client: &Client,
@@ -0,0 +1,543 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    error::{Error, Result},
+    Client, ClientRegister, WalletClient,
+};
+use crate::{acc_packet::load_account_wallet_or_create_with_mnemonic, FilesApi, UploadCfg};
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sn_protocol::{
+    storage::{Chunk, ChunkAddress, RegisterAddress},
+    NetworkAddress,
+};
+use sn_registers::{Entry, EntryHash, MAX_REG_ENTRY_SIZE};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+use xor_name::XorName;
+
+/// The value set to a key by an entry of the Register.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum StoredValue {
+    /// The serialised value, when small enough to fit in the entry along with its key.
+    Inline(Vec<u8>),
+    /// The name of the Chunk holding the serialised value.
+    Chunk(XorName),
+    /// The key was removed.
+    Removed,
+}
+
+/// An entry of the Register, setting the value of a key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct MapEntry {
+    key: Vec<u8>,
+    value: StoredValue,
+}
+
+/// The values of each key, i.e. the entries setting them which no other entry was written atop
+/// of, by the serialised key and the entry hash.
+type KeysValues = BTreeMap<Vec<u8>, BTreeMap<EntryHash, StoredValue>>;
+
+/// A map of keys to values stored in a Register, each entry of the Register setting the value of
+/// a key, or removing it, atop the entries which set it before.
+///
+/// Concurrent writers can set different values to the same key, which are all kept until a
+/// value is set atop of them. The latest value of a key is then the one set by the entry with the
+/// highest hash, hence it is the same on every replica of the map. `get_all` returns them all.
+///
+/// Keys and values are serialised with msgpack, keys having to serialise to the same bytes each
+/// time. Values too large to fit in a Register entry are stored in a Chunk instead.
+///
+/// Every insertion or removal adds an entry to the Register, which holds at most 1024 entries,
+/// `Error::RegisterMapFull` being returned once they are reached. The owner can then compact the
+/// map with `checkpoint`, which only keeps the entries setting the latest values of the keys.
+#[derive(Clone)]
+pub struct RegisterMap<K, V> {
+    client: Client,
+    wallet_dir: PathBuf,
+    register: ClientRegister,
+    files_api: FilesApi,
+    // Cache of the values stored in Chunks. We keep the Chunk itself till we upload it to the network.
+    chunks: BTreeMap<XorName, (Bytes, Option<Chunk>)>,
+    types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> RegisterMap<K, V>
+where
+    K: Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Create RegisterMap instance.
+    pub fn new(
+        client: Client,
+        wallet_dir: &Path,
+        address: Option<RegisterAddress>,
+    ) -> Result<Self> {
+        let register = if let Some(addr) = address {
+            ClientRegister::create_with_addr(client.clone(), addr)
+        } else {
+            let mut rng = rand::thread_rng();
+            ClientRegister::create(client.clone(), XorName::random(&mut rng))
+        };
+
+        Ok(Self::create(client, wallet_dir, register))
+    }
+
+    /// Download a copy of the RegisterMap from the network.
+    pub async fn retrieve(
+        client: Client,
+        wallet_dir: &Path,
+        address: RegisterAddress,
+    ) -> Result<Self> {
+        let register = ClientRegister::retrieve(client.clone(), address).await?;
+        Ok(Self::create(client, wallet_dir, register))
+    }
+
+    /// Clones the register instance. Any change made to one instance will not be reflected on the other register.
+    pub fn register(&self) -> ClientRegister {
+        self.register.clone()
+    }
+
+    /// Return the address of the RegisterMap (Register address) on the network
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Return the address of the RegisterMap (Register address) as a NetworkAddress
+    pub fn as_net_addr(&self) -> NetworkAddress {
+        NetworkAddress::RegisterAddress(*self.address())
+    }
+
+    /// Return the list of the addresses of the Chunks holding values, that need to be payed for
+    /// in order to be able to then store all data on the network upon calling `sync` method.
+    #[allow(clippy::mutable_key_type)]
+    pub fn chunk_addrs_to_pay(&self) -> BTreeSet<NetworkAddress> {
+        self.chunks
+            .iter()
+            .filter_map(|(xorname, (_, chunk))| {
+                chunk
+                    .as_ref()
+                    .map(|_| NetworkAddress::ChunkAddress(ChunkAddress::new(*xorname)))
+            })
+            .collect()
+    }
+
+    /// Returns the latest value of the key, or None if it's not set or was removed.
+    pub async fn get(&mut self, key: &K) -> Result<Option<V>> {
+        let key = rmp_serde::to_vec(key)?;
+        let values = keys_values(&self.register.read())
+            .remove(&key)
+            .unwrap_or_default();
+        match latest(&values) {
+            Some(value) => self.value_of(value).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Returns all the values set to the key concurrently, the latest value first.
+    /// The concurrent removals of the key are left out.
+    pub async fn get_all(&mut self, key: &K) -> Result<Vec<V>> {
+        let key = rmp_serde::to_vec(key)?;
+        let values = keys_values(&self.register.read())
+            .remove(&key)
+            .unwrap_or_default();
+
+        let mut all = vec![];
+        for value in values.values().rev() {
+            if let Some(value) = self.value_of(value).await? {
+                all.push(value);
+            }
+        }
+        Ok(all)
+    }
+
+    /// Returns true if the key is set and wasn't removed.
+    pub fn contains_key(&self, key: &K) -> Result<bool> {
+        let key = rmp_serde::to_vec(key)?;
+        Ok(keys_values(&self.register.read())
+            .get(&key)
+            .and_then(latest)
+            .is_some_and(|value| value != &StoredValue::Removed))
+    }
+
+    /// Returns the keys which are set, along with their latest value, ordered by their
+    /// serialised bytes.
+    pub async fn iter(&mut self) -> Result<Vec<(K, V)>> {
+        let mut entries = vec![];
+        for (key, values) in keys_values(&self.register.read()) {
+            let Some(value) = latest(&values) else {
+                continue;
+            };
+            if let Some(value) = self.value_of(value).await? {
+                entries.push((rmp_serde::from_slice(&key)?, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Set the value of the key (locally), atop all its current values.
+    /// The value is stored in a Chunk if it's too large to fit in the Register entry.
+    pub fn insert(&mut self, key: &K, value: &V) -> Result<EntryHash> {
+        let key = rmp_serde::to_vec(key)?;
+        let value = Bytes::from(rmp_serde::to_vec(value)?);
+        let (entry, chunk) = encode_entry(key.clone(), value.clone())?;
+        if let Some(chunk) = chunk {
+            self.chunks.insert(*chunk.name(), (value, Some(chunk)));
+        }
+        self.write_atop_key(&key, &entry)
+    }
+
+    /// Remove the key (locally), atop all its current values.
+    /// Returns None if the key wasn't set or was already removed.
+    pub fn remove(&mut self, key: &K) -> Result<Option<EntryHash>> {
+        let key = rmp_serde::to_vec(key)?;
+        let is_set = keys_values(&self.register.read())
+            .get(&key)
+            .is_some_and(|values| values.values().any(|value| value != &StoredValue::Removed));
+        if !is_set {
+            return Ok(None);
+        }
+
+        let entry = rmp_serde::to_vec(&MapEntry {
+            key: key.clone(),
+            value: StoredValue::Removed,
+        })?;
+        self.write_atop_key(&key, &entry).map(Some)
+    }
+
+    /// Compact the map (locally), dropping the entries which set the past values of the keys,
+    /// so that new ones can be written once the map holds the maximum number of entries.
+    /// Only the owner can compact the map, which should be synced before doing so, as the
+    /// entries written by others atop the dropped ones and not yet synced are dropped as well.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        self.register.checkpoint()
+    }
+
+    /// Sync local RegisterMap with the network.
+    pub async fn sync(&mut self, upload_cfg: UploadCfg) -> Result<()> {
+        let mut wallet_client = self.wallet()?;
+
+        // First upload any newly created value chunk, which is kept till it's uploaded
+        for (_, value_chunk) in self.chunks.values_mut() {
+            if let Some(chunk) = value_chunk {
+                self.files_api
+                    .get_local_payment_and_upload_chunk(
+                        chunk.clone(),
+                        upload_cfg.verify_store,
+                        Some(upload_cfg.retry_strategy),
+                    )
+                    .await?;
+                *value_chunk = None;
+            }
+        }
+
+        // the Register is paid for when syncing if it's not found on the network
+        let payment_info = wallet_client
+            .get_recent_payment_for_addr(&self.as_net_addr())
+            .ok();
+
+        self.register
+            .sync(&mut wallet_client, upload_cfg.verify_store, payment_info)
+            .await?;
+
+        Ok(())
+    }
+
+    // Private helpers
+
+    // Create a new RegisterMap instance with given register.
+    fn create(client: Client, wallet_dir: &Path, register: ClientRegister) -> Self {
+        let files_api = FilesApi::new(client.clone(), wallet_dir.to_path_buf());
+
+        Self {
+            client,
+            wallet_dir: wallet_dir.to_path_buf(),
+            register,
+            files_api,
+            chunks: BTreeMap::new(),
+            types: PhantomData,
+        }
+    }
+
+    // Create a new WalletClient from the directory set.
+    fn wallet(&self) -> Result<WalletClient> {
+        let wallet = load_account_wallet_or_create_with_mnemonic(&self.wallet_dir, None)?;
+        Ok(WalletClient::new(self.client.clone(), wallet))
+    }
+
+    // Write the entry to the underlying Register atop all the current values of the key.
+    fn write_atop_key(&mut self, key: &[u8], entry: &[u8]) -> Result<EntryHash> {
+        let children = keys_values(&self.register.read())
+            .remove(key)
+            .map(|values| values.into_keys().collect())
+            .unwrap_or_default();
+        self.register
+            .write_atop(entry, &children)
+            .map_err(|err| match err {
+                Error::Register(sn_registers::Error::TooManyEntries(size)) => {
+                    Error::RegisterMapFull(size)
+                }
+                err => err,
+            })
+    }
+
+    // Deserialise the stored value, retrieving it from the network if it's stored in a Chunk.
+    async fn value_of(&mut self, value: &StoredValue) -> Result<Option<V>> {
+        let bytes = match value {
+            StoredValue::Inline(bytes) => Bytes::from(bytes.clone()),
+            StoredValue::Chunk(xorname) => {
+                if let Some((bytes, _)) = self.chunks.get(xorname) {
+                    bytes.clone()
+                } else {
+                    let chunk = self
+                        .client
+                        .get_chunk(ChunkAddress::new(*xorname), false, None)
+                        .await?;
+                    let bytes = chunk.value().clone();
+                    self.chunks.insert(*xorname, (bytes.clone(), None));
+                    bytes
+                }
+            }
+            StoredValue::Removed => return Ok(None),
+        };
+        Ok(Some(rmp_serde::from_slice(&bytes)?))
+    }
+}
+
+// Group the values set by the given Register entries by key, skipping the entries which are
+// not map entries.
+fn keys_values(entries: &BTreeSet<(EntryHash, Entry)>) -> KeysValues {
+    let mut keys_values = KeysValues::new();
+    for (entry_hash, entry) in entries {
+        match rmp_serde::from_slice::<MapEntry>(entry) {
+            Ok(MapEntry { key, value }) => {
+                keys_values
+                    .entry(key)
+                    .or_default()
+                    .insert(*entry_hash, value);
+            }
+            Err(err) => trace!("Ignoring Register entry {entry_hash:?} of another kind: {err:?}"),
+        }
+    }
+    keys_values
+}
+
+// The latest of the values of a key, i.e. the one set by the entry with the highest hash.
+fn latest(values: &BTreeMap<EntryHash, StoredValue>) -> Option<&StoredValue> {
+    values.values().next_back()
+}
+
+// Encode the entry setting the value of a key, along with the Chunk holding the value if it's
+// too large to fit in the entry.
+fn encode_entry(key: Vec<u8>, value: Bytes) -> Result<(Entry, Option<Chunk>)> {
+    let entry = rmp_serde::to_vec(&MapEntry {
+        key: key.clone(),
+        value: StoredValue::Inline(value.to_vec()),
+    })?;
+    if entry.len() <= MAX_REG_ENTRY_SIZE {
+        return Ok((entry, None));
+    }
+
+    let chunk = Chunk::new(value);
+    let entry = rmp_serde::to_vec(&MapEntry {
+        key,
+        value: StoredValue::Chunk(*chunk.name()),
+    })?;
+    Ok((entry, Some(chunk)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use eyre::Result;
+    use sn_registers::{Permissions, Register};
+
+    // A replica of the Register of a map, written to by any of the users.
+    struct Replica {
+        register: Register,
+    }
+
+    impl Replica {
+        fn set(&mut self, key: &str, value: StoredValue, signer: &SecretKey) -> Result<EntryHash> {
+            let key = rmp_serde::to_vec(key)?;
+            let children = keys_values(&self.register.read())
+                .remove(&key)
+                .map(|values| values.into_keys().collect())
+                .unwrap_or_default();
+            let entry = rmp_serde::to_vec(&MapEntry { key, value })?;
+            let (entry_hash, _) = self.register.write(entry, &children, signer)?;
+            Ok(entry_hash)
+        }
+
+        fn values(&self, key: &str) -> Result<BTreeMap<EntryHash, StoredValue>> {
+            let key = rmp_serde::to_vec(key)?;
+            Ok(keys_values(&self.register.read())
+                .remove(&key)
+                .unwrap_or_default())
+        }
+    }
+
+    fn inline(value: &str) -> Result<StoredValue> {
+        Ok(StoredValue::Inline(rmp_serde::to_vec(value)?))
+    }
+
+    fn replicas_with_writers(count: usize) -> (Vec<Replica>, Vec<SecretKey>) {
+        let writers: Vec<_> = (0..count).map(|_| SecretKey::random()).collect();
+        let owner = writers[0].public_key();
+        let permissions = Permissions::new_with(writers.iter().map(|sk| sk.public_key()));
+        let meta = XorName::random(&mut rand::thread_rng());
+        let register = Register::new(owner, meta, permissions);
+        let replicas = (0..count)
+            .map(|_| Replica {
+                register: register.clone(),
+            })
+            .collect();
+        (replicas, writers)
+    }
+
+    fn merge_all(replicas: &mut [Replica]) -> Result<()> {
+        let mut merged = replicas[0].register.clone();
+        for replica in replicas.iter() {
+            merged.merge(&replica.register)?;
+        }
+        for replica in replicas.iter_mut() {
+            replica.register.merge(&merged)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_writers_converge_to_the_same_latest_value() -> Result<()> {
+        let (mut replicas, writers) = replicas_with_writers(3);
+        let _ = replicas[0].set("colour", inline("red")?, &writers[0])?;
+        let _ = replicas[0].set("size", inline("small")?, &writers[0])?;
+        merge_all(&mut replicas)?;
+
+        // every writer sets the key concurrently, atop the value they all know of
+        for (i, (replica, writer)) in replicas.iter_mut().zip(&writers).enumerate() {
+            let _ = replica.set("colour", inline(&format!("colour {i}"))?, writer)?;
+        }
+        merge_all(&mut replicas)?;
+
+        let values = replicas[0].values("colour")?;
+        assert_eq!(values.len(), 3);
+        for replica in &replicas {
+            assert_eq!(replica.values("colour")?, values);
+            assert_eq!(latest(&replica.values("colour")?), latest(&values));
+            assert_eq!(replica.values("size")?.len(), 1);
+        }
+
+        // setting the key atop its concurrent values resolves the conflict
+        let entry_hash = replicas[1].set("colour", inline("blue")?, &writers[1])?;
+        merge_all(&mut replicas)?;
+        for replica in &replicas {
+            let values = replica.values("colour")?;
+            assert_eq!(values.keys().collect::<Vec<_>>(), vec![&entry_hash]);
+            assert_eq!(latest(&values), Some(&inline("blue")?));
+            assert_eq!(latest(&replica.values("size")?), Some(&inline("small")?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_removal_and_update_are_both_kept() -> Result<()> {
+        let (mut replicas, writers) = replicas_with_writers(2);
+        let _ = replicas[0].set("colour", inline("red")?, &writers[0])?;
+        merge_all(&mut replicas)?;
+
+        let removal = replicas[0].set("colour", StoredValue::Removed, &writers[0])?;
+        let update = replicas[1].set("colour", inline("green")?, &writers[1])?;
+        merge_all(&mut replicas)?;
+
+        let values = replicas[0].values("colour")?;
+        assert_eq!(values, replicas[1].values("colour")?);
+        assert_eq!(
+            values,
+            [(removal, StoredValue::Removed), (update, inline("green")?)].into()
+        );
+        // the key is removed only if the removal is the latest of the two
+        let expected = if removal > update {
+            StoredValue::Removed
+        } else {
+            inline("green")?
+        };
+        assert_eq!(latest(&values), Some(&expected));
+
+        // the removal is kept as the value of the key once the key is removed atop both
+        let _ = replicas[1].set("colour", StoredValue::Removed, &writers[1])?;
+        merge_all(&mut replicas)?;
+        assert_eq!(
+            latest(&replicas[0].values("colour")?),
+            Some(&StoredValue::Removed)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn checkpoint_compacts_the_map_to_the_latest_values() -> Result<()> {
+        let (mut replicas, writers) = replicas_with_writers(1);
+        let replica = &mut replicas[0];
+        let mut count = 0;
+        let res = loop {
+            let key = if count % 2 == 0 { "colour" } else { "size" };
+            match replica.set(key, inline(&format!("value {count}"))?, &writers[0]) {
+                Ok(_) => count += 1,
+                Err(err) => break err,
+            }
+        };
+        assert!(
+            matches!(
+                res.downcast_ref::<sn_registers::Error>(),
+                Some(sn_registers::Error::TooManyEntries(_))
+            ),
+            "Unexpected result: {res:?}"
+        );
+
+        let _ = replica.register.checkpoint(&writers[0])?;
+        assert_eq!(replica.register.size(), 2);
+        assert_eq!(
+            latest(&replica.values("colour")?),
+            Some(&inline(&format!("value {}", count - 2))?)
+        );
+        assert_eq!(
+            latest(&replica.values("size")?),
+            Some(&inline(&format!("value {}", count - 1))?)
+        );
+        let _ = replica.set("colour", inline("blue")?, &writers[0])?;
+        assert_eq!(latest(&replica.values("colour")?), Some(&inline("blue")?));
+        Ok(())
+    }
+
+    #[test]
+    fn values_too_large_for_an_entry_are_stored_in_a_chunk() -> Result<()> {
+        let key = rmp_serde::to_vec("key")?;
+        let small = Bytes::from(vec![1; 100]);
+        let (entry, chunk) = encode_entry(key.clone(), small.clone())?;
+        assert!(chunk.is_none());
+        assert_eq!(
+            rmp_serde::from_slice::<MapEntry>(&entry)?.value,
+            StoredValue::Inline(small.to_vec())
+        );
+
+        let large = Bytes::from(vec![1; MAX_REG_ENTRY_SIZE]);
+        let (entry, chunk) = encode_entry(key, large.clone())?;
+        let chunk = chunk.ok_or_else(|| eyre::eyre!("Value not stored in a chunk"))?;
+        assert_eq!(chunk.value(), &large);
+        assert!(entry.len() <= MAX_REG_ENTRY_SIZE);
+        assert_eq!(
+            rmp_serde::from_slice::<MapEntry>(&entry)?.value,
+            StoredValue::Chunk(*chunk.name())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::UploadItem;
+use crate::{Client, ClientRegister, Error as ClientError, Result};
+use bytes::Bytes;
+use itertools::Either;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sn_networking::PayeeQuote;
+use sn_protocol::{
+    messages::RegisterCmd,
+    storage::{Chunk, ChunkAddress},
+    NetworkAddress,
+};
+use sn_registers::Register;
+use sn_transfers::{MainPubkey, NanoTokens, PaymentQuote};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use xor_name::XorName;
+
+/// The dir, under the root dir, the state of the on going uploads are journaled to. Each upload
+/// is journaled to its own file, named after the hex encoded id of the upload.
+const UPLOAD_JOURNALS_DIR: &str = "upload_journals";
+
+/// The path of the journal of the upload with the given id.
+pub(super) fn journal_path(root_dir: &Path, journal_id: &XorName) -> PathBuf {
+    root_dir
+        .join(UPLOAD_JOURNALS_DIR)
+        .join(hex::encode(journal_id.0))
+}
+
+/// A step of the upload, appended to the journal as it happens. Each entry is written as its
+/// length followed by its msgpack serialisation, so an entry cut short by the process being
+/// killed is detected and ignored on resume.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum JournalEntry {
+    /// An item to upload.
+    ItemAdded(JournaledItem),
+    /// A quote was obtained for the item.
+    Quoted {
+        xorname: XorName,
+        payee: Vec<u8>,
+        main_pubkey: MainPubkey,
+        quote: PaymentQuote,
+    },
+    /// Payments were sent for the items.
+    Paid {
+        xornames: Vec<XorName>,
+        storage_cost: NanoTokens,
+        royalty_fees: NanoTokens,
+    },
+    /// The item was uploaded, and verified to be stored if `verify_store` was set.
+    Uploaded { xorname: XorName, verified: bool },
+    /// The item was already present in the network, or was a Register that got merged with the
+    /// remote replica. Nothing was paid for it.
+    Skipped(XorName),
+}
+
+/// An item to upload, as journaled. Chunks on disk are journaled by their path.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) enum JournaledItem {
+    ChunkPath {
+        address: ChunkAddress,
+        path: PathBuf,
+    },
+    Chunk {
+        content: Bytes,
+    },
+    Register {
+        register: Register,
+        ops: Vec<RegisterCmd>,
+    },
+}
+
+impl JournaledItem {
+    pub(super) fn from_upload_item(item: &UploadItem) -> Self {
+        match item {
+            UploadItem::Chunk {
+                address,
+                chunk: Either::Right(path),
+            } => Self::ChunkPath {
+                address: *address,
+                path: path.clone(),
+            },
+            UploadItem::Chunk {
+                chunk: Either::Left(chunk),
+                ..
+            } => Self::Chunk {
+                content: chunk.value().clone(),
+            },
+            UploadItem::Register { reg, .. } => Self::Register {
+                register: reg.register.clone(),
+                ops: reg.cached_ops().cloned().collect(),
+            },
+        }
+    }
+
+    fn into_upload_item(self, client: &Client) -> (XorName, UploadItem) {
+        match self {
+            Self::ChunkPath { address, path } => (
+                *address.xorname(),
+                UploadItem::Chunk {
+                    address,
+                    chunk: Either::Right(path),
+                },
+            ),
+            Self::Chunk { content } => {
+                let chunk = Chunk::new(content);
+                (
+                    *chunk.name(),
+                    UploadItem::Chunk {
+                        address: *chunk.address(),
+                        chunk: Either::Left(chunk),
+                    },
+                )
+            }
+            Self::Register { register, ops } => {
+                let address = *register.address();
+                let reg = ClientRegister::from_cached_ops(client.clone(), register, ops);
+                (address.xorname(), UploadItem::Register { address, reg })
+            }
+        }
+    }
+}
+
+/// Appends the steps of an upload to its journal.
+pub(super) struct UploadJournal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl UploadJournal {
+    /// Start the journal of a new upload, replacing the one of any previous upload with the
+    /// same id. Uploads with other ids keep their own journals.
+    pub(super) fn create(root_dir: &Path, journal_id: &XorName) -> Result<Self> {
+        let path = journal_path(root_dir, journal_id);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(&path)?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Continue the journal of an interrupted upload, after its first `valid_len` bytes. Any
+    /// entry cut short past them is dropped, so it doesn't swallow the entries appended next.
+    fn open(path: PathBuf, valid_len: u64) -> Result<Self> {
+        let file = OpenOptions::new().write(true).open(&path)?;
+        file.set_len(valid_len)?;
+        let mut writer = BufWriter::new(file);
+        let _ = writer.seek(SeekFrom::Start(valid_len))?;
+        Ok(Self { path, writer })
+    }
+
+    /// Replay the journal at the given path, returning its entries along with the journal to
+    /// continue with.
+    fn replay(path: PathBuf) -> Result<(Vec<JournalEntry>, Self)> {
+        let bytes = fs::read(&path)?;
+        let (entries, valid_len) = read_entries(&bytes);
+        Ok((entries, Self::open(path, valid_len as u64)?))
+    }
+
+    /// Append an entry to the journal. The entry is flushed right away, as the process could be
+    /// killed at any point.
+    pub(super) fn append(&mut self, entry: &JournalEntry) -> Result<()> {
+        let bytes = rmp_serde::to_vec(entry)?;
+        self.writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Remove the journal once the upload has completed.
+    pub(super) fn remove(self) {
+        let Self { path, writer } = self;
+        drop(writer);
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Failed to remove the upload journal at {path:?}: {err:?}");
+        }
+    }
+}
+
+/// The state of an interrupted upload, replayed from its journal.
+#[derive(Default)]
+pub(super) struct JournaledState {
+    /// The items that are still to be uploaded.
+    pub(super) items: BTreeMap<XorName, UploadItem>,
+    /// The unexpired quotes obtained for the items that have not been paid for.
+    pub(super) quotes: BTreeMap<XorName, Box<PayeeQuote>>,
+    pub(super) uploaded_addresses: BTreeSet<NetworkAddress>,
+    pub(super) storage_cost: NanoTokens,
+    pub(super) royalty_fees: NanoTokens,
+    pub(super) uploaded_count: usize,
+    pub(super) skipped_count: usize,
+}
+
+impl JournaledState {
+    /// Replay the journal of the upload with the given id under the `root_dir`, returning the
+    /// state of the interrupted upload along with the journal to continue with.
+    pub(super) fn load(
+        client: &Client,
+        root_dir: &Path,
+        journal_id: &XorName,
+    ) -> Result<(Self, UploadJournal)> {
+        let path = journal_path(root_dir, journal_id);
+        if !path.exists() {
+            return Err(ClientError::NoUploadToResume(root_dir.to_path_buf()));
+        }
+        let (entries, journal) = UploadJournal::replay(path)?;
+
+        let mut state = Self::default();
+        for entry in entries {
+            state.apply(client, entry)?;
+        }
+        // Quotes expire, a fresh one has to be obtained for those.
+        state.quotes.retain(|_, quote| !quote.2.has_expired());
+        Ok((state, journal))
+    }
+
+    fn apply(&mut self, client: &Client, entry: JournalEntry) -> Result<()> {
+        match entry {
+            JournalEntry::ItemAdded(item) => {
+                let (xorname, item) = item.into_upload_item(client);
+                let _ = self.items.insert(xorname, item);
+            }
+            JournalEntry::Quoted {
+                xorname,
+                payee,
+                main_pubkey,
+                quote,
+            } => {
+                if let Ok(payee) = PeerId::from_bytes(&payee) {
+                    let _ = self
+                        .quotes
+                        .insert(xorname, Box::new((payee, main_pubkey, quote)));
+                }
+            }
+            JournalEntry::Paid {
+                xornames,
+                storage_cost,
+                royalty_fees,
+            } => {
+                // The payments themselves are held by the wallet, the quotes are no longer needed.
+                for xorname in xornames {
+                    let _ = self.quotes.remove(&xorname);
+                }
+                self.storage_cost = self
+                    .storage_cost
+                    .checked_add(storage_cost)
+                    .ok_or(ClientError::NumericOverflow)?;
+                self.royalty_fees = self
+                    .royalty_fees
+                    .checked_add(royalty_fees)
+                    .ok_or(ClientError::NumericOverflow)?;
+            }
+            JournalEntry::Uploaded { xorname, .. } => {
+                self.remove_item(&xorname);
+                self.uploaded_count += 1;
+            }
+            JournalEntry::Skipped(xorname) => {
+                self.remove_item(&xorname);
+                self.skipped_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_item(&mut self, xorname: &XorName) {
+        let _ = self.quotes.remove(xorname);
+        if let Some(item) = self.items.remove(xorname) {
+            let _ = self.uploaded_addresses.insert(item.address());
+        }
+    }
+}
+
+/// Decode the entries of a journal, stopping at the first one cut short or corrupted.
+/// Returns them along with the length of the bytes they were decoded from.
+fn read_entries(mut bytes: &[u8]) -> (Vec<JournalEntry>, usize) {
+    let mut entries = vec![];
+    let mut valid_len = 0;
+    while bytes.len() >= 4 {
+        let (len, rest) = bytes.split_at(4);
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        if rest.len() < len {
+            warn!("The last upload journal entry was cut short, ignoring it");
+            break;
+        }
+        let (entry, rest) = rest.split_at(len);
+        match rmp_serde::from_slice(entry) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => {
+                warn!("Failed to deserialize an upload journal entry, ignoring the rest: {err:?}");
+                break;
+            }
+        }
+        bytes = rest;
+        valid_len += 4 + len;
+    }
+    (entries, valid_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+    use tempfile::tempdir;
+
+    #[test]
+    fn entries_cut_short_are_ignored_on_replay() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut rng = rand::thread_rng();
+        let xornames = [XorName::random(&mut rng), XorName::random(&mut rng)];
+
+        let journal_id = XorName::random(&mut rng);
+        let path = journal_path(temp_dir.path(), &journal_id);
+        let mut journal = UploadJournal::create(temp_dir.path(), &journal_id)?;
+        journal.append(&JournalEntry::Skipped(xornames[0]))?;
+        let first_entry_len = fs::metadata(&path)?.len() as usize;
+        journal.append(&JournalEntry::Skipped(xornames[1]))?;
+        drop(journal);
+
+        let bytes = fs::read(&path)?;
+        assert_eq!(read_entries(&bytes).0.len(), 2);
+        // the process got killed in the middle of writing the last entry.
+        let (entries, valid_len) = read_entries(&bytes[..bytes.len() - 1]);
+        assert_eq!((entries.len(), valid_len), (1, first_entry_len));
+        let (entries, valid_len) = read_entries(&bytes[..3]);
+        assert!(entries.is_empty());
+        assert_eq!(valid_len, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn entries_appended_after_resuming_are_kept() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let mut rng = rand::thread_rng();
+        let journal_id = XorName::random(&mut rng);
+        let path = journal_path(temp_dir.path(), &journal_id);
+        let mut journal = UploadJournal::create(temp_dir.path(), &journal_id)?;
+
+        let mut skipped = vec![];
+        for _ in 0..2 {
+            // an entry gets written, then the process gets killed while writing the next one.
+            let xorname = XorName::random(&mut rng);
+            journal.append(&JournalEntry::Skipped(xorname))?;
+            skipped.push(xorname);
+            journal.append(&JournalEntry::Skipped(XorName::random(&mut rng)))?;
+            drop(journal);
+            let len = fs::metadata(&path)?.len();
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(len - 1)?;
+
+            let (entries, resumed) = UploadJournal::replay(path.clone())?;
+            assert_eq!(skipped_xornames(entries), skipped);
+            journal = resumed;
+        }
+
+        let xorname = XorName::random(&mut rng);
+        journal.append(&JournalEntry::Skipped(xorname))?;
+        skipped.push(xorname);
+        drop(journal);
+        let (entries, _) = UploadJournal::replay(path)?;
+        assert_eq!(skipped_xornames(entries), skipped);
+        Ok(())
+    }
+
+    fn skipped_xornames(entries: Vec<JournalEntry>) -> Vec<XorName> {
+        entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                JournalEntry::Skipped(xorname) => Some(xorname),
+                _ => None,
+            })
+            .collect()
+    }
+}
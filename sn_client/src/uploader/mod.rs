@@ -6,6 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod journal;
 #[cfg(test)]
 mod tests;
 mod upload;
@@ -37,6 +38,8 @@ pub struct UploadCfg {
     pub retry_strategy: RetryStrategy,
     pub max_repayments_for_failed_data: usize, // we want people to specify an explicit limit here.
     pub collect_registers: bool,
+    /// The id the upload is journaled under, to be resumed with, see `Uploader::set_journal`.
+    pub journal: Option<XorName>,
 }
 
 impl Default for UploadCfg {
@@ -48,6 +51,7 @@ impl Default for UploadCfg {
             retry_strategy: RetryStrategy::Balanced,
             max_repayments_for_failed_data: MAX_REPAYMENTS_PER_FAILED_ITEM,
             collect_registers: false,
+            journal: None,
         }
     }
 }
@@ -155,6 +159,19 @@ impl Uploader {
         }
     }
 
+    /// Resumes an upload that got interrupted, from the journal left under the `root_dir`. The
+    /// upload has to have been started with `set_journal` set to the same `journal_id`.
+    ///
+    /// The items that were paid for are uploaded without being paid for again, and the quotes that
+    /// have not expired are paid without being requested again. The upload keeps being journaled,
+    /// along with any items inserted before starting it again. The configuration is not journaled,
+    /// hence has to be set again.
+    pub fn resume(client: Client, root_dir: PathBuf, journal_id: XorName) -> Result<Self> {
+        Ok(Self {
+            inner: Some(InnerUploader::resume(client, root_dir, journal_id)?),
+        })
+    }
+
     /// Update all the configurations by passing the `UploadCfg` struct
     pub fn set_upload_cfg(&mut self, cfg: UploadCfg) {
         // Self can only be constructed with new(), which will set inner to InnerUploader always.
@@ -232,6 +249,19 @@ impl Uploader {
             .set_collect_registers(collect_registers);
     }
 
+    /// Enables the uploader to journal the state of the upload to the root dir as it progresses, so that an
+    /// interrupted upload can be continued through `Uploader::resume` without paying twice for the same items.
+    /// The journal is kept under the provided id, uploads sharing the root dir must use distinct ids.
+    /// The journal is removed once the upload completes.
+    ///
+    /// By default, this option is set to None
+    pub fn set_journal(&mut self, journal: Option<XorName>) {
+        self.inner
+            .as_mut()
+            .expect("Uploader::new makes sure inner is present")
+            .set_journal(journal);
+    }
+
     /// Returns a receiver for UploadEvent.
     /// This method is optional and the upload process can be performed without it.
     pub fn get_event_receiver(&mut self) -> mpsc::Receiver<UploadEvent> {
@@ -346,6 +376,10 @@ impl InnerUploader {
         self.cfg.collect_registers = collect_registers;
     }
 
+    pub(super) fn set_journal(&mut self, journal: Option<XorName>) {
+        self.cfg.journal = journal;
+    }
+
     pub(super) fn get_event_receiver(&mut self) -> mpsc::Receiver<UploadEvent> {
         let (tx, rx) = mpsc::channel(100);
         self.event_sender = Some(tx);
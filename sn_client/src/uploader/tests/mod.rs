@@ -9,17 +9,24 @@
 mod setup;
 
 use crate::{
-    uploader::tests::setup::{
-        get_dummy_chunk_paths, get_dummy_registers, get_inner_uploader, start_uploading_with_steps,
-        TestSteps,
+    uploader::{
+        journal::{journal_path, JournalEntry, JournaledItem, UploadJournal},
+        tests::setup::{
+            get_dummy_chunk_paths, get_dummy_registers, get_inner_uploader,
+            get_resumed_inner_uploader, start_uploading_with_steps, TestSteps,
+        },
     },
     Error as ClientError, UploadEvent,
 };
 use assert_matches::assert_matches;
 use eyre::Result;
+use libp2p::PeerId;
 use sn_logging::LogBuilder;
+use sn_protocol::storage::ChunkAddress;
+use sn_transfers::{MainSecretKey, NanoTokens, PaymentQuote};
 use std::collections::VecDeque;
 use tempfile::tempdir;
+use xor_name::XorName;
 
 // ===== HAPPY PATH =======
 
@@ -457,3 +464,85 @@ async fn maximum_repayment_error_should_be_triggered_during_get_store_cost() ->
     assert_matches!(events[1], UploadEvent::PaymentMade { .. });
     Ok(())
 }
+
+// 7: Chunks: an interrupted upload should be resumed from its journal, without getting the quotes or the items that
+// were uploaded again.
+#[tokio::test]
+async fn interrupted_upload_should_be_resumed_from_the_journal() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("uploader");
+    let temp_dir = tempdir()?;
+    let chunks = get_dummy_chunk_paths(3, temp_dir.path().to_path_buf());
+
+    // the first chunk got uploaded and the second one got quoted before the upload got interrupted.
+    let journal_id = XorName::random(&mut rand::thread_rng());
+    // another upload sharing the root dir, its journal is left alone.
+    let other_journal_id = XorName::random(&mut rand::thread_rng());
+    drop(UploadJournal::create(temp_dir.path(), &other_journal_id)?);
+    let mut journal = UploadJournal::create(temp_dir.path(), &journal_id)?;
+    for (xorname, path) in chunks.iter() {
+        journal.append(&JournalEntry::ItemAdded(JournaledItem::ChunkPath {
+            address: ChunkAddress::new(*xorname),
+            path: path.clone(),
+        }))?;
+    }
+    journal.append(&JournalEntry::Uploaded {
+        xorname: chunks[0].0,
+        verified: true,
+    })?;
+    let mut quote = PaymentQuote::zero();
+    quote.cost = NanoTokens::from(10);
+    journal.append(&JournalEntry::Quoted {
+        xorname: chunks[1].0,
+        payee: PeerId::random().to_bytes(),
+        main_pubkey: MainSecretKey::random().main_pubkey(),
+        quote,
+    })?;
+    drop(journal);
+
+    let (mut inner_uploader, task_result_rx) =
+        get_resumed_inner_uploader(temp_dir.path().to_path_buf(), journal_id)?;
+
+    // cfg
+    inner_uploader.set_batch_size(1);
+
+    // the path to test
+    let steps = vec![
+        // the quoted chunk is paid for straight away
+        TestSteps::MakePaymentOk,
+        TestSteps::GetStoreCostOk {
+            trigger_zero_cost: false,
+            assert_select_different_payee: false,
+        },
+        TestSteps::UploadItemOk,
+        TestSteps::MakePaymentOk,
+        TestSteps::UploadItemOk,
+    ];
+
+    let (upload_handle, events_handle) =
+        start_uploading_with_steps(inner_uploader, VecDeque::from(steps), task_result_rx);
+
+    let summary = upload_handle.await??;
+    let events = events_handle.await?;
+
+    assert_eq!(summary.uploaded_count, 3);
+    assert_eq!(summary.uploaded_addresses.len(), 3);
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, UploadEvent::PaymentMade { .. }))
+            .count(),
+        2
+    );
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, UploadEvent::ChunkUploaded(..)))
+            .count(),
+        2
+    );
+    // the journal is removed once the upload completes.
+    assert!(!journal_path(temp_dir.path(), &journal_id).exists());
+    assert!(journal_path(temp_dir.path(), &other_journal_id).exists());
+    Ok(())
+}
@@ -387,6 +387,20 @@ pub fn get_inner_uploader(root_dir: PathBuf) -> Result<(InnerUploader, mpsc::Sen
     Ok((inner, task_result_sender))
 }
 
+// Same as `get_inner_uploader`, but resumes the upload journaled under the root_dir.
+pub fn get_resumed_inner_uploader(
+    root_dir: PathBuf,
+    journal_id: XorName,
+) -> Result<(InnerUploader, mpsc::Sender<TaskResult>)> {
+    let client = build_unconnected_client(root_dir.clone())?;
+
+    let mut inner = InnerUploader::resume(client, root_dir, journal_id)?;
+    let (task_result_sender, task_result_receiver) = mpsc::channel(100);
+    inner.testing_task_channels = Some((task_result_sender.clone(), task_result_receiver));
+
+    Ok((inner, task_result_sender))
+}
+
 // Spawns two tasks. One is the actual upload task that will return an UploadStat when completed.
 // The other is a one to collect all the UploadEvent emitted by the previous task.
 pub fn start_uploading_with_steps(
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    journal::{JournalEntry, JournaledItem, JournaledState, UploadJournal},
     GetStoreCostStrategy, TaskResult, UploadCfg, UploadEvent, UploadItem, UploadSummary,
     UploaderInterface,
 };
@@ -85,10 +86,33 @@ pub(super) async fn start_upload(
         uploader.cfg.batch_size,
     )?;
 
+    // journal the items to upload, except for the ones resumed from the journal.
+    if let Some(journal_id) = uploader.cfg.journal.filter(|_| uploader.journal.is_none()) {
+        uploader.journal = Some(UploadJournal::create(&uploader.root_dir, &journal_id)?);
+    }
+    if let Some(journal) = uploader.journal.as_mut() {
+        for (xorname, item) in uploader.all_upload_items.iter() {
+            if !uploader.resumed_items.contains(xorname) {
+                journal.append(&JournalEntry::ItemAdded(JournaledItem::from_upload_item(
+                    item,
+                )))?;
+            }
+        }
+    }
+
+    // items paid for before the upload got interrupted can be uploaded straight away, and the quotes obtained can
+    // be paid for straight away.
+    let resumed_payments = std::mem::take(&mut uploader.resumed_payments);
+    let resumed_quotes = std::mem::take(&mut uploader.resumed_quotes);
+    let is_resumed = |xorname: &XorName| {
+        resumed_payments.contains(xorname) || resumed_quotes.contains_key(xorname)
+    };
+
     // chunks can be pushed to pending_get_store_cost directly
     uploader.pending_to_get_store_cost = uploader
         .all_upload_items
         .iter()
+        .filter(|(xorname, _)| !is_resumed(xorname))
         .filter_map(|(xorname, item)| {
             if let UploadItem::Chunk { .. } = item {
                 Some((*xorname, GetStoreCostStrategy::Cheapest))
@@ -102,6 +126,7 @@ pub(super) async fn start_upload(
     uploader.pending_to_get_register = uploader
         .all_upload_items
         .iter()
+        .filter(|(xorname, _)| !is_resumed(xorname))
         .filter_map(|(_xorname, item)| {
             if let UploadItem::Register { address, .. } = item {
                 Some(*address)
@@ -111,6 +136,9 @@ pub(super) async fn start_upload(
         })
         .collect();
 
+    uploader.pending_to_upload = resumed_payments.into_iter().collect();
+    uploader.pending_to_pay = resumed_quotes.into_iter().collect();
+
     loop {
         // Break if we have uploaded all the items.
         // The loop also breaks if we fail to get_store_cost / make payment / upload for n consecutive times.
@@ -143,6 +171,9 @@ pub(super) async fn start_upload(
                 });
             }
 
+            if let Some(journal) = uploader.journal.take() {
+                journal.remove();
+            }
             return Ok(summary);
         }
 
@@ -292,6 +323,7 @@ pub(super) async fn start_upload(
                 // push modifies the register, so we return this instead of the one from all_upload_items
                 let xorname = updated_register.address().xorname();
                 let _ = uploader.on_going_push_register.remove(&xorname);
+                uploader.append_to_journal(JournalEntry::Skipped(xorname))?;
                 uploader.skipped_count += 1;
                 let _ = uploader
                     .uploaded_addresses
@@ -329,10 +361,17 @@ pub(super) async fn start_upload(
                 trace!("GetStoreCostOk for {xorname:?}'s store_cost {:?}", quote.2);
 
                 if quote.2.cost != NanoTokens::zero() {
+                    uploader.append_to_journal(JournalEntry::Quoted {
+                        xorname,
+                        payee: quote.0.to_bytes(),
+                        main_pubkey: quote.1,
+                        quote: quote.2.clone(),
+                    })?;
                     uploader.pending_to_pay.push((xorname, quote));
                 }
                 // if cost is 0, then it already in the network.
                 else {
+                    uploader.append_to_journal(JournalEntry::Skipped(xorname))?;
                     // remove the item since we have uploaded it.
                     let removed_item = uploader
                         .all_upload_items
@@ -397,6 +436,11 @@ pub(super) async fn start_upload(
                 for xorname in paid_xornames.iter() {
                     let _ = uploader.on_going_payments.remove(xorname);
                 }
+                uploader.append_to_journal(JournalEntry::Paid {
+                    xornames: paid_xornames.clone(),
+                    storage_cost,
+                    royalty_fees,
+                })?;
                 uploader.pending_to_upload.extend(paid_xornames);
                 uploader.make_payments_errors = 0;
                 uploader.upload_final_balance = new_balance;
@@ -449,6 +493,10 @@ pub(super) async fn start_upload(
             }
             TaskResult::UploadOk(xorname) => {
                 let _ = uploader.on_going_uploads.remove(&xorname);
+                uploader.append_to_journal(JournalEntry::Uploaded {
+                    xorname,
+                    verified: uploader.cfg.verify_store,
+                })?;
                 uploader.uploaded_count += 1;
                 trace!("UploadOk for {xorname:?}");
                 // remove the item since we have uploaded it.
@@ -694,6 +742,13 @@ pub(super) struct InnerUploader {
     pub(super) uploaded_count: usize,
     pub(super) skipped_count: usize,
 
+    // Resume states, set if the upload was resumed from a journal
+    #[debug(skip)]
+    pub(super) journal: Option<UploadJournal>,
+    pub(super) resumed_items: BTreeSet<XorName>,
+    pub(super) resumed_payments: BTreeSet<XorName>,
+    pub(super) resumed_quotes: BTreeMap<XorName, Box<PayeeQuote>>,
+
     // Task channels for testing. Not used in actual code.
     pub(super) testing_task_channels:
         Option<(mpsc::Sender<TaskResult>, mpsc::Receiver<TaskResult>)>,
@@ -740,12 +795,56 @@ impl InnerUploader {
             uploaded_count: Default::default(),
             skipped_count: Default::default(),
 
+            journal: None,
+            resumed_items: Default::default(),
+            resumed_payments: Default::default(),
+            resumed_quotes: Default::default(),
+
             testing_task_channels: None,
             logged_event_sender_absence: Default::default(),
             event_sender: Default::default(),
         }
     }
 
+    /// Rebuild the state of an interrupted upload from its journal under the `root_dir`.
+    pub(super) fn resume(client: Client, root_dir: PathBuf, journal_id: XorName) -> Result<Self> {
+        let (state, journal) = JournaledState::load(&client, &root_dir, &journal_id)?;
+        let mut uploader = Self::new(client, root_dir);
+        uploader.cfg.journal = Some(journal_id);
+
+        // The wallet keeps the payments until the item is uploaded, this also covers the payments
+        // made right before the upload got interrupted, which might not have been journaled.
+        uploader.resumed_payments = state
+            .items
+            .keys()
+            .filter(|xorname| uploader.wallet_api.get_recent_payment(xorname).is_ok())
+            .copied()
+            .collect();
+        uploader.resumed_quotes = state
+            .quotes
+            .into_iter()
+            .filter(|(xorname, _)| {
+                state.items.contains_key(xorname) && !uploader.resumed_payments.contains(xorname)
+            })
+            .collect();
+        info!(
+            "Resuming upload of {} items, of which {} are paid for and {} are quoted",
+            state.items.len(),
+            uploader.resumed_payments.len(),
+            uploader.resumed_quotes.len()
+        );
+
+        uploader.resumed_items = state.items.keys().copied().collect();
+        uploader.all_upload_items.extend(state.items);
+        uploader.uploaded_addresses = state.uploaded_addresses;
+        uploader.upload_storage_cost = state.storage_cost;
+        uploader.upload_royalty_fees = state.royalty_fees;
+        uploader.uploaded_count = state.uploaded_count;
+        uploader.skipped_count = state.skipped_count;
+        uploader.journal = Some(journal);
+        Ok(uploader)
+    }
+
     // ====== Pop items ======
 
     fn pop_item_for_push_register(&mut self) -> Result<UploadItem> {
@@ -1014,6 +1113,13 @@ impl InnerUploader {
 
     // ====== Misc ======
 
+    fn append_to_journal(&mut self, entry: JournalEntry) -> Result<()> {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.append(&entry)?;
+        }
+        Ok(())
+    }
+
     fn emit_upload_event(&mut self, event: UploadEvent) {
         if let Some(sender) = self.event_sender.as_ref() {
             let sender_clone = sender.clone();
@@ -495,51 +495,38 @@ impl WalletClient {
         content_addrs: impl Iterator<Item = NetworkAddress>,
         verify_store: bool,
     ) -> WalletResult<StoragePaymentResult> {
-        // get store cost from network in parallel
-        let mut tasks = JoinSet::new();
-        for content_addr in content_addrs {
-            let client = self.client.clone();
-            tasks.spawn(async move {
-                let cost = client
-                    .network
-                    .get_store_costs_from_network(content_addr.clone(), vec![])
-                    .await
-                    .map_err(|error| WalletError::CouldNotSendMoney(error.to_string()));
-
-                debug!("Storecosts retrieved for {content_addr:?} {cost:?}");
-                (content_addr, cost)
-            });
-        }
-        debug!("Pending store cost tasks: {:?}", tasks.len());
+        // get store costs from network, batched by overlapping close groups
+        let content_addrs: Vec<_> = content_addrs.collect();
+        debug!(
+            "Getting store costs for {} content addrs",
+            content_addrs.len()
+        );
+        let costs = self
+            .client
+            .network
+            .get_batched_store_costs_from_network(content_addrs, vec![])
+            .await
+            .map_err(|error| {
+                warn!("Cannot get store costs with error {error:?}");
+                WalletError::CouldNotSendMoney(error.to_string())
+            })?;
 
         // collect store costs
         let mut cost_map = BTreeMap::default();
         let mut skipped_chunks = vec![];
-        #[allow(clippy::mutable_key_type)]
-        while let Some(res) = tasks.join_next().await {
-            match res {
-                Ok((content_addr, Ok(cost))) => {
-                    if let Some(xorname) = content_addr.as_xorname() {
-                        if cost.2.cost == NanoTokens::zero() {
-                            skipped_chunks.push(xorname);
-                            debug!("Skipped existing chunk {content_addr:?}");
-                        } else {
-                            debug!("Storecost inserted into payment map for {content_addr:?}");
-                            let _ = cost_map.insert(xorname, (cost.1, cost.2, cost.0.to_bytes()));
-                        }
-                    } else {
-                        warn!("Cannot get store cost for a content that is not a data type: {content_addr:?}");
-                    }
-                }
-                Ok((content_addr, Err(err))) => {
-                    warn!("Cannot get store cost for {content_addr:?} with error {err:?}");
-                    return Err(err);
-                }
-                Err(e) => {
-                    return Err(WalletError::CouldNotSendMoney(format!(
-                        "Storecost get task failed: {e:?}"
-                    )));
+        for (content_addr, cost) in costs {
+            if let Some(xorname) = content_addr.as_xorname() {
+                if cost.2.cost == NanoTokens::zero() {
+                    skipped_chunks.push(xorname);
+                    debug!("Skipped existing chunk {content_addr:?}");
+                } else {
+                    debug!("Storecost inserted into payment map for {content_addr:?}");
+                    let _ = cost_map.insert(xorname, (cost.1, cost.2, cost.0.to_bytes()));
                 }
+            } else {
+                warn!(
+                    "Cannot get store cost for a content that is not a data type: {content_addr:?}"
+                );
             }
         }
         info!("Storecosts retrieved for all the provided content addrs");
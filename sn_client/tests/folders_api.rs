@@ -13,7 +13,7 @@ use eyre::Result;
 use sn_client::test_utils::{
     get_funded_wallet, get_new_client, pay_for_storage, random_file_chunk,
 };
-use sn_client::{FolderEntry, FoldersApi, Metadata};
+use sn_client::{Error as ClientError, FolderEntry, FolderItemDiff, FoldersApi, Metadata};
 use sn_protocol::{storage::ChunkAddress, NetworkAddress};
 use sn_registers::{EntryHash, RegisterAddress};
 use xor_name::XorName;
@@ -36,20 +36,14 @@ async fn test_folder_basics() -> Result<()> {
         folders_api.add_file("file.txt".into(), file_chunk.clone(), None)?;
     assert_eq!(
         file_metadata,
-        Metadata {
-            name: "file.txt".to_string(),
-            content: FolderEntry::File(file_chunk)
-        }
+        Metadata::new("file.txt".to_string(), FolderEntry::File(file_chunk))
     );
 
     let (subdir_entry_hash, subdir_meta_xorname, subdir_metadata) =
         folders_api.add_folder("subdir".into(), address_subdir, None)?;
     assert_eq!(
         subdir_metadata,
-        Metadata {
-            name: "subdir".to_string(),
-            content: FolderEntry::Folder(address_subdir)
-        }
+        Metadata::new("subdir".to_string(), FolderEntry::Folder(address_subdir))
     );
 
     assert_eq!(folders_api.address(), &address);
@@ -188,6 +182,101 @@ async fn test_folder_remove_replace_entries() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_folder_history_diff_restore() -> Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_dir = tmp_dir.path();
+    let owner_sk = SecretKey::random();
+    let client = get_new_client(owner_sk).await?;
+    let mut folders_api = FoldersApi::new(client, wallet_dir, None)?;
+
+    let (file_v1_entry_hash, file_v1_meta_xorname, file_v1_metadata) =
+        folders_api.add_file("file.txt".into(), random_file_chunk(), None)?;
+    let (file_v2_entry_hash, _, file_v2_metadata) = folders_api.replace_file(
+        file_v1_entry_hash,
+        "file.txt".into(),
+        random_file_chunk(),
+        None,
+    )?;
+    let (other_entry_hash, other_meta_xorname, other_metadata) =
+        folders_api.add_file("other.txt".into(), random_file_chunk(), None)?;
+    folders_api.remove_item(file_v2_entry_hash)?;
+
+    let history = folders_api.history().await?;
+    assert_eq!(history.len(), 4);
+    let position = |entry_hash| {
+        history
+            .iter()
+            .position(|version| version.entry_hash == entry_hash)
+    };
+    assert!(position(file_v1_entry_hash) < position(file_v2_entry_hash));
+    let removal = history
+        .iter()
+        .find(|version| version.item.is_none())
+        .expect("the removal of file.txt to be in the history");
+    assert_eq!(
+        removal.previous,
+        vec![file_v2_entry_hash].into_iter().collect()
+    );
+
+    // file.txt was at its first version, and got removed
+    assert_eq!(
+        folders_api
+            .entries_at(file_v1_entry_hash)
+            .await?
+            .get(&file_v1_entry_hash),
+        Some(&(file_v1_meta_xorname, file_v1_metadata.clone()))
+    );
+    let diff = folders_api
+        .diff(file_v2_entry_hash, removal.entry_hash)
+        .await?;
+    assert_eq!(
+        diff.get("file.txt"),
+        Some(&FolderItemDiff::Removed(file_v2_metadata.clone()))
+    );
+
+    // each version holds every item at its latest version up to it in history, other.txt included
+    for version in &history {
+        let entries = folders_api.entries_at(version.entry_hash).await?;
+        assert_eq!(
+            entries.get(&other_entry_hash),
+            (position(other_entry_hash) <= position(version.entry_hash))
+                .then_some(&(other_meta_xorname, other_metadata.clone()))
+        );
+    }
+    let latest = history.last().expect("the history not to be empty");
+    assert_eq!(
+        folders_api.entries_at(latest.entry_hash).await?,
+        folders_api.entries().await?
+    );
+    assert!(matches!(
+        folders_api.restore(removal.entry_hash).await,
+        Err(ClientError::FolderVersionIsRemoval(_))
+    ));
+
+    // let's bring the first version of file.txt back
+    let (restored_entry_hash, restored_meta_xorname, _) =
+        folders_api.restore(file_v1_entry_hash).await?;
+    assert_eq!(restored_meta_xorname, file_v1_meta_xorname);
+    assert!(folders_api.contains(&restored_entry_hash));
+    assert_eq!(
+        folders_api.find_by_name("file.txt"),
+        Some((&file_v1_meta_xorname, &file_v1_metadata))
+    );
+    assert_eq!(
+        folders_api
+            .diff(file_v2_entry_hash, restored_entry_hash)
+            .await?
+            .get("file.txt"),
+        Some(&FolderItemDiff::Modified {
+            from: file_v2_metadata,
+            to: file_v1_metadata
+        })
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_folder_retrieve() -> Result<()> {
     let owner_sk = SecretKey::random();
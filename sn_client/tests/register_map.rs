@@ -0,0 +1,132 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+// All tests require a network running so Clients can be instantiated.
+
+use bls::SecretKey;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use sn_client::test_utils::{get_funded_wallet, get_new_client, pay_for_storage};
+use sn_client::RegisterMap;
+use sn_protocol::NetworkAddress;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Settings {
+    theme: String,
+    font_size: u32,
+    notes: Vec<u8>,
+}
+
+fn settings(theme: &str, notes_size: usize) -> Settings {
+    Settings {
+        theme: theme.to_string(),
+        font_size: 12,
+        notes: vec![7; notes_size],
+    }
+}
+
+#[tokio::test]
+async fn test_register_map_basics() -> Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_dir = tmp_dir.path();
+    let client = get_new_client(SecretKey::random()).await?;
+    let mut map = RegisterMap::<String, Settings>::new(client, wallet_dir, None)?;
+
+    let small = settings("dark", 10);
+    let large = settings("light", 4096);
+    let _ = map.insert(&"alice".to_string(), &small)?;
+    let _ = map.insert(&"bob".to_string(), &large)?;
+    // only the value too large for a Register entry is stored in a chunk
+    assert_eq!(map.chunk_addrs_to_pay().len(), 1);
+
+    assert_eq!(map.get(&"alice".to_string()).await?, Some(small.clone()));
+    assert_eq!(map.get(&"bob".to_string()).await?, Some(large.clone()));
+    assert_eq!(map.get(&"carol".to_string()).await?, None);
+    assert!(map.contains_key(&"alice".to_string())?);
+
+    // replacing a value keeps a single value for the key
+    let replaced = settings("solarized", 10);
+    let _ = map.insert(&"alice".to_string(), &replaced)?;
+    assert_eq!(
+        map.get_all(&"alice".to_string()).await?,
+        vec![replaced.clone()]
+    );
+
+    assert!(map.remove(&"bob".to_string())?.is_some());
+    assert!(map.remove(&"bob".to_string())?.is_none());
+    assert!(!map.contains_key(&"bob".to_string())?);
+    assert_eq!(map.get(&"bob".to_string()).await?, None);
+    assert_eq!(map.iter().await?, vec![("alice".to_string(), replaced)]);
+
+    // a removed key can be set again
+    let _ = map.insert(&"bob".to_string(), &small)?;
+    assert_eq!(map.get(&"bob".to_string()).await?, Some(small));
+    assert_eq!(map.iter().await?.len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_map_concurrent_writers() -> Result<()> {
+    let owner_sk = SecretKey::random();
+    let client = get_new_client(owner_sk).await?;
+    let tmp_dir = tempfile::tempdir()?;
+    let wallet_dir = tmp_dir.path();
+    let _ = get_funded_wallet(&client, wallet_dir).await?;
+
+    let mut map = RegisterMap::<String, Settings>::new(client.clone(), wallet_dir, None)?;
+    let _ = map.insert(&"alice".to_string(), &settings("dark", 10))?;
+    let mut addrs2pay: Vec<NetworkAddress> = vec![map.as_net_addr()];
+    addrs2pay.extend(map.chunk_addrs_to_pay());
+    pay_for_storage(&client, wallet_dir, addrs2pay).await?;
+    map.sync(Default::default()).await?;
+
+    // two writers set the same key concurrently, one of the values stored in a chunk
+    let mut writer1 =
+        RegisterMap::<String, Settings>::retrieve(client.clone(), wallet_dir, *map.address())
+            .await?;
+    let mut writer2 =
+        RegisterMap::<String, Settings>::retrieve(client.clone(), wallet_dir, *map.address())
+            .await?;
+    let value1 = settings("light", 10);
+    let value2 = settings("solarized", 4096);
+    let _ = writer1.insert(&"alice".to_string(), &value1)?;
+    let _ = writer2.insert(&"alice".to_string(), &value2)?;
+    let _ = writer2.insert(&"bob".to_string(), &value1)?;
+    pay_for_storage(
+        &client,
+        wallet_dir,
+        writer2.chunk_addrs_to_pay().into_iter().collect(),
+    )
+    .await?;
+    writer1.sync(Default::default()).await?;
+    writer2.sync(Default::default()).await?;
+    writer1.sync(Default::default()).await?;
+
+    // both writers and a fresh replica resolve the conflict to the same latest value
+    let mut reader =
+        RegisterMap::<String, Settings>::retrieve(client, wallet_dir, *map.address()).await?;
+    let latest = reader.get(&"alice".to_string()).await?;
+    assert!(latest == Some(value1.clone()) || latest == Some(value2.clone()));
+    assert_eq!(writer1.get(&"alice".to_string()).await?, latest);
+    assert_eq!(writer2.get(&"alice".to_string()).await?, latest);
+    let all = reader.get_all(&"alice".to_string()).await?;
+    assert_eq!(all.len(), 2);
+    assert_eq!(Some(&all[0]), latest.as_ref());
+    assert!(all.contains(&value1) && all.contains(&value2));
+    assert_eq!(reader.get(&"bob".to_string()).await?, Some(value1.clone()));
+
+    // setting the key atop the concurrent values resolves the conflict
+    let resolved = settings("resolved", 10);
+    let _ = reader.insert(&"alice".to_string(), &resolved)?;
+    reader.sync(Default::default()).await?;
+    writer1.sync(Default::default()).await?;
+    assert_eq!(writer1.get_all(&"alice".to_string()).await?, vec![resolved]);
+
+    Ok(())
+}
@@ -10,7 +10,10 @@ use crate::{
     driver::{PendingGetClosestType, SwarmDriver},
     error::{NetworkError, Result},
     event::TerminateNodeReason,
-    multiaddr_pop_p2p, GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
+    hedged_fetch::PeerLatencies,
+    record_cache::CachedRecord,
+    replication_fetcher::ReplicationMode,
+    GetRecordCfg, GetRecordError, MsgResponder, NetworkEvent, CLOSE_GROUP_SIZE,
     REPLICATION_PEERS_COUNT,
 };
 use libp2p::{
@@ -22,7 +25,7 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use sn_protocol::{
-    messages::{Cmd, Request, Response},
+    messages::{Cmd, RecordMetadata, ReplicationSketch, Request, Response},
     storage::{RecordHeader, RecordKind, RecordType},
     NetworkAddress, PrettyPrintRecordKey,
 };
@@ -126,6 +129,11 @@ pub enum SwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<(NanoTokens, QuotingMetrics)>,
     },
+    /// GetLocalStoreCost of each of the keys for this node
+    GetLocalStoreCosts {
+        keys: Vec<RecordKey>,
+        sender: oneshot::Sender<Vec<(NanoTokens, QuotingMetrics)>>,
+    },
     /// Notify the node received a payment.
     PaymentReceived,
     /// Get data from the local RecordStore
@@ -133,6 +141,30 @@ pub enum SwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<Option<Record>>,
     },
+    /// Get the metadata of a record held by the local RecordStore
+    GetLocalRecordMetadata {
+        key: RecordKey,
+        sender: oneshot::Sender<Option<RecordMetadata>>,
+    },
+    /// Get a record from the client record cache
+    GetCachedRecord {
+        key: RecordKey,
+        sender: oneshot::Sender<Option<CachedRecord>>,
+    },
+    /// Report the outcome of reading a chunk the client record cache holds on disk
+    CachedChunkRead {
+        key: RecordKey,
+        record: Option<Record>,
+    },
+    /// Add a record fetched from the network to the client record cache
+    CacheFetchedRecord {
+        record: Record,
+    },
+    /// Rank the peers by their response latency
+    GetPeerLatencies {
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<PeerLatencies>,
+    },
     /// Put record to network
     PutRecord {
         record: Record,
@@ -179,6 +211,11 @@ pub enum SwarmCmd {
     },
     // Notify a fetch completion
     FetchCompleted(RecordKey),
+    /// Outcome of re-verifying a batch of locally stored records
+    LocalRecordsScrubbed {
+        checked: usize,
+        corrupt_keys: Vec<RecordKey>,
+    },
 }
 
 /// Debug impl for SwarmCmd to avoid printing full Record, instead only RecodKey
@@ -234,6 +271,19 @@ impl Debug for SwarmCmd {
             SwarmCmd::TriggerIntervalReplication => {
                 write!(f, "SwarmCmd::TriggerIntervalReplication")
             }
+            SwarmCmd::LocalRecordsScrubbed {
+                checked,
+                corrupt_keys,
+            } => {
+                write!(
+                    f,
+                    "SwarmCmd::LocalRecordsScrubbed {{ checked: {checked}, corrupt_keys: {:?} }}",
+                    corrupt_keys
+                        .iter()
+                        .map(PrettyPrintRecordKey::from)
+                        .collect::<Vec<_>>()
+                )
+            }
             SwarmCmd::DialWithOpts { opts, .. } => {
                 write!(f, "SwarmCmd::DialWithOpts {{ opts: {opts:?} }}")
             }
@@ -249,6 +299,9 @@ impl Debug for SwarmCmd {
             SwarmCmd::GetLocalStoreCost { .. } => {
                 write!(f, "SwarmCmd::GetLocalStoreCost")
             }
+            SwarmCmd::GetLocalStoreCosts { keys, .. } => {
+                write!(f, "SwarmCmd::GetLocalStoreCosts({} keys)", keys.len())
+            }
             SwarmCmd::PaymentReceived => {
                 write!(f, "SwarmCmd::PaymentReceived")
             }
@@ -259,6 +312,38 @@ impl Debug for SwarmCmd {
                     PrettyPrintRecordKey::from(key)
                 )
             }
+            SwarmCmd::GetLocalRecordMetadata { key, .. } => {
+                write!(
+                    f,
+                    "SwarmCmd::GetLocalRecordMetadata {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
+            SwarmCmd::GetCachedRecord { key, .. } => {
+                write!(
+                    f,
+                    "SwarmCmd::GetCachedRecord {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(key)
+                )
+            }
+            SwarmCmd::CachedChunkRead { key, record } => {
+                write!(
+                    f,
+                    "SwarmCmd::CachedChunkRead {{ key: {:?}, read: {} }}",
+                    PrettyPrintRecordKey::from(key),
+                    record.is_some()
+                )
+            }
+            SwarmCmd::CacheFetchedRecord { record } => {
+                write!(
+                    f,
+                    "SwarmCmd::CacheFetchedRecord {{ key: {:?} }}",
+                    PrettyPrintRecordKey::from(&record.key)
+                )
+            }
+            SwarmCmd::GetPeerLatencies { peers, .. } => {
+                write!(f, "SwarmCmd::GetPeerLatencies {{ peers: {peers:?} }}")
+            }
             SwarmCmd::GetAllLocalRecordAddresses { .. } => {
                 write!(f, "SwarmCmd::GetAllLocalRecordAddresses")
             }
@@ -326,6 +411,7 @@ impl SwarmDriver {
             }
             SwarmCmd::GetNetworkRecord { key, sender, cfg } => {
                 cmd_string = "GetNetworkRecord";
+
                 let query_id = self.swarm.behaviour_mut().kademlia.get_record(key.clone());
 
                 debug!(
@@ -366,6 +452,20 @@ impl SwarmDriver {
 
                 let _res = sender.send(cost);
             }
+            SwarmCmd::GetLocalStoreCosts { keys, sender } => {
+                cmd_string = "GetLocalStoreCosts";
+                let store = self.swarm.behaviour_mut().kademlia.store_mut();
+                let costs: Vec<_> = keys.iter().map(|key| store.store_cost(key)).collect();
+                #[cfg(feature = "open-metrics")]
+                if let (Some(metrics), Some(max_cost)) = (
+                    &self.network_metrics,
+                    costs.iter().map(|(cost, _)| cost.as_nano()).max(),
+                ) {
+                    let _ = metrics.store_cost.set(max_cost as i64);
+                }
+
+                let _res = sender.send(costs);
+            }
             SwarmCmd::PaymentReceived => {
                 cmd_string = "PaymentReceived";
                 self.swarm
@@ -385,6 +485,46 @@ impl SwarmDriver {
                     .map(|rec| rec.into_owned());
                 let _ = sender.send(record);
             }
+            SwarmCmd::GetLocalRecordMetadata { key, sender } => {
+                cmd_string = "GetLocalRecordMetadata";
+                let metadata = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .record_metadata(&key);
+                let _ = sender.send(metadata);
+            }
+            SwarmCmd::GetCachedRecord { key, sender } => {
+                cmd_string = "GetCachedRecord";
+                let record = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .get_cached(&key);
+                let _ = sender.send(record);
+            }
+            SwarmCmd::CachedChunkRead { key, record } => {
+                cmd_string = "CachedChunkRead";
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .on_cached_chunk_read(&key, record);
+            }
+            SwarmCmd::CacheFetchedRecord { record } => {
+                cmd_string = "CacheFetchedRecord";
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .cache_fetched(record);
+            }
+            SwarmCmd::GetPeerLatencies { peers, sender } => {
+                cmd_string = "GetPeerLatencies";
+                let _ = sender.send(self.peer_latencies.rank(peers));
+            }
             SwarmCmd::PutRecord {
                 record,
                 sender,
@@ -392,6 +532,11 @@ impl SwarmDriver {
             } => {
                 cmd_string = "PutRecord";
                 let record_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .invalidate_cached(&record.key);
                 trace!(
                     "Putting record sized: {:?} to network {:?}",
                     record.value.len(),
@@ -425,6 +570,11 @@ impl SwarmDriver {
             } => {
                 cmd_string = "PutRecordTo";
                 let record_key = PrettyPrintRecordKey::from(&record.key).into_owned();
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .invalidate_cached(&record.key);
                 trace!(
                     "Putting record {record_key:?} sized: {:?} to {peers:?}",
                     record.value.len(),
@@ -458,6 +608,10 @@ impl SwarmDriver {
                                 error!("Record {record_key:?} with payment shall not be stored locally.");
                                 return Err(NetworkError::InCorrectRecordHeader);
                             }
+                            RecordKind::RegisterOps => {
+                                error!("Record {record_key:?} of Register ops shall not be stored locally.");
+                                return Err(NetworkError::InCorrectRecordHeader);
+                            }
                         }
                     }
                     Err(err) => {
@@ -580,16 +734,7 @@ impl SwarmDriver {
             }
             SwarmCmd::Dial { addr, sender } => {
                 cmd_string = "Dial";
-
-                if let Some(peer_id) = multiaddr_pop_p2p(&mut addr.clone()) {
-                    // Only consider the dial peer is bootstrap node when proper PeerId is provided.
-                    if let Some(kbucket) = self.swarm.behaviour_mut().kademlia.kbucket(peer_id) {
-                        let ilog2 = kbucket.range().0.ilog2();
-                        let peers = self.bootstrap_peers.entry(ilog2).or_default();
-                        peers.insert(peer_id);
-                    }
-                }
-                let _ = match self.dial(addr) {
+                let _ = match self.dial_bootstrap_peer(addr) {
                     Ok(_) => sender.send(Ok(())),
                     Err(e) => sender.send(Err(e.into())),
                 };
@@ -683,6 +828,9 @@ impl SwarmDriver {
                         .request_response
                         .send_request(&peer, req);
                     trace!("Sending request {request_id:?} to peer {peer:?}");
+                    if sender.is_some() {
+                        self.peer_latencies.request_sent(request_id, peer);
+                    }
                     let _ = self.pending_requests.insert(request_id, sender);
 
                     trace!("Pending Requests now: {:?}", self.pending_requests.len());
@@ -712,7 +860,9 @@ impl SwarmDriver {
                             .behaviour_mut()
                             .request_response
                             .send_response(channel, resp)
-                            .map_err(NetworkError::OutgoingResponseDropped)?;
+                            .map_err(|resp| {
+                                NetworkError::OutgoingResponseDropped(Box::new(resp))
+                            })?;
                     }
                 }
             }
@@ -769,6 +919,23 @@ impl SwarmDriver {
                     self.send_event(NetworkEvent::KeysToFetchForReplication(new_keys_to_fetch));
                 }
             }
+            SwarmCmd::LocalRecordsScrubbed {
+                checked,
+                corrupt_keys,
+            } => {
+                cmd_string = "LocalRecordsScrubbed";
+                info!(
+                    "Scrubbed {checked} local records, {} found corrupt",
+                    corrupt_keys.len()
+                );
+                #[cfg(feature = "open-metrics")]
+                if let Some(metrics) = &self.network_metrics {
+                    let _ = metrics.records_scrubbed.inc_by(checked as u64);
+                }
+                for key in corrupt_keys {
+                    self.replace_corrupt_local_record(key);
+                }
+            }
         }
 
         self.log_handling(cmd_string.to_string(), start.elapsed());
@@ -776,6 +943,53 @@ impl SwarmDriver {
         Ok(())
     }
 
+    /// Remove a local record that failed the integrity check,
+    /// then fetch a fresh copy from the closest peer to it.
+    fn replace_corrupt_local_record(&mut self, key: RecordKey) {
+        let pretty_key = PrettyPrintRecordKey::from(&key).into_owned();
+        let store = self.swarm.behaviour_mut().kademlia.store_mut();
+        // The record may have been replaced or pruned while being scrubbed.
+        let Some((address, record_type)) = store.record_addresses_ref().get(&key).cloned() else {
+            debug!("Corrupt record {pretty_key:?} is no longer held, skip removing it");
+            return;
+        };
+
+        warn!("Removing corrupt record {pretty_key:?} from the local store");
+        store.remove(&key);
+        #[cfg(feature = "open-metrics")]
+        if let Some(metrics) = &self.network_metrics {
+            let _ = metrics.corrupt_records_removed.inc();
+        }
+
+        let self_peer_id = self.self_peer_id;
+        let holder = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .get_closest_local_peers(&address.as_kbucket_key())
+            .map(|peer| peer.into_preimage())
+            .find(|peer_id| *peer_id != self_peer_id);
+        let Some(holder) = holder else {
+            warn!("No peer to fetch a fresh copy of corrupt record {pretty_key:?} from");
+            return;
+        };
+
+        #[allow(clippy::mutable_key_type)]
+        let all_keys = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref();
+        let keys_to_fetch =
+            self.replication_fetcher
+                .add_keys(holder, vec![(address, record_type)], all_keys);
+        if !keys_to_fetch.is_empty() {
+            debug!("Fetching a fresh copy of corrupt record {pretty_key:?} from {holder:?}");
+            self.send_event(NetworkEvent::KeysToFetchForReplication(keys_to_fetch));
+        }
+    }
+
     fn record_node_issue(&mut self, peer_id: PeerId, issue: NodeIssue) {
         info!("Peer {peer_id:?} is reported as having issue {issue:?}");
         let (issue_vec, is_bad) = self.bad_nodes.entry(peer_id).or_default();
@@ -890,26 +1104,53 @@ impl SwarmDriver {
             .cloned()
             .collect();
 
-        if !all_records.is_empty() {
-            trace!(
-                "Sending a replication list of {} keys to {replicate_targets:?} ",
-                all_records.len()
-            );
-            let request = Request::Cmd(Cmd::Replicate {
-                holder: NetworkAddress::from_peer(self.self_peer_id),
-                keys: all_records,
-            });
-            for peer_id in replicate_targets {
-                let request_id = self
+        let request = match self.replication_mode {
+            ReplicationMode::FullKeyList => {
+                if all_records.is_empty() {
+                    return Ok(());
+                }
+                trace!(
+                    "Sending a replication list of {} keys to {replicate_targets:?} ",
+                    all_records.len()
+                );
+                Request::Cmd(Cmd::Replicate {
+                    holder: NetworkAddress::from_peer(self.self_peer_id),
+                    keys: all_records,
+                })
+            }
+            ReplicationMode::Sketch => {
+                // Sent even when holding nothing, as the peers reply with what we are missing.
+                // A fresh seed each round avoids hiding the same keys behind a false positive.
+                let sketch = ReplicationSketch::from_keys(all_records.iter(), rand::random());
+                trace!(
+                    "Sending a replication sketch of {} keys in {} bytes to {replicate_targets:?}",
+                    sketch.num_keys(),
+                    sketch.size_in_bytes()
+                );
+                let distance_range = self
                     .swarm
                     .behaviour_mut()
-                    .request_response
-                    .send_request(&peer_id, request.clone());
-                trace!("Sending request {request_id:?} to peer {peer_id:?}");
-                let _ = self.pending_requests.insert(request_id, None);
+                    .kademlia
+                    .store_mut()
+                    .get_farthest_replication_distance_bucket();
+                Request::Cmd(Cmd::ReplicateSketch {
+                    holder: NetworkAddress::from_peer(self.self_peer_id),
+                    sketch,
+                    distance_range,
+                })
             }
-            trace!("Pending Requests now: {:?}", self.pending_requests.len());
+        };
+
+        for peer_id in replicate_targets {
+            let request_id = self
+                .swarm
+                .behaviour_mut()
+                .request_response
+                .send_request(&peer_id, request.clone());
+            trace!("Sending request {request_id:?} to peer {peer_id:?}");
+            let _ = self.pending_requests.insert(request_id, None);
         }
+        trace!("Pending Requests now: {:?}", self.pending_requests.len());
 
         Ok(())
     }
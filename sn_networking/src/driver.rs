@@ -16,12 +16,18 @@ use crate::{
     cmd::SwarmCmd,
     error::{NetworkError, Result},
     event::{NetworkEvent, NodeEvent},
+    hedged_fetch::{PeerLatencyTracker, RecordFetchStrategy},
     multiaddr_pop_p2p,
     network_discovery::NetworkDiscovery,
+    peer_cache::{PeerCache, PEER_CACHE_PERSIST_INTERVAL},
+    pricing::{DefaultPricingStrategy, PricingStrategy},
+    record_cache::RecordCacheConfig,
+    record_scrubber::{DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL, RECORD_SCRUB_INTERVAL},
     record_store::{ClientRecordStore, NodeRecordStore, NodeRecordStoreConfig},
     record_store_api::UnifiedRecordStore,
+    record_store_backend::RecordStorageBackendKind,
     relay_manager::RelayManager,
-    replication_fetcher::ReplicationFetcher,
+    replication_fetcher::{ReplicationFetcher, ReplicationMode},
     target_arch::{interval, spawn, Instant},
     version::{
         IDENTIFY_CLIENT_VERSION_STR, IDENTIFY_NODE_VERSION_STR, IDENTIFY_PROTOCOL_STR,
@@ -115,6 +121,9 @@ const NETWORKING_CHANNEL_SIZE: usize = 10_000;
 /// Time before a Kad query times out if no response is received
 const KAD_QUERY_TIMEOUT_S: Duration = Duration::from_secs(10);
 
+/// Domain used to derive the record store encryption seed from the node keypair
+const RECORD_STORE_ENCRYPTION_DOMAIN: &[u8] = b"sn_networking record_store encryption";
+
 /// The various settings to apply to when fetching a record from network
 #[derive(Clone)]
 pub struct GetRecordCfg {
@@ -126,6 +135,8 @@ pub struct GetRecordCfg {
     pub target_record: Option<Record>,
     /// Logs if the record was not fetched from the provided set of peers.
     pub expected_holders: HashSet<PeerId>,
+    /// How the record gets fetched from the close group.
+    pub fetch_strategy: RecordFetchStrategy,
 }
 
 impl GetRecordCfg {
@@ -150,7 +161,9 @@ impl Debug for GetRecordCfg {
             }
         };
 
-        f.field("expected_holders", &self.expected_holders).finish()
+        f.field("expected_holders", &self.expected_holders)
+            .field("fetch_strategy", &self.fetch_strategy)
+            .finish()
     }
 }
 
@@ -208,6 +221,12 @@ pub struct NetworkBuilder {
     request_timeout: Option<Duration>,
     concurrency_limit: Option<usize>,
     initial_peers: Vec<Multiaddr>,
+    record_storage_backend: RecordStorageBackendKind,
+    max_storage_bytes: Option<u64>,
+    pricing_strategy: Arc<dyn PricingStrategy>,
+    records_scrubbed_per_interval: usize,
+    replication_mode: ReplicationMode,
+    record_cache: Option<RecordCacheConfig>,
     #[cfg(feature = "open-metrics")]
     metrics_registry: Option<Registry>,
     #[cfg(feature = "open-metrics")]
@@ -228,6 +247,12 @@ impl NetworkBuilder {
             request_timeout: None,
             concurrency_limit: None,
             initial_peers: Default::default(),
+            record_storage_backend: Default::default(),
+            max_storage_bytes: None,
+            pricing_strategy: Arc::new(DefaultPricingStrategy),
+            records_scrubbed_per_interval: DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL,
+            replication_mode: Default::default(),
+            record_cache: None,
             #[cfg(feature = "open-metrics")]
             metrics_registry: None,
             #[cfg(feature = "open-metrics")]
@@ -257,6 +282,38 @@ impl NetworkBuilder {
         self.initial_peers = initial_peers;
     }
 
+    /// Select the on-disk layout used by the node's record store.
+    pub fn record_storage_backend(&mut self, record_storage_backend: RecordStorageBackendKind) {
+        self.record_storage_backend = record_storage_backend;
+    }
+
+    /// Limit the number of bytes the node's record store may occupy on disk.
+    pub fn max_storage_bytes(&mut self, max_storage_bytes: Option<u64>) {
+        self.max_storage_bytes = max_storage_bytes;
+    }
+
+    /// Set the strategy used to price store cost quotes. Peers verify quotes against the
+    /// default curve, so only use another strategy for simulations and test networks.
+    pub fn pricing_strategy(&mut self, pricing_strategy: Arc<dyn PricingStrategy>) {
+        self.pricing_strategy = pricing_strategy;
+    }
+
+    /// Set how many stored records get re-verified against their address every minute.
+    /// Zero disables the integrity scrubbing.
+    pub fn record_scrub_rate(&mut self, records_per_minute: usize) {
+        self.records_scrubbed_per_interval = records_per_minute;
+    }
+
+    /// Select how the held keys are advertised to close peers during periodic replication.
+    pub fn replication_mode(&mut self, replication_mode: ReplicationMode) {
+        self.replication_mode = replication_mode;
+    }
+
+    /// Cache the records fetched by a client. Disabled if not set. Unused by nodes.
+    pub fn record_cache(&mut self, record_cache: Option<RecordCacheConfig>) {
+        self.record_cache = record_cache;
+    }
+
     #[cfg(feature = "open-metrics")]
     pub fn metrics_registry(&mut self, metrics_registry: Option<Registry>) {
         self.metrics_registry = metrics_registry;
@@ -323,10 +380,18 @@ impl NetworkBuilder {
                     source: error,
                 });
             }
+            let encryption_seed = self
+                .keypair
+                .derive_secret(RECORD_STORE_ENCRYPTION_DOMAIN)
+                .ok_or(NetworkError::FailedToDeriveRecordStoreEncryptionSeed)?;
             NodeRecordStoreConfig {
                 max_value_bytes: MAX_PACKET_SIZE, // TODO, does this need to be _less_ than MAX_PACKET_SIZE
                 storage_dir: storage_dir_path,
                 historic_quote_dir: self.root_dir.clone(),
+                max_storage_bytes: self.max_storage_bytes,
+                storage_backend: self.record_storage_backend,
+                pricing_strategy: self.pricing_strategy.clone(),
+                encryption_seed,
                 ..Default::default()
             }
         };
@@ -334,6 +399,8 @@ impl NetworkBuilder {
         let listen_addr = self.listen_addr;
         #[cfg(feature = "upnp")]
         let upnp = self.upnp;
+        let peer_cache = PeerCache::load(&self.root_dir);
+        let initial_peers = self.initial_peers.clone();
 
         let (network, events_receiver, mut swarm_driver) = self.build(
             kad_cfg,
@@ -345,6 +412,12 @@ impl NetworkBuilder {
             upnp,
         )?;
 
+        // Rejoin from the previous neighbourhood, the cached peers get dialed once we are listening.
+        // The initial peers are only dialed if none of them can be reached.
+        swarm_driver.cached_peers_to_dial = peer_cache.peers();
+        swarm_driver.peer_cache = Some(peer_cache);
+        swarm_driver.initial_peers_to_dial = initial_peers;
+
         // Listen on the provided address
         let listen_socket_addr = listen_addr.ok_or(NetworkError::ListenAddressNotProvided)?;
 
@@ -478,7 +551,9 @@ impl NetworkBuilder {
                 }
                 // no cfg provided for client
                 None => {
-                    let store = UnifiedRecordStore::Client(ClientRecordStore::default());
+                    let store = UnifiedRecordStore::Client(ClientRecordStore::with_cache(
+                        self.record_cache.clone(),
+                    ));
                     debug!("Using Kademlia with ClientRecordStore!");
                     kad::Behaviour::with_config(peer_id, store, kad_cfg)
                 }
@@ -613,6 +688,13 @@ impl NetworkBuilder {
             bad_nodes: Default::default(),
             bad_nodes_ongoing_verifications: Default::default(),
             quotes_history: Default::default(),
+            records_scrubbed_per_interval: self.records_scrubbed_per_interval,
+            replication_mode: self.replication_mode,
+            peer_cache: None,
+            cached_peers_to_dial: Default::default(),
+            pending_cached_dials: Default::default(),
+            initial_peers_to_dial: Default::default(),
+            peer_latencies: Default::default(),
         };
 
         Ok((
@@ -670,6 +752,21 @@ pub struct SwarmDriver {
     pub(crate) bad_nodes: BadNodes,
     pub(crate) bad_nodes_ongoing_verifications: BTreeSet<PeerId>,
     pub(crate) quotes_history: BTreeMap<PeerId, PaymentQuote>,
+    /// The number of stored records to re-verify every `RECORD_SCRUB_INTERVAL`.
+    records_scrubbed_per_interval: usize,
+    /// How the held keys are advertised during periodic replication.
+    pub(crate) replication_mode: ReplicationMode,
+    /// The peers of the routing table persisted across restarts. Only set for nodes.
+    pub(crate) peer_cache: Option<PeerCache>,
+    /// The peers loaded from the peer cache, yet to be dialed.
+    pub(crate) cached_peers_to_dial: Vec<(PeerId, Vec<Multiaddr>)>,
+    /// The cached peers dialed on startup, until one of them gets connected or all of them fail.
+    pub(crate) pending_cached_dials: HashSet<PeerId>,
+    /// The initial peers, dialed once we are listening unless a cached peer gets connected.
+    /// Only set for nodes.
+    pub(crate) initial_peers_to_dial: Vec<Multiaddr>,
+    /// The latency of the responses to the awaited requests, to rank the peers for hedged fetches.
+    pub(crate) peer_latencies: PeerLatencyTracker,
 }
 
 impl SwarmDriver {
@@ -684,6 +781,12 @@ impl SwarmDriver {
         let mut bootstrap_interval = interval(BOOTSTRAP_INTERVAL);
         let mut set_farthest_record_interval = interval(CLOSET_RECORD_CHECK_INTERVAL);
         let mut relay_manager_reservation_interval = interval(RELAY_MANAGER_RESERVATION_INTERVAL);
+        let mut record_scrub_interval = interval(RECORD_SCRUB_INTERVAL);
+        let mut peer_cache_persist_interval = interval(PEER_CACHE_PERSIST_INTERVAL);
+        // The first ticks complete immediately, there is nothing worth scrubbing nor persisting
+        // at startup.
+        let _ = record_scrub_interval.tick().await;
+        let _ = peer_cache_persist_interval.tick().await;
 
         loop {
             tokio::select! {
@@ -726,6 +829,12 @@ impl SwarmDriver {
                     }
                 }
                 _ = relay_manager_reservation_interval.tick() => self.relay_manager.try_connecting_to_relay(&mut self.swarm, &self.bad_nodes),
+                _ = record_scrub_interval.tick() => {
+                    if !self.is_client && self.records_scrubbed_per_interval > 0 {
+                        self.swarm.behaviour_mut().kademlia.store_mut().scrub_records(self.records_scrubbed_per_interval);
+                    }
+                }
+                _ = peer_cache_persist_interval.tick() => self.persist_peer_cache(),
             }
         }
     }
@@ -831,6 +940,19 @@ impl SwarmDriver {
         self.swarm.dial(opts)
     }
 
+    /// Dials a peer we bootstrap from, noting it as such when its `PeerId` is provided.
+    pub(crate) fn dial_bootstrap_peer(&mut self, addr: Multiaddr) -> Result<(), DialError> {
+        if let Some(peer_id) = multiaddr_pop_p2p(&mut addr.clone()) {
+            // Only consider the dial peer is bootstrap node when proper PeerId is provided.
+            if let Some(kbucket) = self.swarm.behaviour_mut().kademlia.kbucket(peer_id) {
+                let ilog2 = kbucket.range().0.ilog2();
+                let peers = self.bootstrap_peers.entry(ilog2).or_default();
+                peers.insert(peer_id);
+            }
+        }
+        self.dial(addr)
+    }
+
     /// Dials with the `DialOpts` given.
     pub(crate) fn dial_with_opts(&mut self, opts: DialOpts) -> Result<(), DialError> {
         trace!(?opts, "Dialing manually");
@@ -125,6 +125,12 @@ pub enum NetworkError {
     #[error("Record header is incorrect")]
     InCorrectRecordHeader,
 
+    #[error("Unexpected response received for the record metadata query: {0}")]
+    UnexpectedRecordMetadataResponse(Box<Response>),
+
+    #[error("Unexpected response received for the replicated record query: {0}")]
+    UnexpectedReplicatedRecordResponse(Box<Response>),
+
     // ---------- Transfer Errors
     #[error("Failed to get spend: {0}")]
     FailedToGetSpend(String),
@@ -151,6 +157,9 @@ pub enum NetworkError {
         source: std::io::Error,
     },
 
+    #[error("Could not derive the record store encryption seed from the node keypair")]
+    FailedToDeriveRecordStoreEncryptionSeed,
+
     // ---------- Internal Network Errors
     #[error("Could not get enough peers ({required}) to satisfy the request, found {found}")]
     NotEnoughPeers { found: usize, required: usize },
@@ -182,7 +191,7 @@ pub enum NetworkError {
     ReceivedResponseDropped(OutboundRequestId),
 
     #[error("Outgoing response has been dropped due to a conn being closed or timeout: {0}")]
-    OutgoingResponseDropped(Response),
+    OutgoingResponseDropped(Box<Response>),
 
     #[error("Error setting up behaviour: {0}")]
     BahviourErr(String),
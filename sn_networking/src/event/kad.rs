@@ -399,7 +399,7 @@ impl SwarmDriver {
                 let (sender, result_map, _) = entry.remove();
 
                 if result_map.len() == 1 {
-                    Self::send_record_after_checking_target(sender, peer_record.record, &cfg)?;
+                    self.send_record_after_checking_target(sender, peer_record.record, &cfg)?;
                 } else {
                     debug!("For record {pretty_key:?} task {query_id:?}, fetch completed with split record");
                     sender
@@ -551,7 +551,7 @@ impl SwarmDriver {
                 // if we have enough responses here, we can return the record
                 if let Some((record, peers)) = result_map.values().next() {
                     if peers.len() >= required_response_count {
-                        Self::send_record_after_checking_target(sender, record.clone(), &cfg)?;
+                        self.send_record_after_checking_target(sender, record.clone(), &cfg)?;
                         return Ok(());
                     }
                 }
@@ -568,11 +568,19 @@ impl SwarmDriver {
     }
 
     fn send_record_after_checking_target(
+        &mut self,
         sender: oneshot::Sender<std::result::Result<Record, GetRecordError>>,
         record: Record,
         cfg: &GetRecordCfg,
     ) -> Result<()> {
         if cfg.target_record.is_none() || cfg.does_target_match(&record) {
+            if self.is_client {
+                self.swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .cache_fetched(record.clone());
+            }
             sender
                 .send(Ok(record))
                 .map_err(|_| NetworkError::InternalMsgChannelDropped)
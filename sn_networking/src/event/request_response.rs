@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    sort_peers_by_address, MsgResponder, NetworkError, NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE,
-    REPLICATION_PEERS_COUNT,
+    replication_fetcher::keys_missing_from_sketch, sort_peers_by_address, MsgResponder,
+    NetworkError, NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE, REPLICATION_PEERS_COUNT,
 };
 use itertools::Itertools;
 use libp2p::{
@@ -17,7 +17,7 @@ use libp2p::{
 };
 use rand::{rngs::OsRng, Rng};
 use sn_protocol::{
-    messages::{CmdResponse, Request, Response},
+    messages::{Cmd, CmdResponse, ReplicationSketch, Request, Response},
     storage::RecordType,
     NetworkAddress,
 };
@@ -53,6 +53,22 @@ impl SwarmDriver {
 
                             self.add_keys_to_replication_fetcher(holder, keys);
                         }
+                        Request::Cmd(sn_protocol::messages::Cmd::ReplicateSketch {
+                            holder,
+                            sketch,
+                            distance_range,
+                        }) => {
+                            let response = Response::Cmd(
+                                sn_protocol::messages::CmdResponse::Replicate(Ok(())),
+                            );
+                            self.swarm
+                                .behaviour_mut()
+                                .request_response
+                                .send_response(channel, response)
+                                .map_err(|_| NetworkError::InternalMsgChannelDropped)?;
+
+                            self.reply_to_replication_sketch(holder, sketch, distance_range);
+                        }
                         Request::Cmd(sn_protocol::messages::Cmd::QuoteVerification {
                             quotes,
                             ..
@@ -113,6 +129,7 @@ impl SwarmDriver {
                     response,
                 } => {
                     trace!("Got response {request_id:?} from peer {peer:?}, res: {response}.");
+                    self.peer_latencies.response_received(&request_id);
                     if let Some(sender) = self.pending_requests.remove(&request_id) {
                         // The sender will be provided if the caller (Requester) is awaiting for a response
                         // at the call site.
@@ -147,6 +164,7 @@ impl SwarmDriver {
                 error,
                 peer,
             } => {
+                self.peer_latencies.request_failed(&request_id);
                 if let Some(sender) = self.pending_requests.remove(&request_id) {
                     match sender {
                         Some(sender) => {
@@ -248,6 +266,81 @@ impl SwarmDriver {
         }
     }
 
+    /// On receiving the sketch of a close peer's keys, send back the keys we hold that are
+    /// missing from it, for the peer to fetch them through its `ReplicationFetcher`.
+    fn reply_to_replication_sketch(
+        &mut self,
+        sender: NetworkAddress,
+        sketch: ReplicationSketch,
+        distance_range: Option<u32>,
+    ) {
+        let holder = if let Some(peer_id) = sender.as_peer_id() {
+            peer_id
+        } else {
+            warn!("Replication sketch sender is not a peer_id {sender:?}");
+            return;
+        };
+
+        trace!(
+            "Received replication sketch from {holder:?} of {} keys",
+            sketch.num_keys()
+        );
+
+        let closest_k_peers = self.get_closest_k_value_local_peers();
+        if !closest_k_peers.contains(&holder) || holder == self.self_peer_id {
+            trace!("Holder {holder:?} is self or not in replication range.");
+            return;
+        }
+
+        #[allow(clippy::mutable_key_type)]
+        let locally_stored_keys = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .record_addresses_ref();
+        let missing_keys: Vec<_> = keys_missing_from_sketch(
+            &sender,
+            &sketch,
+            distance_range,
+            locally_stored_keys.values(),
+        )
+        .into_iter()
+        .filter(|(key, _record_type)| Self::is_in_close_range(&holder, key, &closest_k_peers))
+        .collect();
+
+        if missing_keys.is_empty() {
+            trace!("Holder {holder:?} is not missing any of our keys.");
+        } else {
+            debug!(
+                "Holder {holder:?} is missing {} of our keys, sending them over",
+                missing_keys.len()
+            );
+            let request = Request::Cmd(Cmd::Replicate {
+                holder: NetworkAddress::from_peer(self.self_peer_id),
+                keys: missing_keys,
+            });
+            let request_id = self
+                .swarm
+                .behaviour_mut()
+                .request_response
+                .send_request(&holder, request);
+            let _ = self.pending_requests.insert(request_id, None);
+        }
+
+        // The sketch replaces the periodical replication list, hence the chunk_proof check
+        // against the sender is triggered here.
+        let keys_to_verify = self.select_verification_data_candidates(sender);
+        if keys_to_verify.is_empty() {
+            debug!("No valid candidate to be checked against peer {holder:?}");
+        } else {
+            self.send_event(NetworkEvent::ChunkProofVerification {
+                peer_id: holder,
+                keys_to_verify,
+            });
+        }
+    }
+
     /// Checks suggested records against what we hold, so we only
     /// enqueue what we do not have
     fn select_non_existent_records_for_replications(
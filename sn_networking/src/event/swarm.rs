@@ -339,6 +339,7 @@ impl SwarmDriver {
                 }
 
                 self.send_event(NetworkEvent::NewListenAddr(address.clone()));
+                self.dial_cached_peers();
 
                 info!("Local node is listening {listener_id:?} on {address:?}");
             }
@@ -379,6 +380,7 @@ impl SwarmDriver {
                 if endpoint.is_dialer() {
                     self.dialed_peers.push(peer_id);
                 }
+                self.on_cached_peer_dialed(&peer_id, true);
             }
             SwarmEvent::ConnectionClosed {
                 peer_id,
@@ -406,6 +408,7 @@ impl SwarmDriver {
             } => {
                 event_string = "OutgoingConnErr";
                 warn!("OutgoingConnectionError to {failed_peer_id:?} on {connection_id:?} - {error:?}");
+                self.on_cached_peer_dialed(&failed_peer_id, false);
 
                 // we need to decide if this was a critical error and the peer should be removed from the routing table
                 let should_clean_peer = match error {
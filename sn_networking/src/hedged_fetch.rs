@@ -0,0 +1,400 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    cmd::SwarmCmd,
+    driver::GetRecordCfg,
+    error::{GetRecordError, NetworkError, Result},
+    get_quorum_value,
+    record_cache::{read_cached_chunk, CachedRecord},
+    record_scrubber::is_record_intact,
+    target_arch::{timeout, Instant},
+    Network, CLOSE_GROUP_SIZE,
+};
+use futures::{stream::FuturesUnordered, StreamExt};
+use libp2p::{
+    kad::{Record, RecordKey},
+    request_response::OutboundRequestId,
+    PeerId,
+};
+use sn_protocol::{
+    messages::{Query, QueryResponse, Request, Response},
+    NetworkAddress, PrettyPrintRecordKey,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+use xor_name::XorName;
+
+/// Number of latency samples kept for each peer.
+const MAX_SAMPLES_PER_PEER: usize = 16;
+
+/// Number of the most recent latency samples, over all peers, the hedge delay is derived from.
+const MAX_RECENT_SAMPLES: usize = 256;
+
+/// A backup fetch gets hedged once a fetch has been outstanding for longer than this
+/// percentile of the recent response latencies.
+const HEDGE_PERCENTILE: usize = 90;
+
+/// The number of recent samples required before trusting their percentile.
+const MIN_SAMPLES_FOR_PERCENTILE: usize = 10;
+
+/// The hedge delay used until enough latencies have been sampled.
+const DEFAULT_HEDGE_DELAY: Duration = Duration::from_secs(1);
+
+/// Bounds of the hedge delay, so a burst of fast or failed responses does not make hedging
+/// either flood the close group or never kick in.
+const MIN_HEDGE_DELAY: Duration = Duration::from_millis(100);
+const MAX_HEDGE_DELAY: Duration = Duration::from_secs(5);
+
+/// How a record gets fetched from the network.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordFetchStrategy {
+    /// A Kademlia `get_record` query, waiting for the quorum of the close group to respond.
+    #[default]
+    Kademlia,
+    /// Direct `GetReplicatedRecord` fetches from the close group, fastest known peers first.
+    /// A backup fetch is hedged to the next fastest peer whenever a fetch is outstanding for
+    /// longer than the usual response latency. Falls back to `Kademlia` if no peer serves it.
+    Hedged,
+}
+
+/// The peers ranked by their response latency, and how long to wait on a fetch before hedging.
+#[derive(Clone, Debug)]
+pub struct PeerLatencies {
+    /// Fastest first. Peers without a known latency are ranked as typical ones.
+    pub peers: Vec<PeerId>,
+    /// How long a fetch may be outstanding before a backup one gets hedged.
+    pub hedge_delay: Duration,
+}
+
+/// Tracks the latency of the responses to the requests awaited by the upper layers.
+#[derive(Debug, Default)]
+pub(crate) struct PeerLatencyTracker {
+    in_flight: HashMap<OutboundRequestId, (PeerId, Instant)>,
+    per_peer: HashMap<PeerId, VecDeque<Duration>>,
+    recent: VecDeque<Duration>,
+}
+
+impl PeerLatencyTracker {
+    pub(crate) fn request_sent(&mut self, request_id: OutboundRequestId, peer: PeerId) {
+        let _ = self.in_flight.insert(request_id, (peer, Instant::now()));
+    }
+
+    pub(crate) fn response_received(&mut self, request_id: &OutboundRequestId) {
+        if let Some((peer, sent_at)) = self.in_flight.remove(request_id) {
+            self.record_sample(peer, sent_at.elapsed());
+        }
+    }
+
+    /// A failed request is sampled with the time it took to fail, at least the max hedge delay,
+    /// so unreachable peers get ranked last.
+    pub(crate) fn request_failed(&mut self, request_id: &OutboundRequestId) {
+        if let Some((peer, sent_at)) = self.in_flight.remove(request_id) {
+            self.record_sample(peer, sent_at.elapsed().max(MAX_HEDGE_DELAY));
+        }
+    }
+
+    fn record_sample(&mut self, peer: PeerId, latency: Duration) {
+        let samples = self.per_peer.entry(peer).or_default();
+        samples.push_back(latency);
+        if samples.len() > MAX_SAMPLES_PER_PEER {
+            let _ = samples.pop_front();
+        }
+
+        self.recent.push_back(latency);
+        if self.recent.len() > MAX_RECENT_SAMPLES {
+            let _ = self.recent.pop_front();
+        }
+    }
+
+    /// The median of the latencies sampled for the peer.
+    fn estimate(&self, peer: &PeerId) -> Option<Duration> {
+        let samples = self.per_peer.get(peer)?;
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort_unstable();
+        sorted.get(sorted.len() / 2).copied()
+    }
+
+    fn hedge_delay(&self) -> Duration {
+        if self.recent.len() < MIN_SAMPLES_FOR_PERCENTILE {
+            return DEFAULT_HEDGE_DELAY;
+        }
+        let mut sorted: Vec<Duration> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = (sorted.len() * HEDGE_PERCENTILE / 100).min(sorted.len() - 1);
+        sorted[index].clamp(MIN_HEDGE_DELAY, MAX_HEDGE_DELAY)
+    }
+
+    /// Rank the peers fastest first. The sort is stable, so peers of equal latency keep the
+    /// order they are provided in.
+    pub(crate) fn rank(&self, mut peers: Vec<PeerId>) -> PeerLatencies {
+        let hedge_delay = self.hedge_delay();
+        peers.sort_by_key(|peer| self.estimate(peer).unwrap_or(hedge_delay));
+        PeerLatencies { peers, hedge_delay }
+    }
+}
+
+impl Network {
+    /// Fetch the record with the hedged strategy, if the cfg asks for it. Returns None otherwise,
+    /// or if the hedged fetch failed, for the caller to fall back to a Kademlia query.
+    pub(crate) async fn try_get_record_hedged(
+        &self,
+        key: &RecordKey,
+        cfg: &GetRecordCfg,
+    ) -> Option<Record> {
+        if cfg.fetch_strategy != RecordFetchStrategy::Hedged {
+            return None;
+        }
+        match self.get_record_hedged(key, cfg).await {
+            Ok(record) => Some(record),
+            Err(err) => {
+                warn!(
+                    "Hedged fetch of {:?} failed, falling back to Kademlia: {err:?}",
+                    PrettyPrintRecordKey::from(key)
+                );
+                None
+            }
+        }
+    }
+
+    /// Fetch a record with the `RecordFetchStrategy::Hedged` strategy, see there.
+    async fn get_record_hedged(&self, key: &RecordKey, cfg: &GetRecordCfg) -> Result<Record> {
+        let pretty_key = PrettyPrintRecordKey::from(key).into_owned();
+        let is_verification = cfg.target_record.is_some() || !cfg.expected_holders.is_empty();
+        let address = NetworkAddress::from_record_key(key);
+        let mut holders = self.get_close_group_local_peers(&address).await?;
+        holders.retain(|peer| peer != &*self.peer_id);
+        if holders.len() < CLOSE_GROUP_SIZE {
+            holders = self.get_closest_peers(&address, true).await?;
+        }
+        let PeerLatencies { peers, hedge_delay } = self.get_peer_latencies(holders).await?;
+        debug!("Hedged fetch of {pretty_key:?} from {peers:?}, hedging after {hedge_delay:?}");
+
+        let request = Request::Query(Query::GetReplicatedRecord {
+            requester: NetworkAddress::from_peer(*self.peer_id),
+            key: address,
+        });
+        let quorum = get_quorum_value(&cfg.get_quorum);
+        let mut candidates = peers.into_iter();
+        let mut in_flight = FuturesUnordered::new();
+        for peer in candidates.by_ref().take(quorum) {
+            in_flight.push(self.fetch_replicated_record(key, peer, request.clone()));
+        }
+
+        let mut copies: HashMap<XorName, (Record, usize)> = HashMap::new();
+        while !in_flight.is_empty() {
+            let (peer, result) = match timeout(hedge_delay, in_flight.next()).await {
+                Ok(Some(fetched)) => fetched,
+                Ok(None) => break,
+                Err(_elapsed) => {
+                    if let Some(backup) = candidates.next() {
+                        debug!("Fetch of {pretty_key:?} is slow, hedging to {backup:?}");
+                        in_flight.push(self.fetch_replicated_record(key, backup, request.clone()));
+                    }
+                    continue;
+                }
+            };
+
+            let record = match result {
+                Ok(record) => record,
+                Err(err) => {
+                    debug!("Peer {peer:?} did not serve {pretty_key:?}: {err:?}");
+                    if let Some(next) = candidates.next() {
+                        in_flight.push(self.fetch_replicated_record(key, next, request.clone()));
+                    }
+                    continue;
+                }
+            };
+
+            let (record, count) = copies
+                .entry(XorName::from_content(&record.value))
+                .or_insert((record, 0));
+            *count += 1;
+            if *count < quorum {
+                continue;
+            }
+
+            let record = record.clone();
+            if cfg.target_record.is_some() && !cfg.does_target_match(&record) {
+                return Err(GetRecordError::RecordDoesNotMatch(record).into());
+            }
+            if !is_verification {
+                self.send_swarm_cmd(SwarmCmd::CacheFetchedRecord {
+                    record: record.clone(),
+                });
+            }
+            return Ok(record);
+        }
+
+        match copies.into_values().max_by_key(|(_record, count)| *count) {
+            Some((record, got)) => Err(GetRecordError::NotEnoughCopies {
+                record,
+                expected: quorum,
+                got,
+            }
+            .into()),
+            None => Err(GetRecordError::RecordNotFound.into()),
+        }
+    }
+
+    /// Fetch the record straight from the peer, rejecting a corrupt copy.
+    async fn fetch_replicated_record(
+        &self,
+        key: &RecordKey,
+        peer: PeerId,
+        request: Request,
+    ) -> (PeerId, Result<Record>) {
+        let result = match self.send_request(request, peer).await {
+            Ok(Response::Query(QueryResponse::GetReplicatedRecord(Ok((_holder, value))))) => {
+                let record = Record::new(key.clone(), value.to_vec());
+                if is_record_intact(key, &record) {
+                    Ok(record)
+                } else {
+                    warn!("Peer {peer:?} served a corrupt copy of {key:?}");
+                    Err(GetRecordError::RecordNotFound.into())
+                }
+            }
+            Ok(Response::Query(QueryResponse::GetReplicatedRecord(Err(err)))) => {
+                Err(NetworkError::ProtocolError(err))
+            }
+            Ok(other) => Err(NetworkError::UnexpectedReplicatedRecordResponse(Box::new(
+                other,
+            ))),
+            Err(err) => Err(err),
+        };
+        (peer, result)
+    }
+
+    /// Rank the peers by their response latency.
+    pub async fn get_peer_latencies(&self, peers: Vec<PeerId>) -> Result<PeerLatencies> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetPeerLatencies { peers, sender });
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
+    /// The record cached by the client, if any. Verifications must reach the network, thus are
+    /// never served from the cache.
+    pub(crate) async fn get_cached_record(
+        &self,
+        key: &RecordKey,
+        cfg: &GetRecordCfg,
+    ) -> Result<Option<Record>> {
+        if cfg.target_record.is_some() || !cfg.expected_holders.is_empty() {
+            return Ok(None);
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetCachedRecord {
+            key: key.clone(),
+            sender,
+        });
+        let cached = receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)?;
+        let record = match cached {
+            Some(CachedRecord::InMemory(record)) => Some(record),
+            // Read here rather than on the swarm thread holding the cache.
+            Some(CachedRecord::OnDisk(path)) => {
+                let record = read_cached_chunk(key, &path);
+                self.send_swarm_cmd(SwarmCmd::CachedChunkRead {
+                    key: key.clone(),
+                    record: record.clone(),
+                });
+                record
+            }
+            None => None,
+        };
+        if record.is_some() {
+            debug!(
+                "Record {:?} served from the record cache",
+                PrettyPrintRecordKey::from(key)
+            );
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker_with(samples: &[(PeerId, u64)]) -> PeerLatencyTracker {
+        let mut tracker = PeerLatencyTracker::default();
+        for (peer, millis) in samples {
+            tracker.record_sample(*peer, Duration::from_millis(*millis));
+        }
+        tracker
+    }
+
+    #[test]
+    fn peers_are_ranked_fastest_first() {
+        let (fast, slow, unknown) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let typical = PeerId::random();
+        let mut samples = vec![(slow, 2_000), (slow, 2_000)];
+        for _ in 0..MIN_SAMPLES_FOR_PERCENTILE {
+            samples.push((fast, 50));
+            samples.push((typical, 300));
+            samples.push((typical, 300));
+        }
+        let tracker = tracker_with(&samples);
+
+        let ranked = tracker.rank(vec![slow, unknown, fast]);
+        // The unknown peer is ranked at the hedge delay, between the fast and the slow ones.
+        assert_eq!(ranked.peers, vec![fast, unknown, slow]);
+    }
+
+    #[test]
+    fn a_single_slow_response_does_not_outrank_a_fast_peer() {
+        let (fast, steady) = (PeerId::random(), PeerId::random());
+        let mut samples = vec![(fast, 3_000)];
+        for _ in 0..4 {
+            samples.push((fast, 40));
+            samples.push((steady, 200));
+        }
+        let tracker = tracker_with(&samples);
+
+        assert_eq!(tracker.rank(vec![steady, fast]).peers, vec![fast, steady]);
+    }
+
+    #[test]
+    fn hedge_delay_follows_the_latency_percentile() {
+        let tracker = tracker_with(&[(PeerId::random(), 300)]);
+        assert_eq!(tracker.hedge_delay(), DEFAULT_HEDGE_DELAY);
+
+        let peer = PeerId::random();
+        let samples: Vec<_> = (1..=100).map(|millis| (peer, millis * 10)).collect();
+        let tracker = tracker_with(&samples);
+        // 90% of the responses arrived within 910ms.
+        assert_eq!(tracker.hedge_delay(), Duration::from_millis(910));
+
+        let fast_samples: Vec<_> = (0..20).map(|_| (peer, 1)).collect();
+        assert_eq!(tracker_with(&fast_samples).hedge_delay(), MIN_HEDGE_DELAY);
+        let slow_samples: Vec<_> = (0..20).map(|_| (peer, 60_000)).collect();
+        assert_eq!(tracker_with(&slow_samples).hedge_delay(), MAX_HEDGE_DELAY);
+    }
+
+    #[test]
+    fn samples_are_bounded() {
+        let peer = PeerId::random();
+        let samples: Vec<_> = (0..MAX_RECENT_SAMPLES as u64 * 2)
+            .map(|millis| (peer, millis))
+            .collect();
+        let tracker = tracker_with(&samples);
+
+        assert_eq!(tracker.recent.len(), MAX_RECENT_SAMPLES);
+        assert_eq!(
+            tracker.per_peer.get(&peer).map(|samples| samples.len()),
+            Some(MAX_SAMPLES_PER_PEER)
+        );
+    }
+}
@@ -15,14 +15,20 @@ mod cmd;
 mod driver;
 mod error;
 mod event;
+mod hedged_fetch;
 mod log_markers;
 #[cfg(feature = "open-metrics")]
 mod metrics;
 #[cfg(feature = "open-metrics")]
 mod metrics_service;
 mod network_discovery;
+mod peer_cache;
+mod pricing;
+mod record_cache;
+mod record_scrubber;
 mod record_store;
 mod record_store_api;
+mod record_store_backend;
 mod relay_manager;
 mod replication_fetcher;
 mod spends;
@@ -39,23 +45,39 @@ pub use self::{
     driver::{GetRecordCfg, NetworkBuilder, PutRecordCfg, SwarmDriver, VerificationKind},
     error::{GetRecordError, NetworkError},
     event::{MsgResponder, NetworkEvent},
+    hedged_fetch::{PeerLatencies, RecordFetchStrategy},
+    pricing::{
+        replay_pricing_scenario, DefaultPricingStrategy, LinearPricingStrategy, PricingScenario,
+        PricingSimulationReport, PricingStrategy,
+    },
+    record_cache::{
+        RecordCacheConfig, DEFAULT_MUTABLE_RECORD_TTL, DEFAULT_RECORD_CACHE_DISK_BYTES,
+        DEFAULT_RECORD_CACHE_MEMORY_BYTES,
+    },
+    record_scrubber::DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL,
     record_store::{calculate_cost_for_records, NodeRecordStore},
+    record_store_backend::RecordStorageBackendKind,
+    replication_fetcher::ReplicationMode,
     transfers::{get_raw_signed_spends_from_record, get_signed_spend_from_record},
 };
 
 use self::{cmd::SwarmCmd, error::Result};
 use backoff::{Error as BackoffError, ExponentialBackoff};
-use futures::future::select_all;
+use futures::future::{join_all, select_all};
 use libp2p::{
     identity::Keypair,
     kad::{KBucketDistance, KBucketKey, Quorum, Record, RecordKey},
     multiaddr::Protocol,
+    request_response::OutboundFailure,
     Multiaddr, PeerId,
 };
 use rand::Rng;
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::{ChunkProof, Cmd, Nonce, Query, QueryResponse, Request, Response},
+    messages::{
+        ChunkProof, Cmd, Nonce, Query, QueryResponse, RecordMetadata, Request, Response,
+        MAX_STORE_COSTS_BATCH_SIZE,
+    },
     storage::{RecordType, RetryStrategy},
     NetworkAddress, PrettyPrintKBucketKey, PrettyPrintRecordKey,
 };
@@ -98,7 +120,7 @@ pub const fn close_group_majority() -> usize {
 /// Max duration to wait for verification.
 const MAX_WAIT_BEFORE_READING_A_PUT: Duration = Duration::from_millis(750);
 /// Min duration to wait for verification
-const MIN_WAIT_BEFORE_READING_A_PUT: Duration = Duration::from_millis(300);
+pub const MIN_WAIT_BEFORE_READING_A_PUT: Duration = Duration::from_millis(300);
 
 /// Sort the provided peers by their distance to the given `NetworkAddress`.
 /// Return with the closest expected number of entries if has.
@@ -348,7 +370,46 @@ impl Network {
         ))
     }
 
-    /// Get the store costs from the majority of the closest peers to the provided RecordKey.
+    /// Get the metadata of a record from each of the peers closest to it, without fetching
+    /// the record itself. Peers not holding the record, or failing to respond, get an `Err`.
+    pub async fn get_record_metadata_from_close_group(
+        &self,
+        key: &NetworkAddress,
+    ) -> Result<BTreeMap<PeerId, Result<RecordMetadata>>> {
+        let close_nodes = self.get_closest_peers(key, true).await?;
+
+        let request = Request::Query(Query::GetRecordMetadata { key: key.clone() });
+        let responses = self
+            .send_and_get_responses(&close_nodes, &request, true)
+            .await;
+
+        let metadata = responses
+            .into_iter()
+            .map(|(peer, resp)| {
+                let metadata = match resp {
+                    Ok(Response::Query(QueryResponse::GetRecordMetadata(Ok((
+                        _holder,
+                        metadata,
+                    ))))) => Ok(metadata),
+                    Ok(Response::Query(QueryResponse::GetRecordMetadata(Err(err)))) => {
+                        Err(NetworkError::ProtocolError(err))
+                    }
+                    Ok(other) => Err(NetworkError::UnexpectedRecordMetadataResponse(Box::new(
+                        other,
+                    ))),
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = &metadata {
+                    debug!("Did not get the metadata of {key:?} from {peer:?}: {err:?}");
+                }
+                (peer, metadata)
+            })
+            .collect();
+
+        Ok(metadata)
+    }
+
+    /// Get the store cost from the majority of the closest peers to the provided RecordKey.
     /// Record already exists will have a cost of zero to be returned.
     ///
     /// Ignore the quote from any peers from `ignore_peers`. This is useful if we want to repay a different PeerId
@@ -397,39 +458,182 @@ impl Network {
             }
         }
 
-        for peer_id in close_nodes.iter() {
-            let request = Request::Cmd(Cmd::QuoteVerification {
-                target: NetworkAddress::from_peer(*peer_id),
-                quotes: all_quotes.clone(),
-            });
-
-            self.send_req_ignore_reply(request, *peer_id);
-        }
+        self.send_quotes_for_verification(&close_nodes, all_quotes);
 
-        // Sort all_costs by the NetworkAddress proximity to record_address
-        all_costs.sort_by(|(peer_address_a, _, _), (peer_address_b, _, _)| {
-            record_address
-                .distance(peer_address_a)
-                .cmp(&record_address.distance(peer_address_b))
-        });
         #[allow(clippy::mutable_key_type)]
         let ignore_peers = ignore_peers
             .into_iter()
             .map(NetworkAddress::from_peer)
             .collect::<BTreeSet<_>>();
 
-        // Ensure we dont have any further out nodes than `close_group_majority()`
-        // This should ensure that if we didnt get all responses from close nodes,
-        // we're less likely to be paying a node that is not in the CLOSE_GROUP
-        //
-        // Also filter out the peers.
-        let all_costs = all_costs
-            .into_iter()
-            .filter(|(peer_address, ..)| !ignore_peers.contains(peer_address))
-            .take(close_group_majority())
-            .collect();
+        select_payee_from_store_costs(&record_address, all_costs, &ignore_peers)
+    }
+
+    /// Get the store costs of many records, the same way as `get_store_costs_from_network`.
+    ///
+    /// The addresses whose close groups (as known to our routing table) overlap by at least
+    /// `close_group_majority()` peers get quoted together, with `Query::GetStoreCosts` batches
+    /// of at most `MAX_STORE_COSTS_BATCH_SIZE` addresses, rather than a request per address.
+    /// A batch sent to peers which don't support `Query::GetStoreCosts` yet is quoted one
+    /// address at a time instead.
+    pub async fn get_batched_store_costs_from_network(
+        &self,
+        record_addresses: Vec<NetworkAddress>,
+        ignore_peers: Vec<PeerId>,
+    ) -> Result<Vec<(NetworkAddress, PayeeQuote)>> {
+        let mut batches: Vec<StoreCostsBatch> = vec![];
+        for record_address in record_addresses {
+            let close_group: BTreeSet<PeerId> = self
+                .get_close_group_local_peers(&record_address)
+                .await?
+                .into_iter()
+                .collect();
+            match batches
+                .iter_mut()
+                .find(|batch| batch.can_take(&close_group))
+            {
+                Some(batch) => batch.push(record_address, close_group),
+                None => batches.push(StoreCostsBatch::new(record_address, close_group)),
+            }
+        }
+
+        debug!("Getting store costs in {} batches", batches.len());
+
+        let mut payee_quotes = vec![];
+        for batch_result in join_all(
+            batches
+                .into_iter()
+                .map(|batch| self.get_store_costs_of_batch(batch, &ignore_peers)),
+        )
+        .await
+        {
+            payee_quotes.extend(batch_result?);
+        }
+
+        Ok(payee_quotes)
+    }
+
+    /// Quote a batch of addresses with overlapping close groups with a single
+    /// `Query::GetStoreCosts`, sent to the close group of each of the addresses.
+    /// Falls back on `Query::GetStoreCost` for each of the addresses if any of the peers
+    /// doesn't understand the request.
+    async fn get_store_costs_of_batch(
+        &self,
+        batch: StoreCostsBatch,
+        ignore_peer_ids: &[PeerId],
+    ) -> Result<Vec<(NetworkAddress, PayeeQuote)>> {
+        let StoreCostsBatch {
+            record_addresses,
+            peers,
+            ..
+        } = batch;
+        let Some(first_address) = record_addresses.first().cloned() else {
+            return Ok(vec![]);
+        };
+        let mut close_nodes = self.get_closest_peers(&first_address, true).await?;
+        for peer in peers {
+            if !close_nodes.contains(&peer) {
+                close_nodes.push(peer);
+            }
+        }
+
+        let request = Request::Query(Query::GetStoreCosts(record_addresses.clone()));
+        let responses = self
+            .send_and_get_responses(&close_nodes, &request, true)
+            .await;
+
+        let unsupported = responses
+            .values()
+            .filter(|response| matches!(response, Err(err) if is_unsupported_request_error(err)))
+            .count();
+        if unsupported > 0 {
+            warn!(
+                "{unsupported} peers failed to handle a StoreCostsReq, quoting its {} addresses one by one",
+                record_addresses.len()
+            );
+            let quotes = join_all(record_addresses.into_iter().map(|record_address| async {
+                let quote = self
+                    .get_store_costs_from_network(record_address.clone(), ignore_peer_ids.to_vec())
+                    .await?;
+                Ok((record_address, quote))
+            }))
+            .await;
+            return quotes.into_iter().collect();
+        }
+
+        #[allow(clippy::mutable_key_type)]
+        let ignore_peers = ignore_peer_ids
+            .iter()
+            .map(|peer_id| NetworkAddress::from_peer(*peer_id))
+            .collect::<BTreeSet<_>>();
+        let mut all_costs: BTreeMap<NetworkAddress, Vec<_>> = BTreeMap::new();
+        let mut first_address_quotes = vec![];
+        for response in responses.into_values().flatten() {
+            debug!(
+                "StoreCostsReq for {} addresses received response: {response:?}",
+                record_addresses.len()
+            );
+            let Response::Query(QueryResponse::GetStoreCosts {
+                quotes,
+                payment_address,
+                peer_address,
+            }) = response
+            else {
+                error!("Non store costs response received,  was {:?}", response);
+                continue;
+            };
+
+            for (record_address, quote) in quotes {
+                let quote = match quote {
+                    Ok(quote) => quote,
+                    Err(ProtocolError::RecordExists(_)) => PaymentQuote::zero(),
+                    Err(ProtocolError::NotInCloseGroup(_)) => {
+                        trace!("{peer_address:?} is not close to {record_address:?}");
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!("{peer_address:?} failed to quote {record_address:?}: {err:?}");
+                        continue;
+                    }
+                };
+                if record_address == first_address && quote.cost != NanoTokens::zero() {
+                    first_address_quotes.push((peer_address.clone(), quote.clone()));
+                }
+                all_costs.entry(record_address).or_default().push((
+                    peer_address.clone(),
+                    payment_address,
+                    quote,
+                ));
+            }
+        }
 
-        get_fees_from_store_cost_responses(all_costs)
+        // Verifying the quotes of a single address per batch keeps the bad quote detection
+        // going without sending a verification per address.
+        self.send_quotes_for_verification(&close_nodes, first_address_quotes);
+
+        let mut payee_quotes = Vec::with_capacity(record_addresses.len());
+        for record_address in record_addresses {
+            let costs = all_costs.remove(&record_address).unwrap_or_default();
+            let payee = select_payee_from_store_costs(&record_address, costs, &ignore_peers)?;
+            payee_quotes.push((record_address, payee));
+        }
+        Ok(payee_quotes)
+    }
+
+    /// Share the collected quotes with the quoting peers, for them to detect bad quotes.
+    fn send_quotes_for_verification(
+        &self,
+        close_nodes: &[PeerId],
+        quotes: Vec<(NetworkAddress, PaymentQuote)>,
+    ) {
+        for peer_id in close_nodes.iter() {
+            let request = Request::Cmd(Cmd::QuoteVerification {
+                target: NetworkAddress::from_peer(*peer_id),
+                quotes: quotes.clone(),
+            });
+
+            self.send_req_ignore_reply(request, *peer_id);
+        }
     }
 
     /// Get a record from the network
@@ -442,6 +646,12 @@ impl Network {
     ) -> Result<Record> {
         let pretty_key = PrettyPrintRecordKey::from(&key);
         info!("Getting record from network of {pretty_key:?}. with cfg {cfg:?}",);
+        if let Some(record) = self.get_cached_record(&key, cfg).await? {
+            return Ok(record);
+        }
+        if let Some(record) = self.try_get_record_hedged(&key, cfg).await {
+            return Ok(record);
+        }
         let (sender, receiver) = oneshot::channel();
         self.send_swarm_cmd(SwarmCmd::GetNetworkRecord {
             key: key.clone(),
@@ -467,6 +677,13 @@ impl Network {
         key: RecordKey,
         cfg: &GetRecordCfg,
     ) -> Result<Record> {
+        if let Some(record) = self.get_cached_record(&key, cfg).await? {
+            return Ok(record);
+        }
+        if let Some(record) = self.try_get_record_hedged(&key, cfg).await {
+            return Ok(record);
+        }
+
         let retry_duration = cfg.retry_strategy.map(|strategy| strategy.get_duration());
         backoff::future::retry(
             ExponentialBackoff {
@@ -545,6 +762,19 @@ impl Network {
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
+    /// Get the cost of storing the next record at each of the keys
+    pub async fn get_local_storecosts(
+        &self,
+        keys: Vec<RecordKey>,
+    ) -> Result<Vec<(NanoTokens, QuotingMetrics)>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetLocalStoreCosts { keys, sender });
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
     /// Notify the node receicced a payment.
     pub fn notify_payment_received(&self) {
         self.send_swarm_cmd(SwarmCmd::PaymentReceived);
@@ -563,6 +793,22 @@ impl Network {
             .map_err(|_e| NetworkError::InternalMsgChannelDropped)
     }
 
+    /// Get the metadata of a `Record` held by the local RecordStore
+    pub async fn get_local_record_metadata(
+        &self,
+        key: &RecordKey,
+    ) -> Result<Option<RecordMetadata>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetLocalRecordMetadata {
+            key: key.clone(),
+            sender,
+        });
+
+        receiver
+            .await
+            .map_err(|_e| NetworkError::InternalMsgChannelDropped)
+    }
+
     /// Whether the target peer is considered blacklisted by self
     pub async fn is_peer_shunned(&self, target: NetworkAddress) -> Result<bool> {
         let (sender, receiver) = oneshot::channel();
@@ -860,6 +1106,72 @@ impl Network {
     }
 }
 
+/// Addresses quoted together by `Network::get_batched_store_costs_from_network`.
+struct StoreCostsBatch {
+    record_addresses: Vec<NetworkAddress>,
+    /// The close group of the first address, the others overlapping with it.
+    seed_close_group: BTreeSet<PeerId>,
+    /// The close groups of all the addresses, the batch being sent to each of them.
+    peers: BTreeSet<PeerId>,
+}
+
+impl StoreCostsBatch {
+    fn new(record_address: NetworkAddress, close_group: BTreeSet<PeerId>) -> Self {
+        Self {
+            record_addresses: vec![record_address],
+            seed_close_group: close_group.clone(),
+            peers: close_group,
+        }
+    }
+
+    /// Whether an address with the given close group can join the batch, i.e. the batch is not
+    /// full and the close group shares a majority of its peers with the seed close group.
+    fn can_take(&self, close_group: &BTreeSet<PeerId>) -> bool {
+        self.record_addresses.len() < MAX_STORE_COSTS_BATCH_SIZE
+            && self.seed_close_group.intersection(close_group).count() >= close_group_majority()
+    }
+
+    fn push(&mut self, record_address: NetworkAddress, close_group: BTreeSet<PeerId>) {
+        self.record_addresses.push(record_address);
+        self.peers.extend(close_group);
+    }
+}
+
+/// Whether a request failed because the peer doesn't know it, i.e. it runs an older version
+/// which can't decode the request and drops the stream without a response.
+fn is_unsupported_request_error(err: &NetworkError) -> bool {
+    matches!(err, NetworkError::OutboundError(OutboundFailure::Io(_)))
+}
+
+/// Select the payee for `record_address` among the quotes of the peers closest to it,
+/// skipping the quotes of the `ignore_peers`.
+#[allow(clippy::mutable_key_type, clippy::result_large_err)]
+fn select_payee_from_store_costs(
+    record_address: &NetworkAddress,
+    mut all_costs: Vec<(NetworkAddress, MainPubkey, PaymentQuote)>,
+    ignore_peers: &BTreeSet<NetworkAddress>,
+) -> Result<PayeeQuote> {
+    // Sort all_costs by the NetworkAddress proximity to record_address
+    all_costs.sort_by(|(peer_address_a, _, _), (peer_address_b, _, _)| {
+        record_address
+            .distance(peer_address_a)
+            .cmp(&record_address.distance(peer_address_b))
+    });
+
+    // Ensure we dont have any further out nodes than `close_group_majority()`
+    // This should ensure that if we didnt get all responses from close nodes,
+    // we're less likely to be paying a node that is not in the CLOSE_GROUP
+    //
+    // Also filter out the peers.
+    let all_costs = all_costs
+        .into_iter()
+        .filter(|(peer_address, ..)| !ignore_peers.contains(peer_address))
+        .take(close_group_majority())
+        .collect();
+
+    get_fees_from_store_cost_responses(all_costs)
+}
+
 /// Given `all_costs` it will return the closest / lowest cost
 /// Closest requiring it to be within CLOSE_GROUP nodes
 fn get_fees_from_store_cost_responses(
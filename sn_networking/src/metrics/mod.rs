@@ -9,8 +9,11 @@
 use crate::target_arch::sleep;
 use libp2p::metrics::{Metrics as Libp2pMetrics, Recorder};
 #[cfg(feature = "upnp")]
-use prometheus_client::metrics::{counter::Counter, family::Family};
-use prometheus_client::{metrics::gauge::Gauge, registry::Registry};
+use prometheus_client::metrics::family::Family;
+use prometheus_client::{
+    metrics::{counter::Counter, gauge::Gauge},
+    registry::Registry,
+};
 use sysinfo::{Pid, ProcessRefreshKind, System};
 use tokio::time::Duration;
 
@@ -31,6 +34,8 @@ pub(crate) struct NetworkMetrics {
     pub(crate) records_stored: Gauge,
     pub(crate) estimated_network_size: Gauge,
     pub(crate) store_cost: Gauge,
+    pub(crate) records_scrubbed: Counter,
+    pub(crate) corrupt_records_removed: Counter,
     #[cfg(feature = "upnp")]
     pub(crate) upnp_events: Family<upnp::UpnpEventLabels, Counter>,
 
@@ -64,6 +69,19 @@ impl NetworkMetrics {
             store_cost.clone(),
         );
 
+        let records_scrubbed = Counter::default();
+        sub_registry.register(
+            "records_scrubbed",
+            "The number of stored records re-verified by the integrity scrubber",
+            records_scrubbed.clone(),
+        );
+        let corrupt_records_removed = Counter::default();
+        sub_registry.register(
+            "corrupt_records_removed",
+            "The number of stored records removed after failing the integrity check",
+            corrupt_records_removed.clone(),
+        );
+
         #[cfg(feature = "upnp")]
         let upnp_events = Family::default();
         #[cfg(feature = "upnp")]
@@ -92,6 +110,8 @@ impl NetworkMetrics {
             records_stored,
             estimated_network_size,
             store_cost,
+            records_scrubbed,
+            corrupt_records_removed,
             #[cfg(feature = "upnp")]
             upnp_events,
             process_memory_used_mb,
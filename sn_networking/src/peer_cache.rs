@@ -0,0 +1,334 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{target_arch::spawn, SwarmDriver};
+use libp2p::{
+    swarm::dial_opts::{DialOpts, PeerCondition},
+    Multiaddr, PeerId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The file, under the node root dir, the known peers of the routing table are persisted to.
+pub(crate) const PEER_CACHE_FILENAME: &str = "peer_cache";
+
+/// Interval at which the peers of the routing table get persisted.
+pub(crate) const PEER_CACHE_PERSIST_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Peers not seen in the routing table for longer than this are pruned from the cache,
+/// they most likely left the network or changed their address.
+const PEER_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Upper bound of the number of cached peers, the most recently seen ones are kept.
+/// About the size of a well filled routing table.
+const MAX_CACHED_PEERS: usize = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct CachedPeer {
+    /// The multiaddrs the peer was last known on, as bytes.
+    addrs: Vec<Vec<u8>>,
+    /// When the peer was last seen in the routing table, in seconds since the UNIX epoch.
+    last_seen: u64,
+}
+
+/// The peers of the routing table, persisted across restarts so a restarted node rejoins the
+/// network from its previous neighbourhood instead of bootstrapping from scratch.
+#[derive(Debug, Default)]
+pub(crate) struct PeerCache {
+    path: PathBuf,
+    /// Keyed by the bytes of the `PeerId`.
+    peers: BTreeMap<Vec<u8>, CachedPeer>,
+}
+
+impl PeerCache {
+    /// Load the cache from the `root_dir`, pruning the stale peers.
+    /// A missing or unreadable file results in an empty cache.
+    pub(crate) fn load(root_dir: &Path) -> Self {
+        let path = root_dir.join(PEER_CACHE_FILENAME);
+        let peers = match fs::read(&path) {
+            Ok(bytes) => match rmp_serde::from_slice(&bytes) {
+                Ok(peers) => peers,
+                Err(err) => {
+                    warn!("Failed to deserialize the peer cache at {path:?}, ignoring it: {err:?}");
+                    Default::default()
+                }
+            },
+            Err(_) => Default::default(),
+        };
+
+        let mut cache = Self { path, peers };
+        cache.prune(now_in_secs());
+        info!("Loaded {} peers from the peer cache", cache.peers.len());
+        cache
+    }
+
+    /// The cached peers with their addresses, most recently seen first.
+    pub(crate) fn peers(&self) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        let mut peers: Vec<_> = self
+            .peers
+            .iter()
+            .filter_map(|(peer_id, cached)| {
+                let peer_id = PeerId::from_bytes(peer_id).ok()?;
+                let addrs: Vec<Multiaddr> = cached
+                    .addrs
+                    .iter()
+                    .filter_map(|addr| Multiaddr::try_from(addr.clone()).ok())
+                    .collect();
+                (!addrs.is_empty()).then_some((cached.last_seen, peer_id, addrs))
+            })
+            .collect();
+        peers.sort_by_key(|(last_seen, _, _)| std::cmp::Reverse(*last_seen));
+        peers
+            .into_iter()
+            .map(|(_last_seen, peer_id, addrs)| (peer_id, addrs))
+            .collect()
+    }
+
+    /// Refresh the cache with the peers currently in the routing table, then prune the stale ones.
+    /// Peers that dropped out of the routing table are kept until they turn stale, so a short
+    /// lived disconnection does not wipe the neighbourhood.
+    pub(crate) fn update(
+        &mut self,
+        routing_table: impl IntoIterator<Item = (PeerId, Vec<Multiaddr>)>,
+    ) {
+        self.update_at(routing_table, now_in_secs());
+    }
+
+    fn update_at(
+        &mut self,
+        routing_table: impl IntoIterator<Item = (PeerId, Vec<Multiaddr>)>,
+        now: u64,
+    ) {
+        for (peer_id, addrs) in routing_table {
+            if addrs.is_empty() {
+                continue;
+            }
+            let _ = self.peers.insert(
+                peer_id.to_bytes(),
+                CachedPeer {
+                    addrs: addrs.into_iter().map(|addr| addr.to_vec()).collect(),
+                    last_seen: now,
+                },
+            );
+        }
+        self.prune(now);
+    }
+
+    /// Persist the cache off thread.
+    pub(crate) fn persist(&self) {
+        let bytes = match rmp_serde::to_vec(&self.peers) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!("Failed to serialize the peer cache: {err:?}");
+                return;
+            }
+        };
+        let path = self.path.clone();
+        let num_peers = self.peers.len();
+        spawn(async move {
+            match write_atomically(&path, &bytes) {
+                Ok(()) => trace!("Persisted {num_peers} peers to the peer cache"),
+                Err(err) => warn!("Failed to write the peer cache to {path:?}: {err:?}"),
+            }
+        });
+    }
+
+    fn prune(&mut self, now: u64) {
+        let max_age = PEER_CACHE_MAX_AGE.as_secs();
+        self.peers
+            .retain(|_peer_id, cached| now.saturating_sub(cached.last_seen) <= max_age);
+
+        if self.peers.len() > MAX_CACHED_PEERS {
+            let mut by_last_seen: Vec<(u64, Vec<u8>)> = self
+                .peers
+                .iter()
+                .map(|(peer_id, cached)| (cached.last_seen, peer_id.clone()))
+                .collect();
+            by_last_seen.sort_by_key(|(last_seen, _)| std::cmp::Reverse(*last_seen));
+            for (_last_seen, peer_id) in by_last_seen.into_iter().skip(MAX_CACHED_PEERS) {
+                let _ = self.peers.remove(&peer_id);
+            }
+        }
+    }
+}
+
+impl SwarmDriver {
+    /// Dial the peers loaded from the peer cache, once we are listening. The dialed peers get
+    /// added to the routing table on identify, as with any other dialed peer.
+    /// The initial peers are dialed instead if there are no cached peers to dial.
+    pub(crate) fn dial_cached_peers(&mut self) {
+        let cached_peers = std::mem::take(&mut self.cached_peers_to_dial);
+        for (peer_id, addrs) in cached_peers {
+            if let Some((_issues, true)) = self.bad_nodes.get(&peer_id) {
+                continue;
+            }
+            let opts = DialOpts::peer_id(peer_id)
+                .condition(PeerCondition::NotDialing)
+                .addresses(addrs)
+                .build();
+            match self.dial_with_opts(opts) {
+                Ok(()) => {
+                    let _ = self.pending_cached_dials.insert(peer_id);
+                }
+                Err(err) => debug!("Failed to dial cached peer {peer_id:?}: {err:?}"),
+            }
+        }
+
+        if self.pending_cached_dials.is_empty() {
+            self.dial_initial_peers();
+        } else {
+            info!(
+                "Warm starting from {} cached peers",
+                self.pending_cached_dials.len()
+            );
+        }
+    }
+
+    /// Track the outcome of dialing a cached peer. Once one of them is connected the initial
+    /// peers are no longer needed, whereas they get dialed if all of them fail.
+    pub(crate) fn on_cached_peer_dialed(&mut self, peer_id: &PeerId, connected: bool) {
+        if !self.pending_cached_dials.remove(peer_id) {
+            return;
+        }
+
+        if connected {
+            info!("Warm started from cached peer {peer_id:?}, not dialing the initial peers");
+            self.pending_cached_dials.clear();
+            self.initial_peers_to_dial.clear();
+        } else if self.pending_cached_dials.is_empty() {
+            warn!("None of the cached peers could be dialed, falling back on the initial peers");
+            self.dial_initial_peers();
+        }
+    }
+
+    fn dial_initial_peers(&mut self) {
+        let initial_peers = std::mem::take(&mut self.initial_peers_to_dial);
+        if cfg!(feature = "local-discovery") {
+            return;
+        }
+        for addr in initial_peers {
+            if let Err(err) = self.dial_bootstrap_peer(addr.clone()) {
+                error!("Failed to dial {addr}: {err:?}");
+            }
+        }
+    }
+
+    /// Refresh the peer cache with the peers of the routing table and persist it.
+    pub(crate) fn persist_peer_cache(&mut self) {
+        let Some(peer_cache) = self.peer_cache.as_mut() else {
+            return;
+        };
+
+        let mut routing_table = vec![];
+        for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+            for entry in kbucket.iter() {
+                let addrs: Vec<Multiaddr> = entry.node.value.iter().cloned().collect();
+                routing_table.push((*entry.node.key.preimage(), addrs));
+            }
+        }
+        peer_cache.update(routing_table);
+        peer_cache.persist();
+    }
+}
+
+/// Write to a temporary file then rename it over the cache, so a crash mid write doesn't leave
+/// a truncated cache behind.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, bytes)?;
+    fs::rename(&temp_path, path)
+}
+
+fn now_in_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+
+    fn random_peer(port: u16) -> (PeerId, Vec<Multiaddr>) {
+        let addr = format!("/ip4/10.0.0.1/udp/{port}/quic-v1")
+            .parse()
+            .expect("valid multiaddr");
+        (PeerId::random(), vec![addr])
+    }
+
+    #[test]
+    fn peer_cache_survives_a_restart() -> Result<()> {
+        let root_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&root_dir)?;
+        let peers: BTreeMap<PeerId, Vec<Multiaddr>> =
+            (0..10).map(|i| random_peer(12000 + i)).collect();
+
+        let mut cache = PeerCache::load(&root_dir);
+        assert!(cache.peers().is_empty());
+        cache.update(peers.clone());
+        // Write synchronously, `persist` writes off thread.
+        write_atomically(&cache.path, &rmp_serde::to_vec(&cache.peers)?)?;
+        assert!(!cache.path.with_extension("tmp").exists());
+
+        let reloaded = PeerCache::load(&root_dir);
+        let reloaded_peers: BTreeMap<PeerId, Vec<Multiaddr>> =
+            reloaded.peers().into_iter().collect();
+        assert_eq!(reloaded_peers, peers);
+
+        fs::remove_dir_all(&root_dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn stale_peers_are_pruned() {
+        let mut cache = PeerCache::default();
+        let now = PEER_CACHE_MAX_AGE.as_secs() * 2;
+        let (stale_peer, stale_addrs) = random_peer(12000);
+        let (fresh_peer, fresh_addrs) = random_peer(12001);
+
+        cache.update_at([(stale_peer, stale_addrs)], 0);
+        cache.update_at([(fresh_peer, fresh_addrs)], now);
+
+        let cached: Vec<PeerId> = cache.peers().into_iter().map(|(peer, _)| peer).collect();
+        assert_eq!(cached, vec![fresh_peer]);
+    }
+
+    #[test]
+    fn peers_out_of_the_routing_table_are_kept_until_stale() {
+        let mut cache = PeerCache::default();
+        let (peer, addrs) = random_peer(12000);
+
+        cache.update_at([(peer, addrs)], 0);
+        cache.update_at([], PEER_CACHE_MAX_AGE.as_secs());
+        assert_eq!(cache.peers().len(), 1);
+
+        cache.update_at([], PEER_CACHE_MAX_AGE.as_secs() + 1);
+        assert!(cache.peers().is_empty());
+    }
+
+    #[test]
+    fn most_recently_seen_peers_are_kept_when_full() {
+        let mut cache = PeerCache::default();
+        let old_peers: Vec<_> = (0..MAX_CACHED_PEERS as u16).map(random_peer).collect();
+        let (new_peer, new_addrs) = random_peer(u16::MAX);
+
+        cache.update_at(old_peers, 0);
+        cache.update_at([(new_peer, new_addrs)], 1);
+
+        let cached = cache.peers();
+        assert_eq!(cached.len(), MAX_CACHED_PEERS);
+        assert_eq!(cached[0].0, new_peer);
+    }
+}
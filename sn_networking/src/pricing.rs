@@ -0,0 +1,211 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{record_store::calculate_cost_for_records, CLOSE_GROUP_SIZE};
+use sn_transfers::{QuotingMetrics, TOTAL_SUPPLY};
+use std::{cmp::max, fmt::Debug};
+
+/// Decides the store cost a node quotes for the current state of its record store.
+///
+/// Nodes verify the quotes of their peers against [`calculate_cost_for_records`], hence any
+/// strategy other than [`DefaultPricingStrategy`] is meant for simulations and test networks.
+pub trait PricingStrategy: Debug + Send + Sync {
+    /// The cost, in nanos, to store a new record given the `quoting_metrics`.
+    fn cost(&self, quoting_metrics: &QuotingMetrics) -> u64;
+}
+
+/// The network pricing curve: linear at first, exponential after 60% of `max_records`, and
+/// lowered by the payments received and the time the node has been alive.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultPricingStrategy;
+
+impl PricingStrategy for DefaultPricingStrategy {
+    fn cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        calculate_cost_for_records(quoting_metrics)
+    }
+}
+
+/// A curve growing linearly from `min_cost` for an empty store to `max_cost` for a full one.
+/// Payments received and live time are not taken into account.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearPricingStrategy {
+    min_cost: u64,
+    max_cost: u64,
+}
+
+impl LinearPricingStrategy {
+    /// Create a linear strategy, `max_cost` is capped the same way the default curve is.
+    pub fn new(min_cost: u64, max_cost: u64) -> Self {
+        let max_cost = max_cost.min(TOTAL_SUPPLY / CLOSE_GROUP_SIZE as u64);
+        Self {
+            min_cost: min_cost.min(max_cost),
+            max_cost,
+        }
+    }
+}
+
+impl PricingStrategy for LinearPricingStrategy {
+    fn cost(&self, quoting_metrics: &QuotingMetrics) -> u64 {
+        let max_records = max(1, quoting_metrics.max_records) as u128;
+        let records_stored = (records_stored_for_pricing(quoting_metrics) as u128).min(max_records);
+        let range = (self.max_cost - self.min_cost) as u128;
+
+        self.min_cost + (range * records_stored / max_records) as u64
+    }
+}
+
+/// The number of records a store is priced at. When a `max_storage_bytes` quota is set, the
+/// bytes fill counts as the equivalent share of `max_records` if that is the fuller of the two.
+pub(crate) fn records_stored_for_pricing(quoting_metrics: &QuotingMetrics) -> usize {
+    match quoting_metrics.max_storage_bytes {
+        Some(max_storage_bytes) if max_storage_bytes > 0 => {
+            let bytes_fill_as_records = quoting_metrics.stored_bytes as u128
+                * quoting_metrics.max_records as u128
+                / max_storage_bytes as u128;
+            max(
+                quoting_metrics.close_records_stored,
+                bytes_fill_as_records as usize,
+            )
+        }
+        _ => quoting_metrics.close_records_stored,
+    }
+}
+
+/// A named sequence of `QuotingMetrics`, replayed against a `PricingStrategy` by
+/// [`replay_pricing_scenario`].
+#[derive(Debug, Clone)]
+pub struct PricingScenario {
+    pub name: String,
+    pub quoting_metrics: Vec<QuotingMetrics>,
+}
+
+impl PricingScenario {
+    /// A store filling up from empty to `max_records` in `steps` even steps, getting paid once
+    /// for every `records_per_payment` records it holds.
+    pub fn filling_store(max_records: usize, steps: usize, records_per_payment: usize) -> Self {
+        let steps = max(1, steps);
+        let quoting_metrics = (0..=steps)
+            .map(|step| {
+                let close_records_stored = max_records * step / steps;
+                QuotingMetrics {
+                    close_records_stored,
+                    max_records,
+                    received_payment_count: close_records_stored / max(1, records_per_payment),
+                    live_time: 0,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Self {
+            name: format!("filling_store({max_records}, 1 payment per {records_per_payment})"),
+            quoting_metrics,
+        }
+    }
+
+    /// The same store state quoted by a node that has been alive for `0..=days` days.
+    pub fn ageing_node(close_records_stored: usize, max_records: usize, days: u64) -> Self {
+        let quoting_metrics = (0..=days)
+            .map(|day| QuotingMetrics {
+                close_records_stored,
+                max_records,
+                received_payment_count: 0,
+                live_time: day * 24 * 3600,
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            name: format!("ageing_node({close_records_stored}/{max_records}, {days} days)"),
+            quoting_metrics,
+        }
+    }
+}
+
+/// The costs quoted by a `PricingStrategy` over a `PricingScenario`.
+#[derive(Debug, Clone)]
+pub struct PricingSimulationReport {
+    pub scenario: String,
+    pub strategy: String,
+    pub costs: Vec<u64>,
+}
+
+impl PricingSimulationReport {
+    pub fn min_cost(&self) -> u64 {
+        self.costs.iter().copied().min().unwrap_or_default()
+    }
+
+    pub fn max_cost(&self) -> u64 {
+        self.costs.iter().copied().max().unwrap_or_default()
+    }
+
+    pub fn total_cost(&self) -> u128 {
+        self.costs.iter().map(|cost| *cost as u128).sum()
+    }
+}
+
+/// Replay each of the scenario's `QuotingMetrics` against the strategy.
+pub fn replay_pricing_scenario(
+    strategy: &dyn PricingStrategy,
+    scenario: &PricingScenario,
+) -> PricingSimulationReport {
+    let costs = scenario
+        .quoting_metrics
+        .iter()
+        .map(|quoting_metrics| strategy.cost(quoting_metrics))
+        .collect();
+
+    PricingSimulationReport {
+        scenario: scenario.name.clone(),
+        strategy: format!("{strategy:?}"),
+        costs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record_store::MAX_RECORDS_COUNT;
+
+    #[test]
+    fn default_strategy_replays_the_network_curve() {
+        let scenario = PricingScenario::filling_store(MAX_RECORDS_COUNT, 100, 5);
+        let report = replay_pricing_scenario(&DefaultPricingStrategy, &scenario);
+
+        assert_eq!(report.costs.len(), scenario.quoting_metrics.len());
+        for (quoting_metrics, cost) in scenario.quoting_metrics.iter().zip(report.costs.iter()) {
+            assert_eq!(*cost, calculate_cost_for_records(quoting_metrics));
+        }
+        assert_eq!(report.min_cost(), report.costs[0]);
+        assert_eq!(report.max_cost(), *report.costs.last().expect("no costs"));
+    }
+
+    #[test]
+    fn linear_strategy_is_monotonic_and_bounded() {
+        let strategy = LinearPricingStrategy::new(10, 10_000);
+        let scenario = PricingScenario::filling_store(MAX_RECORDS_COUNT, 100, 1);
+        let report = replay_pricing_scenario(&strategy, &scenario);
+
+        assert!(report.costs.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(report.min_cost(), 10);
+        assert_eq!(report.max_cost(), 10_000);
+    }
+
+    #[test]
+    fn default_strategy_rewards_long_lived_nodes() {
+        // The live time reward only shows above the minimum cost, hence a nearly full store.
+        let scenario =
+            PricingScenario::ageing_node(MAX_RECORDS_COUNT * 9 / 10, MAX_RECORDS_COUNT, 30);
+        let default_report = replay_pricing_scenario(&DefaultPricingStrategy, &scenario);
+        let linear_report =
+            replay_pricing_scenario(&LinearPricingStrategy::new(10, 10_000), &scenario);
+
+        assert!(default_report.costs[0] > *default_report.costs.last().expect("no costs"));
+        assert_eq!(linear_report.min_cost(), linear_report.max_cost());
+    }
+}
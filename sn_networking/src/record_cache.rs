@@ -0,0 +1,562 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    record_scrubber::is_record_intact,
+    target_arch::{spawn, Instant},
+};
+use libp2p::kad::{Record, RecordKey};
+use sn_protocol::{
+    storage::{RecordHeader, RecordKind},
+    PrettyPrintRecordKey,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Default number of bytes of records a client caches in memory.
+pub const DEFAULT_RECORD_CACHE_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Default number of bytes of chunks a client caches on disk.
+pub const DEFAULT_RECORD_CACHE_DISK_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Default duration registers and spends are served from the cache for.
+pub const DEFAULT_MUTABLE_RECORD_TTL: Duration = Duration::from_secs(10);
+
+/// The settings of the client side record cache.
+///
+/// Chunks are content addressed, hence always safe to serve from the cache, and are kept until
+/// evicted. Registers and spends can change on the network, and are only served for a short TTL.
+#[derive(Clone, Debug)]
+pub struct RecordCacheConfig {
+    /// The number of bytes of records held in memory.
+    pub max_memory_bytes: usize,
+    /// The directory chunks are also cached in, to outlive the client. None to cache in memory only.
+    pub disk_dir: Option<PathBuf>,
+    /// The number of bytes of chunks held on disk.
+    pub max_disk_bytes: u64,
+    /// How long registers and spends are served from the cache for. Zero disables caching them.
+    pub mutable_record_ttl: Duration,
+}
+
+impl Default for RecordCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_memory_bytes: DEFAULT_RECORD_CACHE_MEMORY_BYTES,
+            disk_dir: None,
+            max_disk_bytes: DEFAULT_RECORD_CACHE_DISK_BYTES,
+            mutable_record_ttl: DEFAULT_MUTABLE_RECORD_TTL,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MemoryEntry {
+    record: Record,
+    /// Set for registers and spends, which expire.
+    expires_at: Option<Instant>,
+    /// The last access, to evict the least recently used entries first.
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct DiskEntry {
+    size: u64,
+    tick: u64,
+}
+
+/// A record found in the cache. Chunks cached on disk are read, and checked against their
+/// address, by the caller with `read_cached_chunk`, so the disk IO and hashing don't hold up
+/// the swarm thread the cache lives on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CachedRecord {
+    /// A record held in memory.
+    InMemory(Record),
+    /// A chunk held on disk, at the given path.
+    OnDisk(PathBuf),
+}
+
+/// Read a chunk cached on disk, None if it can't be read or doesn't match its address.
+/// The outcome is then to be reported with `RecordCache::on_disk_read`.
+pub(crate) fn read_cached_chunk(key: &RecordKey, path: &Path) -> Option<Record> {
+    fs::read(path)
+        .ok()
+        .map(|value| Record::new(key.clone(), value))
+        .filter(|record| is_record_intact(key, record))
+}
+
+/// A bounded least recently used cache of the records fetched by a client,
+/// held in memory and, for chunks, optionally on disk.
+#[derive(Debug)]
+pub(crate) struct RecordCache {
+    config: RecordCacheConfig,
+    memory: HashMap<RecordKey, MemoryEntry>,
+    memory_lru: BTreeMap<u64, RecordKey>,
+    memory_bytes: usize,
+    disk: HashMap<RecordKey, DiskEntry>,
+    disk_lru: BTreeMap<u64, RecordKey>,
+    disk_bytes: u64,
+    /// The chunks being written to disk off thread, flagged once removed from the cache while
+    /// being written, for the writing task to remove the file it wrote.
+    pending_writes: Arc<Mutex<HashMap<RecordKey, bool>>>,
+    tick: u64,
+}
+
+impl RecordCache {
+    /// Create the cache, picking up the chunks cached on disk by a previous client.
+    pub(crate) fn new(config: RecordCacheConfig) -> Self {
+        let mut cache = Self {
+            config,
+            memory: Default::default(),
+            memory_lru: Default::default(),
+            memory_bytes: 0,
+            disk: Default::default(),
+            disk_lru: Default::default(),
+            disk_bytes: 0,
+            pending_writes: Default::default(),
+            tick: 0,
+        };
+        cache.load_disk_index();
+        cache
+    }
+
+    /// The cached record, if any and still fresh.
+    pub(crate) fn get(&mut self, key: &RecordKey) -> Option<CachedRecord> {
+        let tick = self.next_tick();
+        if let Some(entry) = self.memory.get_mut(key) {
+            if entry
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= Instant::now())
+            {
+                self.remove_from_memory(key);
+                return None;
+            }
+            let _ = self.memory_lru.remove(&entry.tick);
+            entry.tick = tick;
+            let _ = self.memory_lru.insert(tick, key.clone());
+            return Some(CachedRecord::InMemory(entry.record.clone()));
+        }
+
+        let path = self.disk_path(key)?;
+        let entry = self.disk.get_mut(key)?;
+        let _ = self.disk_lru.remove(&entry.tick);
+        entry.tick = tick;
+        let _ = self.disk_lru.insert(tick, key.clone());
+        Some(CachedRecord::OnDisk(path))
+    }
+
+    /// Take in the outcome of reading a chunk cached on disk. A chunk read fine is then also
+    /// held in memory, whereas one which can't be read is dropped.
+    pub(crate) fn on_disk_read(&mut self, key: &RecordKey, record: Option<Record>) {
+        match record {
+            Some(record) => self.insert_in_memory(record, None),
+            None => {
+                warn!(
+                    "Dropping the corrupt cached chunk {:?}",
+                    PrettyPrintRecordKey::from(key)
+                );
+                self.remove_from_disk(key);
+            }
+        }
+    }
+
+    /// Cache a record fetched from the network. Only chunks, registers and spends get cached,
+    /// chunks only once checked against their address.
+    pub(crate) fn insert(&mut self, record: Record) {
+        let kind = match RecordHeader::from_record(&record) {
+            Ok(header) => header.kind,
+            Err(_) => return,
+        };
+        match kind {
+            RecordKind::Chunk => {
+                if !is_record_intact(&record.key, &record) {
+                    warn!(
+                        "Not caching the corrupt chunk {:?}",
+                        PrettyPrintRecordKey::from(&record.key)
+                    );
+                    return;
+                }
+                self.write_to_disk(&record);
+                self.insert_in_memory(record, None);
+            }
+            RecordKind::Register | RecordKind::Spend => {
+                if !self.config.mutable_record_ttl.is_zero() {
+                    let expires_at = Instant::now() + self.config.mutable_record_ttl;
+                    self.insert_in_memory(record, Some(expires_at));
+                }
+            }
+            RecordKind::ChunkWithPayment
+            | RecordKind::RegisterWithPayment
+            | RecordKind::RegisterOps => {}
+        }
+    }
+
+    /// Drop the cached copy of a record, e.g. as the client is putting a newer version of it.
+    pub(crate) fn remove(&mut self, key: &RecordKey) {
+        self.remove_from_memory(key);
+        if self.disk.contains_key(key) {
+            self.remove_from_disk(key);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn insert_in_memory(&mut self, record: Record, expires_at: Option<Instant>) {
+        let size = record.value.len();
+        if size > self.config.max_memory_bytes {
+            return;
+        }
+        let key = record.key.clone();
+        self.remove_from_memory(&key);
+
+        while self.memory_bytes + size > self.config.max_memory_bytes {
+            let Some((_tick, oldest)) = self.memory_lru.pop_first() else {
+                break;
+            };
+            self.remove_from_memory(&oldest);
+        }
+
+        let tick = self.next_tick();
+        let _ = self.memory_lru.insert(tick, key.clone());
+        self.memory_bytes += size;
+        let _ = self.memory.insert(
+            key,
+            MemoryEntry {
+                record,
+                expires_at,
+                tick,
+            },
+        );
+    }
+
+    fn remove_from_memory(&mut self, key: &RecordKey) {
+        if let Some(entry) = self.memory.remove(key) {
+            let _ = self.memory_lru.remove(&entry.tick);
+            self.memory_bytes -= entry.record.value.len();
+        }
+    }
+
+    fn disk_path(&self, key: &RecordKey) -> Option<PathBuf> {
+        self.config
+            .disk_dir
+            .as_ref()
+            .map(|dir| dir.join(hex::encode(key.as_ref())))
+    }
+
+    /// Index the chunks cached on disk, the most recently written being the most recently used.
+    fn load_disk_index(&mut self) {
+        let Some(dir) = self.config.disk_dir.clone() else {
+            return;
+        };
+        if let Err(err) = fs::create_dir_all(&dir) {
+            warn!("Failed to create the record cache dir {dir:?}, caching in memory only: {err:?}");
+            self.config.disk_dir = None;
+            return;
+        }
+
+        let mut cached_files = vec![];
+        if let Ok(dir_entries) = fs::read_dir(&dir) {
+            for dir_entry in dir_entries.flatten() {
+                let Ok(metadata) = dir_entry.metadata() else {
+                    continue;
+                };
+                let Some(key) = dir_entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| hex::decode(name).ok())
+                    .map(RecordKey::from)
+                else {
+                    continue;
+                };
+                cached_files.push((metadata.modified().ok(), key, metadata.len()));
+            }
+        }
+        cached_files.sort_by_key(|(modified, _, _)| *modified);
+
+        for (_modified, key, size) in cached_files {
+            let tick = self.next_tick();
+            let _ = self.disk_lru.insert(tick, key.clone());
+            self.disk_bytes += size;
+            let _ = self.disk.insert(key, DiskEntry { size, tick });
+        }
+        self.evict_from_disk(0);
+        info!(
+            "Record cache picked up {} chunks ({} bytes) from {dir:?}",
+            self.disk.len(),
+            self.disk_bytes
+        );
+    }
+
+    fn write_to_disk(&mut self, record: &Record) {
+        let Some(path) = self.disk_path(&record.key) else {
+            return;
+        };
+        let size = record.value.len() as u64;
+        if size > self.config.max_disk_bytes || self.disk.contains_key(&record.key) {
+            return;
+        }
+
+        self.evict_from_disk(size);
+        let tick = self.next_tick();
+        let _ = self.disk_lru.insert(tick, record.key.clone());
+        self.disk_bytes += size;
+        let _ = self
+            .disk
+            .insert(record.key.clone(), DiskEntry { size, tick });
+
+        if let Ok(mut pending_writes) = self.pending_writes.lock() {
+            let _ = pending_writes.insert(record.key.clone(), false);
+        }
+        let pending_writes = Arc::clone(&self.pending_writes);
+        let key = record.key.clone();
+        let value = record.value.clone();
+        spawn(async move {
+            if let Err(err) = fs::write(&path, value) {
+                warn!("Failed to write the cached chunk to {path:?}: {err:?}");
+            }
+            let removed_meanwhile = pending_writes
+                .lock()
+                .map(|mut pending_writes| pending_writes.remove(&key) == Some(true))
+                .unwrap_or(false);
+            if removed_meanwhile {
+                let _ = fs::remove_file(&path);
+            }
+        });
+    }
+
+    /// Evict the least recently used chunks until `incoming` more bytes fit on disk.
+    fn evict_from_disk(&mut self, incoming: u64) {
+        while self.disk_bytes + incoming > self.config.max_disk_bytes {
+            let Some((_tick, oldest)) = self.disk_lru.first_key_value() else {
+                break;
+            };
+            let oldest = oldest.clone();
+            self.remove_from_disk(&oldest);
+        }
+    }
+
+    fn remove_from_disk(&mut self, key: &RecordKey) {
+        if let Some(entry) = self.disk.remove(key) {
+            let _ = self.disk_lru.remove(&entry.tick);
+            self.disk_bytes -= entry.size;
+        }
+        // A chunk still being written gets removed by the writing task once written.
+        if let Ok(mut pending_writes) = self.pending_writes.lock() {
+            if let Some(removed) = pending_writes.get_mut(key) {
+                *removed = true;
+            }
+        }
+        if let Some(path) = self.disk_path(key) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use eyre::Result;
+    use sn_protocol::{
+        storage::{try_serialize_record, Chunk, ChunkAddress},
+        NetworkAddress,
+    };
+    use xor_name::XorName;
+
+    fn chunk_record(len: usize) -> Result<Record> {
+        let chunk = Chunk::new(Bytes::from(
+            (0..len).map(|_| rand::random::<u8>()).collect::<Vec<u8>>(),
+        ));
+        Ok(Record::new(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+        ))
+    }
+
+    fn mutable_record(kind: RecordKind) -> Result<Record> {
+        let address = ChunkAddress::new(XorName::random(&mut rand::thread_rng()));
+        Ok(Record::new(
+            NetworkAddress::from_chunk_address(address).to_record_key(),
+            try_serialize_record(&vec![1u8, 2, 3], kind)?.to_vec(),
+        ))
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Get a record the way the network does, reading the chunks cached on disk.
+    fn get_record(cache: &mut RecordCache, key: &RecordKey) -> Option<Record> {
+        match cache.get(key)? {
+            CachedRecord::InMemory(record) => Some(record),
+            CachedRecord::OnDisk(path) => {
+                let record = read_cached_chunk(key, &path);
+                cache.on_disk_read(key, record.clone());
+                record
+            }
+        }
+    }
+
+    #[test]
+    fn cached_chunks_are_served_from_memory() -> Result<()> {
+        let mut cache = RecordCache::new(RecordCacheConfig::default());
+        let record = chunk_record(1024)?;
+
+        assert!(cache.get(&record.key).is_none());
+        cache.insert(record.clone());
+        assert_eq!(cache.get(&record.key), Some(CachedRecord::InMemory(record)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn least_recently_used_records_are_evicted() -> Result<()> {
+        let records = (0..3)
+            .map(|_| chunk_record(1024))
+            .collect::<Result<Vec<_>>>()?;
+        let mut cache = RecordCache::new(RecordCacheConfig {
+            // Room for two of the records only.
+            max_memory_bytes: records[0].value.len() * 2 + 1,
+            ..Default::default()
+        });
+
+        cache.insert(records[0].clone());
+        cache.insert(records[1].clone());
+        // Touch the first record, so the second one is the least recently used.
+        assert!(cache.get(&records[0].key).is_some());
+        cache.insert(records[2].clone());
+
+        assert!(cache.get(&records[0].key).is_some());
+        assert!(cache.get(&records[1].key).is_none());
+        assert!(cache.get(&records[2].key).is_some());
+        assert!(cache.memory_bytes <= cache.config.max_memory_bytes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunks_not_matching_their_address_are_not_cached() -> Result<()> {
+        let mut cache = RecordCache::new(RecordCacheConfig::default());
+        let record = chunk_record(1024)?;
+        let other = chunk_record(1024)?;
+
+        cache.insert(Record::new(record.key.clone(), other.value.clone()));
+        assert!(cache.get(&record.key).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn removed_records_are_no_longer_served() -> Result<()> {
+        let mut cache = RecordCache::new(RecordCacheConfig::default());
+        let record = mutable_record(RecordKind::Register)?;
+
+        cache.insert(record.clone());
+        assert!(cache.get(&record.key).is_some());
+        cache.remove(&record.key);
+        assert!(cache.get(&record.key).is_none());
+        assert_eq!(cache.memory_bytes, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mutable_records_expire() -> Result<()> {
+        let mut cache = RecordCache::new(RecordCacheConfig {
+            mutable_record_ttl: Duration::from_millis(50),
+            ..Default::default()
+        });
+        let record = mutable_record(RecordKind::Register)?;
+
+        cache.insert(record.clone());
+        assert_eq!(
+            cache.get(&record.key),
+            Some(CachedRecord::InMemory(record.clone()))
+        );
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(cache.get(&record.key).is_none());
+
+        let mut no_ttl_cache = RecordCache::new(RecordCacheConfig {
+            mutable_record_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        no_ttl_cache.insert(record.clone());
+        assert!(no_ttl_cache.get(&record.key).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cached_chunks_outlive_the_client_on_disk() -> Result<()> {
+        let disk_dir = temp_dir();
+        let config = RecordCacheConfig {
+            disk_dir: Some(disk_dir.clone()),
+            ..Default::default()
+        };
+        let record = chunk_record(1024)?;
+
+        let mut cache = RecordCache::new(config.clone());
+        cache.insert(record.clone());
+        // Let the off thread write complete.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut restarted_cache = RecordCache::new(config);
+        assert_eq!(
+            get_record(&mut restarted_cache, &record.key),
+            Some(record.clone())
+        );
+        // Then held in memory as well.
+        assert_eq!(
+            restarted_cache.get(&record.key),
+            Some(CachedRecord::InMemory(record.clone()))
+        );
+
+        // A corrupt copy on disk is dropped rather than served.
+        fs::write(disk_dir.join(hex::encode(record.key.as_ref())), b"corrupt")?;
+        let mut corrupt_cache = RecordCache::new(RecordCacheConfig {
+            disk_dir: Some(disk_dir.clone()),
+            ..Default::default()
+        });
+        assert!(get_record(&mut corrupt_cache, &record.key).is_none());
+        assert!(corrupt_cache.disk.is_empty());
+
+        fs::remove_dir_all(disk_dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunks_removed_while_being_written_leave_no_file_behind() -> Result<()> {
+        let disk_dir = temp_dir();
+        let mut cache = RecordCache::new(RecordCacheConfig {
+            disk_dir: Some(disk_dir.clone()),
+            ..Default::default()
+        });
+        let record = chunk_record(1024)?;
+
+        // Removed before the spawned write gets to run.
+        cache.insert(record.clone());
+        cache.remove(&record.key);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert!(!disk_dir.join(hex::encode(record.key.as_ref())).exists());
+        assert!(cache
+            .pending_writes
+            .lock()
+            .is_ok_and(|pending| pending.is_empty()));
+
+        fs::remove_dir_all(disk_dir)?;
+        Ok(())
+    }
+}
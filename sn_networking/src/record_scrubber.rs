@@ -0,0 +1,183 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::kad::{Record, RecordKey};
+use sn_protocol::{
+    storage::{try_deserialize_record, Chunk, RecordHeader, RecordKind},
+    NetworkAddress, PrettyPrintRecordKey,
+};
+use sn_registers::SignedRegister;
+use sn_transfers::SignedSpend;
+use tokio::time::Duration;
+
+/// Interval at which a batch of locally stored records gets re-verified.
+pub(crate) const RECORD_SCRUB_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default number of records re-verified every `RECORD_SCRUB_INTERVAL`.
+/// With a full store of 4096 records, every record gets checked about once every 40 minutes.
+pub const DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL: usize = 100;
+
+/// Re-verify a record read back from disk against the key it is stored under:
+///   - chunks must hash to the xorname of their `ChunkAddress`
+///   - spends must be signed and addressed to the key
+///   - registers must carry a valid owner signature and be addressed to the key
+///
+/// Returns false if the record is corrupt and shall not be served any longer.
+pub(crate) fn is_record_intact(key: &RecordKey, record: &Record) -> bool {
+    let pretty_key = PrettyPrintRecordKey::from(key);
+    let header = match RecordHeader::from_record(record) {
+        Ok(header) => header,
+        Err(err) => {
+            warn!("Scrubbed record {pretty_key:?} has a corrupt header: {err:?}");
+            return false;
+        }
+    };
+
+    match header.kind {
+        RecordKind::Chunk => match try_deserialize_record::<Chunk>(record) {
+            Ok(chunk) => {
+                let is_intact = chunk.network_address().to_record_key() == *key;
+                if !is_intact {
+                    warn!("Scrubbed chunk {pretty_key:?} does not match its address");
+                }
+                is_intact
+            }
+            Err(err) => {
+                warn!("Scrubbed chunk {pretty_key:?} failed to deserialize: {err:?}");
+                false
+            }
+        },
+        RecordKind::Spend => match try_deserialize_record::<Vec<SignedSpend>>(record) {
+            Ok(spends) if !spends.is_empty() => spends.iter().all(|spend| {
+                let address = NetworkAddress::from_spend_address(spend.address());
+                if address.to_record_key() != *key {
+                    warn!("Scrubbed spend {pretty_key:?} holds a spend for another address");
+                    return false;
+                }
+                if let Err(err) = spend.verify(spend.spent_tx_hash()) {
+                    warn!("Scrubbed spend {pretty_key:?} failed verification: {err:?}");
+                    return false;
+                }
+                true
+            }),
+            Ok(_) => {
+                warn!("Scrubbed spend {pretty_key:?} holds no spends");
+                false
+            }
+            Err(err) => {
+                warn!("Scrubbed spend {pretty_key:?} failed to deserialize: {err:?}");
+                false
+            }
+        },
+        RecordKind::Register => match try_deserialize_record::<SignedRegister>(record) {
+            Ok(register) => {
+                let address = NetworkAddress::from_register_address(*register.address());
+                if address.to_record_key() != *key {
+                    warn!("Scrubbed register {pretty_key:?} does not match its address");
+                    return false;
+                }
+                if let Err(err) = register.verify() {
+                    warn!("Scrubbed register {pretty_key:?} failed verification: {err:?}");
+                    return false;
+                }
+                true
+            }
+            Err(err) => {
+                warn!("Scrubbed register {pretty_key:?} failed to deserialize: {err:?}");
+                false
+            }
+        },
+        RecordKind::ChunkWithPayment
+        | RecordKind::RegisterWithPayment
+        | RecordKind::RegisterOps => {
+            // Payments are stripped before storing and ops are merged into the Register they
+            // are for, hence these never reach the disk.
+            warn!(
+                "Scrubbed record {pretty_key:?} is of kind {:?}, which shall not be stored",
+                header.kind
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use sn_protocol::storage::try_serialize_record;
+    use sn_registers::{Permissions, Register};
+    use xor_name::XorName;
+
+    fn record_of(key: RecordKey, value: Bytes) -> Record {
+        Record {
+            key,
+            value: value.to_vec(),
+            publisher: None,
+            expires: None,
+        }
+    }
+
+    #[test]
+    fn chunk_with_flipped_bit_is_detected() -> eyre::Result<()> {
+        let chunk = Chunk::new(Bytes::from(vec![7u8; 1024]));
+        let key = chunk.network_address().to_record_key();
+        let mut record = record_of(
+            key.clone(),
+            try_serialize_record(&chunk, RecordKind::Chunk)?,
+        );
+        assert!(is_record_intact(&key, &record));
+
+        let last = record.value.len() - 1;
+        record.value[last] ^= 0x01;
+        assert!(!is_record_intact(&key, &record));
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_under_another_key_or_forged_is_detected() -> eyre::Result<()> {
+        let owner = bls::SecretKey::random();
+        let register = Register::new(
+            owner.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::default(),
+        );
+        let signed_register = register.clone().into_signed(&owner)?;
+        let key = NetworkAddress::from_register_address(*signed_register.address()).to_record_key();
+        let value = try_serialize_record(&signed_register, RecordKind::Register)?;
+        assert!(is_record_intact(
+            &key,
+            &record_of(key.clone(), value.clone())
+        ));
+
+        let other_key = NetworkAddress::from_register_address(
+            *Register::new(
+                owner.public_key(),
+                XorName::random(&mut rand::thread_rng()),
+                Permissions::default(),
+            )
+            .address(),
+        )
+        .to_record_key();
+        assert!(!is_record_intact(
+            &other_key,
+            &record_of(other_key.clone(), value)
+        ));
+
+        let forger = bls::SecretKey::random();
+        let forged = SignedRegister::new(register, forger.sign(b"not the register"));
+        let forged_value = try_serialize_record(&forged, RecordKind::Register)?;
+        assert!(!is_record_intact(
+            &key,
+            &record_of(key.clone(), forged_value)
+        ));
+
+        Ok(())
+    }
+}
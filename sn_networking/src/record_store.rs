@@ -7,6 +7,12 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
+use crate::pricing::{records_stored_for_pricing, DefaultPricingStrategy, PricingStrategy};
+use crate::record_cache::{CachedRecord, RecordCache, RecordCacheConfig};
+use crate::record_scrubber::is_record_intact;
+use crate::record_store_backend::{
+    generate_filename, RecordStorageBackend, RecordStorageBackendKind,
+};
 use crate::target_arch::{spawn, Instant};
 use crate::CLOSE_GROUP_SIZE;
 use crate::{cmd::SwarmCmd, event::NetworkEvent, log_markers::Marker, send_swarm_cmd};
@@ -15,7 +21,6 @@ use aes_gcm_siv::{
     Aes256GcmSiv, Nonce,
 };
 
-use itertools::Itertools;
 use libp2p::{
     identity::PeerId,
     kad::{
@@ -29,20 +34,21 @@ use rand::RngCore;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use sn_protocol::{
+    messages::RecordMetadata,
     storage::{RecordHeader, RecordKind, RecordType},
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::{NanoTokens, QuotingMetrics, TOTAL_SUPPLY};
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
     vec,
 };
 use tokio::sync::mpsc;
-use walkdir::{DirEntry, WalkDir};
 use xor_name::XorName;
 
 // A spend record is at the size of 4KB roughly.
@@ -51,11 +57,24 @@ use xor_name::XorName;
 // which makes the average record size is around 256k.
 // Given we are targeting small nodes use 1GB diskspace,
 // this shall allow around 4K records.
-const MAX_RECORDS_COUNT: usize = 4096;
+pub(crate) const MAX_RECORDS_COUNT: usize = 4096;
 
 /// File name of the recorded historical quoting metrics.
 const HISTORICAL_QUOTING_METRICS_FILENAME: &str = "historic_quoting_metrics";
 
+/// File name of the sealed record encryption key.
+const ENCRYPTION_KEY_FILENAME: &str = "record_store_encryption_key";
+
+/// Length of the nonce used when sealing the record encryption key.
+const SEALING_NONCE_LEN: usize = 12;
+
+/// The bytes encrypting a record adds to its value, i.e. the AES-GCM-SIV tag.
+const ENCRYPTION_OVERHEAD_BYTES: usize = if cfg!(feature = "encrypt-records") {
+    16
+} else {
+    0
+};
+
 /// A `RecordStore` that stores records on disk.
 pub struct NodeRecordStore {
     /// The identity of the peer owning the store.
@@ -64,8 +83,14 @@ pub struct NodeRecordStore {
     local_address: NetworkAddress,
     /// The configuration of the store.
     config: NodeRecordStoreConfig,
+    /// The backend holding the bytes of the records on disk.
+    storage_backend: Arc<dyn RecordStorageBackend>,
     /// A set of keys, each corresponding to a data `Record` stored on disk.
     records: HashMap<Key, (NetworkAddress, RecordType)>,
+    /// The bytes each record written (or being written) to disk takes there.
+    record_sizes: HashMap<Key, usize>,
+    /// The total bytes the records held on disk take there, as per `record_sizes`.
+    stored_bytes: usize,
     /// Send network events to the node layer.
     network_event_sender: mpsc::Sender<NetworkEvent>,
     /// Send cmds to the network layer. Used to interact with self in an async fashion.
@@ -79,13 +104,15 @@ pub struct NodeRecordStore {
     record_count_metric: Option<Gauge>,
     /// Counting how many times got paid
     received_payment_count: usize,
-    /// Encyption cipher for the records, randomly generated at node startup
-    /// Plus a 4 byte nonce starter
+    /// Encyption cipher for the records, randomly generated on the first node startup
+    /// and persisted sealed by the `encryption_seed`. Plus a 4 byte nonce starter
     encryption_details: (Aes256GcmSiv, [u8; 4]),
     /// Time that this record_store got started
     timestamp: SystemTime,
     /// Farthest record to self
     farthest_record: Option<(Key, Distance)>,
+    /// Records yet to be re-verified in the current scrubbing round
+    scrub_queue: VecDeque<Key>,
 }
 
 /// Configuration for a `DiskBackedRecordStore`.
@@ -93,23 +120,40 @@ pub struct NodeRecordStore {
 pub struct NodeRecordStoreConfig {
     /// The directory where the records are stored.
     pub storage_dir: PathBuf,
-    /// The directory where the historic quote to be stored
+    /// The directory where the historic quote and the sealed encryption key to be stored
     /// (normally to be the parent dir of the storage_dir)
     pub historic_quote_dir: PathBuf,
     /// The maximum number of records.
     pub max_records: usize,
     /// The maximum size of record values, in bytes.
     pub max_value_bytes: usize,
+    /// The maximum number of bytes the stored records may occupy on disk, including the bytes
+    /// of overwritten and removed records the storage backend has yet to reclaim.
+    /// None means only `max_records` limits the store.
+    pub max_storage_bytes: Option<u64>,
+    /// The on-disk layout used to hold the records.
+    pub storage_backend: RecordStorageBackendKind,
+    /// The seed used to seal the persisted record encryption key.
+    /// Nodes derive this from their keypair, so that stored records survive a restart.
+    pub encryption_seed: [u8; 32],
+    /// The strategy deciding the store cost quoted for the state of the store.
+    pub pricing_strategy: Arc<dyn PricingStrategy>,
 }
 
 impl Default for NodeRecordStoreConfig {
     fn default() -> Self {
         let historic_quote_dir = std::env::temp_dir();
+        let mut encryption_seed = [0u8; 32];
+        OsRng.fill_bytes(&mut encryption_seed);
         Self {
             storage_dir: historic_quote_dir.clone(),
             historic_quote_dir,
             max_records: MAX_RECORDS_COUNT,
             max_value_bytes: 65 * 1024,
+            max_storage_bytes: None,
+            storage_backend: Default::default(),
+            encryption_seed,
+            pricing_strategy: Arc::new(DefaultPricingStrategy),
         }
     }
 }
@@ -132,71 +176,66 @@ struct HistoricQuotingMetrics {
 
 impl NodeRecordStore {
     /// If a directory for our node already exists, repopulate the records from the files in the dir
+    ///
+    /// When `remove_undecryptable` is set, records that cannot be decrypted are removed from disk.
+    /// These are left over from a store that predates the persisted encryption key, and can only
+    /// be recovered by replication.
     fn update_records_from_an_existing_store(
-        config: &NodeRecordStoreConfig,
+        storage_backend: &Arc<dyn RecordStorageBackend>,
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
-    ) -> HashMap<Key, (NetworkAddress, RecordType)> {
-        let process_entry = |entry: &DirEntry| -> _ {
-            let path = entry.path();
-            if path.is_file() {
-                trace!("Existing record found: {path:?}");
-                // if we've got a file, lets try and read it
-                let filename = match path.file_name().and_then(|n| n.to_str()) {
-                    Some(file_name) => file_name,
-                    None => {
-                        // warn and remove this file as it's not a valid record
-                        warn!(
-                            "Found a file in the storage dir that is not a valid record: {:?}",
-                            path
-                        );
-                        if let Err(e) = fs::remove_file(path) {
-                            warn!(
-                                "Failed to remove invalid record file from storage dir: {:?}",
-                                e
-                            );
+        remove_undecryptable: bool,
+    ) -> Vec<(Key, (NetworkAddress, RecordType), usize)> {
+        let process_key = |key: &Key| -> _ {
+            let filename = generate_filename(key);
+            let (record, stored_len) = match storage_backend.read(key) {
+                Ok(bytes) => {
+                    let stored_len = bytes.len();
+                    // and the stored record
+                    let record = match Self::get_record_from_bytes(bytes, key, encryption_details) {
+                        Some(record) => record,
+                        None => {
+                            if remove_undecryptable {
+                                warn!("Removing record that cannot be decrypted with the persisted key: {filename}");
+                                if let Err(e) = storage_backend.remove(key) {
+                                    warn!(
+                                        "Failed to remove undecryptable record from storage: {e:?}"
+                                    );
+                                }
+                            }
+                            return None;
                         }
-                        return None;
-                    }
-                };
-                // get the record key from the filename
-                let key = Self::get_data_from_filename(filename)?;
-                let record = match fs::read(path) {
-                    Ok(bytes) => {
-                        // and the stored record
-                        Self::get_record_from_bytes(bytes, &key, encryption_details)?
-                    }
-                    Err(err) => {
-                        error!("Error while reading file. filename: {filename}, error: {err:?}");
-                        return None;
-                    }
-                };
+                    };
+                    (record, stored_len)
+                }
+                Err(err) => {
+                    error!("Error while reading file. filename: {filename}, error: {err:?}");
+                    return None;
+                }
+            };
 
-                let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
-                    Ok(true) => RecordType::Chunk,
-                    Ok(false) => {
-                        let xorname_hash = XorName::from_content(&record.value);
-                        RecordType::NonChunk(xorname_hash)
-                    }
-                    Err(error) => {
-                        warn!("Failed to parse record type from record: {:?}", error);
-                        return None;
-                    }
-                };
+            let record_type = match RecordHeader::is_record_of_type_chunk(&record) {
+                Ok(true) => RecordType::Chunk,
+                Ok(false) => {
+                    let xorname_hash = XorName::from_content(&record.value);
+                    RecordType::NonChunk(xorname_hash)
+                }
+                Err(error) => {
+                    warn!("Failed to parse record type from record: {:?}", error);
+                    return None;
+                }
+            };
 
-                let address = NetworkAddress::from_record_key(&key);
-                info!("Existing record loaded: {path:?}");
-                return Some((key, (address, record_type)));
-            }
-            None
+            let address = NetworkAddress::from_record_key(key);
+            info!("Existing record loaded: {filename}");
+            let size = storage_backend.size_on_disk(key, stored_len) as usize;
+            Some((key.clone(), (address, record_type), size))
         };
 
         info!("Attempting to repopulate records from existing store...");
-        let records = WalkDir::new(&config.storage_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .collect_vec()
+        let records = storage_backend
+            .keys()
             .par_iter()
-            .filter_map(process_entry)
+            .filter_map(process_key)
             .collect();
         records
     }
@@ -214,6 +253,79 @@ impl NodeRecordStore {
         None
     }
 
+    /// Restore the encryption details sealed in the `historic_quote_dir`,
+    /// or generate and persist new ones if there are none (or they cannot be unsealed).
+    /// Returns the encryption details and whether they were newly generated.
+    fn restore_or_create_encryption_details(
+        config: &NodeRecordStoreConfig,
+    ) -> ((Aes256GcmSiv, [u8; 4]), bool) {
+        let file_path = config.historic_quote_dir.join(ENCRYPTION_KEY_FILENAME);
+        let sealing_cipher = Aes256GcmSiv::new(&config.encryption_seed.into());
+
+        if let Ok(sealed) = fs::read(&file_path) {
+            if let Some(encryption_details) =
+                Self::unseal_encryption_details(&sealing_cipher, &sealed)
+            {
+                info!("Restored record encryption key from {file_path:?}");
+                return (encryption_details, false);
+            }
+            warn!(
+                "Failed to unseal record encryption key from {file_path:?}, generating a new one"
+            );
+        }
+
+        let mut key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut key_bytes);
+        let mut nonce_starter = [0u8; 4];
+        OsRng.fill_bytes(&mut nonce_starter);
+
+        // Only persist the key when records are actually encrypted.
+        if cfg!(feature = "encrypt-records") {
+            let mut sealing_nonce = [0u8; SEALING_NONCE_LEN];
+            OsRng.fill_bytes(&mut sealing_nonce);
+            let mut plaintext = key_bytes.to_vec();
+            plaintext.extend_from_slice(&nonce_starter);
+
+            match sealing_cipher.encrypt(Nonce::from_slice(&sealing_nonce), plaintext.as_ref()) {
+                Ok(ciphertext) => {
+                    let mut sealed = sealing_nonce.to_vec();
+                    sealed.extend(ciphertext);
+                    if let Err(err) = fs::write(&file_path, sealed) {
+                        error!("Failed to persist record encryption key to {file_path:?}: {err:?}");
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to seal record encryption key: {err:?}");
+                }
+            }
+        }
+
+        let cipher = Aes256GcmSiv::new(&key_bytes.into());
+        ((cipher, nonce_starter), true)
+    }
+
+    /// Unseal the encryption details persisted as `nonce || ciphertext(key || nonce_starter)`.
+    fn unseal_encryption_details(
+        sealing_cipher: &Aes256GcmSiv,
+        sealed: &[u8],
+    ) -> Option<(Aes256GcmSiv, [u8; 4])> {
+        if sealed.len() < SEALING_NONCE_LEN {
+            return None;
+        }
+        let (sealing_nonce, ciphertext) = sealed.split_at(SEALING_NONCE_LEN);
+        let plaintext = sealing_cipher
+            .decrypt(Nonce::from_slice(sealing_nonce), ciphertext)
+            .ok()?;
+        if plaintext.len() != 36 {
+            return None;
+        }
+
+        let cipher = Aes256GcmSiv::new_from_slice(&plaintext[..32]).ok()?;
+        let mut nonce_starter = [0u8; 4];
+        nonce_starter.copy_from_slice(&plaintext[32..]);
+        Some((cipher, nonce_starter))
+    }
+
     fn flush_historic_quoting_metrics(&self) {
         let file_path = self
             .config
@@ -240,12 +352,8 @@ impl NodeRecordStore {
         network_event_sender: mpsc::Sender<NetworkEvent>,
         swarm_cmd_sender: mpsc::Sender<SwarmCmd>,
     ) -> Self {
-        let key = Aes256GcmSiv::generate_key(&mut OsRng);
-        let cipher = Aes256GcmSiv::new(&key);
-        let mut nonce_starter = [0u8; 4];
-        OsRng.fill_bytes(&mut nonce_starter);
-
-        let encryption_details = (cipher, nonce_starter);
+        let (encryption_details, is_new_encryption_key) =
+            Self::restore_or_create_encryption_details(&config);
 
         // Recover the quoting_metrics first, as the historical file will be cleaned by
         // the later on update_records_from_an_existing_store function
@@ -260,12 +368,31 @@ impl NodeRecordStore {
             (0, SystemTime::now())
         };
 
-        let records = Self::update_records_from_an_existing_store(&config, &encryption_details);
+        let storage_backend = config
+            .storage_backend
+            .open(&config.storage_dir, config.max_storage_bytes);
+        let existing_records = Self::update_records_from_an_existing_store(
+            &storage_backend,
+            &encryption_details,
+            is_new_encryption_key && cfg!(feature = "encrypt-records"),
+        );
+        let mut records = HashMap::new();
+        let mut record_sizes = HashMap::new();
+        let mut stored_bytes = 0;
+        for (key, record_details, size) in existing_records {
+            stored_bytes += size;
+            let _ = record_sizes.insert(key.clone(), size);
+            let _ = records.insert(key, record_details);
+        }
+
         let mut record_store = NodeRecordStore {
             local_key: KBucketKey::from(local_id),
             local_address: NetworkAddress::from_peer(local_id),
             config,
+            storage_backend,
             records,
+            record_sizes,
+            stored_bytes,
             network_event_sender,
             swarm_cmd_sender,
             responsible_distance_range: None,
@@ -275,10 +402,23 @@ impl NodeRecordStore {
             encryption_details,
             timestamp,
             farthest_record: None,
+            scrub_queue: VecDeque::new(),
         };
 
         record_store.farthest_record = record_store.calculate_farthest();
 
+        // The quota may have been lowered since the records were stored.
+        while record_store.exceeds_storage_quota(0) {
+            let Some((farthest_record, _)) = record_store.farthest_record.clone() else {
+                break;
+            };
+            info!(
+                "Record {:?} will be pruned to fit the storage quota",
+                PrettyPrintRecordKey::from(&farthest_record)
+            );
+            record_store.remove(&farthest_record);
+        }
+
         record_store.flush_historic_quoting_metrics();
 
         record_store
@@ -296,22 +436,6 @@ impl NodeRecordStore {
         self.responsible_distance_range
     }
 
-    // Converts a Key into a Hex string.
-    fn generate_filename(key: &Key) -> String {
-        hex::encode(key.as_ref())
-    }
-
-    // Converts a Hex string back into a Key.
-    fn get_data_from_filename(hex_str: &str) -> Option<Key> {
-        match hex::decode(hex_str) {
-            Ok(bytes) => Some(Key::from(bytes)),
-            Err(error) => {
-                error!("Error decoding hex string: {:?}", error);
-                None
-            }
-        }
-    }
-
     /// Upon read perform any data transformations required to return a `Record`.
     fn get_record_from_bytes<'a>(
         bytes: Vec<u8>,
@@ -348,14 +472,13 @@ impl NodeRecordStore {
     fn read_from_disk<'a>(
         encryption_details: &(Aes256GcmSiv, [u8; 4]),
         key: &Key,
-        storage_dir: &Path,
+        storage_backend: &Arc<dyn RecordStorageBackend>,
     ) -> Option<Cow<'a, Record>> {
         let start = Instant::now();
-        let filename = Self::generate_filename(key);
-        let file_path = storage_dir.join(&filename);
+        let filename = generate_filename(key);
 
         // we should only be reading if we know the record is written to disk properly
-        match fs::read(file_path) {
+        match storage_backend.read(key) {
             Ok(bytes) => {
                 // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                 info!(
@@ -398,19 +521,60 @@ impl NodeRecordStore {
         }
     }
 
+    /// The most bytes the records stored take on disk, including the bytes of overwritten
+    /// and removed records the storage backend has yet to reclaim.
+    fn disk_usage(&self, extra_bytes: usize) -> u64 {
+        self.storage_backend
+            .max_disk_usage((self.stored_bytes + extra_bytes) as u64)
+    }
+
+    /// Returns `true` if storing `extra_bytes` more would exceed the storage quota.
+    fn exceeds_storage_quota(&self, extra_bytes: usize) -> bool {
+        self.config
+            .max_storage_bytes
+            .is_some_and(|max_bytes| self.disk_usage(extra_bytes) > max_bytes)
+    }
+
     /// Prune the records in the store to ensure that we free up space
     /// for the incoming record.
     /// Returns Ok if the record can be stored because it is closer to the local peer
     /// or we are not full.
     ///
     /// Err MaxRecords if we cannot store as it's farther than the farthest data we have
-    fn prune_records_if_needed(&mut self, incoming_record_key: &Key) -> Result<()> {
-        // we're not full, so we don't need to prune
-        if self.records.len() < self.config.max_records {
-            return Ok(());
-        }
+    /// Err ValueTooLarge if the record would not fit the storage quota even in an empty store
+    fn prune_records_if_needed(
+        &mut self,
+        incoming_record_key: &Key,
+        incoming_record_bytes: usize,
+    ) -> Result<()> {
+        loop {
+            // Any existing copy of the record is replaced by the incoming one.
+            let existing_bytes = self
+                .record_sizes
+                .get(incoming_record_key)
+                .copied()
+                .unwrap_or(0);
+            let is_full = self.records.len() >= self.config.max_records;
+            let is_over_quota =
+                self.exceeds_storage_quota(incoming_record_bytes.saturating_sub(existing_bytes));
+
+            // we're not full, so we don't need to prune
+            if !is_full && !is_over_quota {
+                return Ok(());
+            }
+
+            let Some((farthest_record, farthest_record_distance)) = self.farthest_record.clone()
+            else {
+                if is_over_quota {
+                    warn!(
+                        "Record {:?} of {incoming_record_bytes} bytes does not fit the storage quota",
+                        PrettyPrintRecordKey::from(incoming_record_key)
+                    );
+                    return Err(Error::ValueTooLarge);
+                }
+                return Ok(());
+            };
 
-        if let Some((farthest_record, farthest_record_distance)) = self.farthest_record.clone() {
             // if the incoming record is farther than the farthest record, we can't store it
             if farthest_record_distance
                 < self
@@ -425,9 +589,12 @@ impl NodeRecordStore {
                 PrettyPrintRecordKey::from(&farthest_record)
             );
             self.remove(&farthest_record);
-        }
 
-        Ok(())
+            // The record count only ever requires a single record to be pruned.
+            if !is_over_quota {
+                return Ok(());
+            }
+        }
     }
 }
 
@@ -452,6 +619,33 @@ impl NodeRecordStore {
         &self.records
     }
 
+    /// Returns the metadata of a locally held record, read back from disk.
+    /// The size and content hash are those of the decrypted record value.
+    pub(crate) fn record_metadata(&self, key: &Key) -> Option<RecordMetadata> {
+        let record = self.get(key)?;
+        let held_for = match self.storage_backend.stored_at(key) {
+            Ok(stored_at) => stored_at.elapsed().unwrap_or_default(),
+            Err(err) => {
+                warn!(
+                    "Failed to tell when {:?} was stored: {err:?}",
+                    PrettyPrintRecordKey::from(key)
+                );
+                Default::default()
+            }
+        };
+
+        match RecordMetadata::from_record(&record, held_for) {
+            Ok(metadata) => Some(metadata),
+            Err(err) => {
+                warn!(
+                    "Failed to describe record {:?}: {err:?}",
+                    PrettyPrintRecordKey::from(key)
+                );
+                None
+            }
+        }
+    }
+
     /// The follow up to `put_verified`, this only registers the RecordKey
     /// in the RecordStore records set. After this it should be safe
     /// to return the record as stored.
@@ -505,10 +699,18 @@ impl NodeRecordStore {
         let record_key = PrettyPrintRecordKey::from(&r.key).into_owned();
         trace!("PUT a verified Record: {record_key:?}");
 
-        self.prune_records_if_needed(&r.key)?;
+        // Accounted by the bytes it takes on disk once prepared for storage.
+        let size = self
+            .storage_backend
+            .size_on_disk(&r.key, r.value.len() + ENCRYPTION_OVERHEAD_BYTES)
+            as usize;
+        self.prune_records_if_needed(&r.key, size)?;
 
-        let filename = Self::generate_filename(&r.key);
-        let file_path = self.config.storage_dir.join(&filename);
+        // Account for the record size up front, so concurrent writes cannot overrun the quota.
+        let previous_size = self.record_sizes.insert(r.key.clone(), size);
+        self.stored_bytes = self.stored_bytes - previous_size.unwrap_or(0) + size;
+
+        let filename = generate_filename(&r.key);
 
         #[cfg(feature = "open-metrics")]
         if let Some(metric) = &self.record_count_metric {
@@ -517,10 +719,11 @@ impl NodeRecordStore {
 
         let encryption_details = self.encryption_details.clone();
         let cloned_cmd_sender = self.swarm_cmd_sender.clone();
+        let storage_backend = self.storage_backend.clone();
         spawn(async move {
             let key = r.key.clone();
             if let Some(bytes) = Self::prepare_record_bytes(r, encryption_details) {
-                let cmd = match fs::write(&file_path, bytes) {
+                let cmd = match storage_backend.write(&key, &bytes) {
                     Ok(_) => {
                         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
                         info!("Wrote record {record_key:?} to disk! filename: {filename}");
@@ -542,6 +745,63 @@ impl NodeRecordStore {
         Ok(())
     }
 
+    /// Re-verify the next `count` records of the scrubbing round against their keys.
+    /// A new round over all the records starts once the previous one is done.
+    ///
+    /// The records are read back from disk off thread, the outcome being reported with
+    /// `SwarmCmd::LocalRecordsScrubbed`.
+    pub(crate) fn scrub_records(&mut self, count: usize) {
+        if self.scrub_queue.is_empty() {
+            self.scrub_queue.extend(self.records.keys().cloned());
+        }
+
+        let mut keys = Vec::with_capacity(count);
+        while keys.len() < count {
+            let Some(key) = self.scrub_queue.pop_front() else {
+                break;
+            };
+            // Skip those having been removed since the round started.
+            if self.records.contains_key(&key) {
+                keys.push(key);
+            }
+        }
+        if keys.is_empty() {
+            return;
+        }
+
+        let encryption_details = self.encryption_details.clone();
+        let cloned_cmd_sender = self.swarm_cmd_sender.clone();
+        let storage_backend = self.storage_backend.clone();
+        spawn(async move {
+            let checked = keys.len();
+            let corrupt_keys = keys
+                .into_iter()
+                .filter(|key| {
+                    let is_intact = match storage_backend.read(key) {
+                        Ok(bytes) => Self::get_record_from_bytes(bytes, key, &encryption_details)
+                            .is_some_and(|record| is_record_intact(key, &record)),
+                        Err(err) => {
+                            warn!(
+                                "Failed to read record {:?} for scrubbing: {err:?}",
+                                PrettyPrintRecordKey::from(key)
+                            );
+                            false
+                        }
+                    };
+                    !is_intact
+                })
+                .collect();
+
+            send_swarm_cmd(
+                cloned_cmd_sender,
+                SwarmCmd::LocalRecordsScrubbed {
+                    checked,
+                    corrupt_keys,
+                },
+            );
+        });
+    }
+
     /// Calculate the cost to store data for our current store state
     #[allow(clippy::mutable_key_type)]
     pub(crate) fn store_cost(&self, key: &Key) -> (NanoTokens, QuotingMetrics) {
@@ -559,6 +819,13 @@ impl NodeRecordStore {
             max_records: self.config.max_records,
             received_payment_count: self.received_payment_count,
             live_time,
+            // Only carried along (and hence serialized) with a byte quota.
+            stored_bytes: if self.config.max_storage_bytes.is_some() {
+                self.disk_usage(0)
+            } else {
+                0
+            },
+            max_storage_bytes: self.config.max_storage_bytes,
         };
 
         if let Some(distance_range) = self.responsible_distance_range {
@@ -573,7 +840,7 @@ impl NodeRecordStore {
         let cost = if self.contains(key) {
             0
         } else {
-            calculate_cost_for_records(&quoting_metrics)
+            self.config.pricing_strategy.cost(&quoting_metrics)
         };
         // vdash metric (if modified please notify at https://github.com/happybeing/vdash/issues):
         info!("Cost is now {cost:?} for quoting_metrics {quoting_metrics:?}");
@@ -640,7 +907,7 @@ impl RecordStore for NodeRecordStore {
 
         debug!("GET request for Record key: {key}");
 
-        Self::read_from_disk(&self.encryption_details, k, &self.config.storage_dir)
+        Self::read_from_disk(&self.encryption_details, k, &self.storage_backend)
     }
 
     fn put(&mut self, record: Record) -> Result<()> {
@@ -707,6 +974,9 @@ impl RecordStore for NodeRecordStore {
 
     fn remove(&mut self, k: &Key) {
         let _ = self.records.remove(k);
+        if let Some(size) = self.record_sizes.remove(k) {
+            self.stored_bytes = self.stored_bytes.saturating_sub(size);
+        }
         #[cfg(feature = "open-metrics")]
         if let Some(metric) = &self.record_count_metric {
             let _ = metric.set(self.records.len() as i64);
@@ -718,11 +988,12 @@ impl RecordStore for NodeRecordStore {
             }
         }
 
-        let filename = Self::generate_filename(k);
-        let file_path = self.config.storage_dir.join(&filename);
+        let filename = generate_filename(k);
+        let storage_backend = self.storage_backend.clone();
+        let key = k.clone();
 
         let _handle = spawn(async move {
-            match fs::remove_file(file_path) {
+            match storage_backend.remove(&key) {
                 Ok(_) => {
                     info!("Removed record from disk! filename: {filename}");
                 }
@@ -758,13 +1029,46 @@ impl RecordStore for NodeRecordStore {
     }
 }
 
-/// A place holder RecordStore impl for the client that does nothing
+/// A RecordStore impl for the client, that holds no record on behalf of the network.
+/// It optionally caches the records fetched by the client.
 #[derive(Default, Debug)]
 pub struct ClientRecordStore {
     empty_record_addresses: HashMap<Key, (NetworkAddress, RecordType)>,
+    /// Kept out of `RecordStore::get`, so a cached copy never counts towards the quorum of a query.
+    cache: Option<RecordCache>,
 }
 
 impl ClientRecordStore {
+    /// A store caching the fetched records with the provided config, if any.
+    pub(crate) fn with_cache(cache_config: Option<RecordCacheConfig>) -> Self {
+        Self {
+            cache: cache_config.map(RecordCache::new),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn get_cached(&mut self, key: &Key) -> Option<CachedRecord> {
+        self.cache.as_mut().and_then(|cache| cache.get(key))
+    }
+
+    pub(crate) fn on_cached_chunk_read(&mut self, key: &Key, record: Option<Record>) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.on_disk_read(key, record);
+        }
+    }
+
+    pub(crate) fn cache_fetched(&mut self, record: Record) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.insert(record);
+        }
+    }
+
+    pub(crate) fn invalidate_cached(&mut self, key: &Key) {
+        if let Some(cache) = self.cache.as_mut() {
+            cache.remove(key);
+        }
+    }
+
     pub(crate) fn contains(&self, _key: &Key) -> bool {
         false
     }
@@ -823,10 +1127,12 @@ impl RecordStore for ClientRecordStore {
 // to allow nodes receiving too many replication copies can still got paid,
 // and gives an exponential pricing curve when storage reaches high.
 // and give extra reward (lower the quoting price to gain a better chance) to long lived nodes.
+// When a `max_storage_bytes` quota is set, the bytes fill is priced as the equivalent share
+// of `max_records`, so a node filling up with large records is not quoted as nearly empty.
 pub fn calculate_cost_for_records(quoting_metrics: &QuotingMetrics) -> u64 {
     use std::cmp::{max, min};
 
-    let records_stored = quoting_metrics.close_records_stored;
+    let records_stored = records_stored_for_pricing(quoting_metrics);
     let received_payment_count = quoting_metrics.received_payment_count;
     let max_records = quoting_metrics.max_records;
     let live_time = quoting_metrics.live_time;
@@ -867,12 +1173,16 @@ pub fn calculate_cost_for_records(quoting_metrics: &QuotingMetrics) -> u64 {
 mod tests {
 
     use super::*;
-    use crate::{close_group_majority, sort_peers_by_key, REPLICATION_PEERS_COUNT};
+    use crate::{
+        close_group_majority, pricing::LinearPricingStrategy, sort_peers_by_key,
+        REPLICATION_PEERS_COUNT,
+    };
     use bytes::Bytes;
     use eyre::ContextCompat;
+    use itertools::Itertools;
     use libp2p::{core::multihash::Multihash, kad::RecordKey};
     use quickcheck::*;
-    use sn_protocol::storage::{try_serialize_record, ChunkAddress};
+    use sn_protocol::storage::{try_serialize_record, Chunk, ChunkAddress};
     use std::collections::BTreeMap;
     use tokio::runtime::Runtime;
     use tokio::time::{sleep, Duration};
@@ -919,6 +1229,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: MAX_RECORDS_COUNT + 1,
             live_time: 1,
+            ..Default::default()
         });
         assert_eq!(sut, TOTAL_SUPPLY / CLOSE_GROUP_SIZE as u64);
     }
@@ -931,6 +1242,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 20480);
@@ -943,6 +1255,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 24570);
@@ -956,6 +1269,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 2528900);
@@ -969,6 +1283,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 262645870);
@@ -982,6 +1297,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 2689140767040);
@@ -995,6 +1311,7 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: percent,
             live_time: 1,
+            ..Default::default()
         });
         // at this point we should be at max cost
         assert_eq!(sut, 27719885856440320);
@@ -1007,10 +1324,34 @@ mod tests {
             max_records: MAX_RECORDS_COUNT,
             received_payment_count: 0,
             live_time: 1,
+            ..Default::default()
         });
         assert_eq!(sut, 10);
     }
 
+    #[test]
+    fn test_calculate_cost_for_records_honours_storage_quota() {
+        let max_storage_bytes = 1024 * 1024 * 1024;
+        let percent = MAX_RECORDS_COUNT * 80 / 100;
+        // A few large records filling 80% of the quota are priced as an 80% records fill.
+        let sut = calculate_cost_for_records(&QuotingMetrics {
+            close_records_stored: 10,
+            max_records: MAX_RECORDS_COUNT,
+            received_payment_count: percent,
+            live_time: 1,
+            stored_bytes: max_storage_bytes * 80 / 100,
+            max_storage_bytes: Some(max_storage_bytes),
+        });
+        let expected = calculate_cost_for_records(&QuotingMetrics {
+            close_records_stored: percent,
+            max_records: MAX_RECORDS_COUNT,
+            received_payment_count: percent,
+            live_time: 1,
+            ..Default::default()
+        });
+        assert_eq!(sut, expected);
+    }
+
     #[test]
     fn put_get_remove_record() {
         fn prop(r: ArbitraryRecord) {
@@ -1227,6 +1568,311 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn records_survive_restart() -> eyre::Result<()> {
+        let temp_dir = std::env::temp_dir();
+        let unique_dir_name = uuid::Uuid::new_v4().to_string();
+        let root_dir = temp_dir.join(unique_dir_name);
+        let storage_dir = root_dir.join("record_store");
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            historic_quote_dir: root_dir,
+            ..Default::default()
+        };
+        let self_id = PeerId::random();
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+
+        let mut store = NodeRecordStore::with_config(
+            self_id,
+            store_config.clone(),
+            network_event_sender.clone(),
+            swarm_cmd_sender.clone(),
+        );
+
+        let record_key = NetworkAddress::from_peer(PeerId::random()).to_record_key();
+        let value = match try_serialize_record(
+            &(0..50).map(|_| rand::random::<u8>()).collect::<Bytes>(),
+            RecordKind::Chunk,
+        ) {
+            Ok(value) => value.to_vec(),
+            Err(err) => panic!("Cannot generate record value {err:?}"),
+        };
+        let record = Record {
+            key: record_key.clone(),
+            value,
+            publisher: None,
+            expires: None,
+        };
+
+        assert!(store
+            .put_verified(record.clone(), RecordType::Chunk)
+            .is_ok());
+        store.mark_as_stored(record_key.clone(), RecordType::Chunk);
+
+        // loop over max_iterations times to ensure async disk write had time to complete.
+        let max_iterations = 10;
+        let mut iteration = 0;
+        while iteration < max_iterations {
+            if store.get(&record_key).is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+            iteration += 1;
+        }
+        assert!(iteration < max_iterations, "record was not written to disk");
+
+        // Restart the store with the same config (i.e. the same encryption seed).
+        drop(store);
+        let restarted_store = NodeRecordStore::with_config(
+            self_id,
+            store_config.clone(),
+            network_event_sender.clone(),
+            swarm_cmd_sender.clone(),
+        );
+
+        assert!(restarted_store.contains(&record_key));
+        assert_eq!(
+            Some(Cow::Borrowed(&record)),
+            restarted_store.get(&record_key),
+            "record can be read back after a restart"
+        );
+
+        // A store whose seed cannot unseal the persisted key cannot decrypt the existing records.
+        if cfg!(feature = "encrypt-records") {
+            drop(restarted_store);
+            let other_seed_config = NodeRecordStoreConfig {
+                encryption_seed: [0u8; 32],
+                ..store_config
+            };
+            let other_store = NodeRecordStore::with_config(
+                self_id,
+                other_seed_config,
+                network_event_sender,
+                swarm_cmd_sender,
+            );
+            assert!(!other_store.contains(&record_key));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scrubbing_reports_corrupt_records() -> eyre::Result<()> {
+        let temp_dir = std::env::temp_dir();
+        let unique_dir_name = uuid::Uuid::new_v4().to_string();
+        let storage_dir = temp_dir.join(unique_dir_name);
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+
+        let store_config = NodeRecordStoreConfig {
+            storage_dir: storage_dir.clone(),
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, mut swarm_cmd_receiver) = mpsc::channel(10);
+
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+
+        let mut keys = vec![];
+        for _ in 0..2 {
+            let chunk = Chunk::new((0..100).map(|_| rand::random::<u8>()).collect::<Bytes>());
+            let key = chunk.network_address().to_record_key();
+            let record = Record {
+                key: key.clone(),
+                value: try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec(),
+                publisher: None,
+                expires: None,
+            };
+            assert!(store.put_verified(record, RecordType::Chunk).is_ok());
+            store.mark_as_stored(key.clone(), RecordType::Chunk);
+            keys.push(key);
+        }
+
+        // loop over max_iterations times to ensure async disk write had time to complete.
+        let max_iterations = 10;
+        let mut iteration = 0;
+        while iteration < max_iterations {
+            if keys.iter().all(|key| store.get(key).is_some()) {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+            iteration += 1;
+        }
+        assert!(
+            iteration < max_iterations,
+            "records were not written to disk"
+        );
+
+        // Flip a bit of the first record on disk.
+        let corrupt_file = storage_dir.join(generate_filename(&keys[0]));
+        let mut bytes = fs::read(&corrupt_file)?;
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&corrupt_file, bytes)?;
+
+        store.scrub_records(10);
+
+        loop {
+            match swarm_cmd_receiver.recv().await {
+                Some(SwarmCmd::LocalRecordsScrubbed {
+                    checked,
+                    corrupt_keys,
+                }) => {
+                    assert_eq!(checked, 2);
+                    assert_eq!(corrupt_keys, vec![keys[0].clone()]);
+                    break;
+                }
+                Some(_) => continue,
+                None => panic!("swarm cmd channel closed before the scrubbing completed"),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_metadata_describes_stored_record() -> eyre::Result<()> {
+        let temp_dir = std::env::temp_dir();
+        let unique_dir_name = uuid::Uuid::new_v4().to_string();
+        let storage_dir = temp_dir.join(unique_dir_name);
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            ..Default::default()
+        };
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+
+        let mut store = NodeRecordStore::with_config(
+            PeerId::random(),
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+
+        let chunk = Chunk::new((0..100).map(|_| rand::random::<u8>()).collect::<Bytes>());
+        let key = chunk.network_address().to_record_key();
+        let value = try_serialize_record(&chunk, RecordKind::Chunk)?.to_vec();
+        assert!(store.record_metadata(&key).is_none());
+
+        let record = Record {
+            key: key.clone(),
+            value: value.clone(),
+            publisher: None,
+            expires: None,
+        };
+        assert!(store.put_verified(record, RecordType::Chunk).is_ok());
+        store.mark_as_stored(key.clone(), RecordType::Chunk);
+
+        // loop over max_iterations times to ensure async disk write had time to complete.
+        let max_iterations = 10;
+        let mut iteration = 0;
+        let metadata = loop {
+            if let Some(metadata) = store.record_metadata(&key) {
+                break metadata;
+            }
+            iteration += 1;
+            assert!(iteration < max_iterations, "record was not written to disk");
+            sleep(Duration::from_millis(100)).await;
+        };
+
+        assert_eq!(metadata.kind, RecordKind::Chunk);
+        assert_eq!(metadata.size, value.len());
+        assert_eq!(metadata.content_hash, XorName::from_content(&value));
+        assert!(metadata.held_for < Duration::from_secs(60));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pruning_on_storage_quota() -> Result<()> {
+        let record_value_len = 1024;
+        // Leave room for about 20 records, each taking a whole file block, far below max_records
+        let max_storage_bytes = 20 * 4096;
+
+        let temp_dir = std::env::temp_dir();
+        let unique_dir_name = uuid::Uuid::new_v4().to_string();
+        let storage_dir = temp_dir.join(unique_dir_name);
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+
+        let store_config = NodeRecordStoreConfig {
+            storage_dir,
+            max_storage_bytes: Some(max_storage_bytes),
+            ..Default::default()
+        };
+        let self_id = PeerId::random();
+        let (network_event_sender, _) = mpsc::channel(1);
+        let (swarm_cmd_sender, _) = mpsc::channel(1);
+
+        let mut store = NodeRecordStore::with_config(
+            self_id,
+            store_config,
+            network_event_sender,
+            swarm_cmd_sender,
+        );
+        let self_address = NetworkAddress::from_peer(self_id);
+
+        let mut failed_records = vec![];
+        for _ in 0..100 {
+            let record_key = NetworkAddress::from_peer(PeerId::random()).to_record_key();
+            let value = match try_serialize_record(
+                &(0..record_value_len)
+                    .map(|_| rand::random::<u8>())
+                    .collect::<Bytes>(),
+                RecordKind::Chunk,
+            ) {
+                Ok(value) => value.to_vec(),
+                Err(err) => panic!("Cannot generate record value {err:?}"),
+            };
+            let record = Record {
+                key: record_key.clone(),
+                value,
+                publisher: None,
+                expires: None,
+            };
+
+            if store.put_verified(record, RecordType::Chunk).is_ok() {
+                store.mark_as_stored(record_key, RecordType::Chunk);
+            } else {
+                failed_records.push(record_key);
+            }
+
+            assert!(
+                store.disk_usage(0) <= max_storage_bytes,
+                "disk usage {} exceeds the quota {max_storage_bytes}",
+                store.disk_usage(0)
+            );
+        }
+
+        assert!(store.record_addresses().len() < store.config.max_records);
+        assert_eq!(
+            store.stored_bytes,
+            store.record_sizes.values().sum::<usize>()
+        );
+
+        // Records rejected for the quota are farther than anything kept.
+        let farthest_stored = store
+            .record_addresses()
+            .keys()
+            .map(|addr| self_address.distance(addr))
+            .max()
+            .expect("some records are stored");
+        for failed_record in failed_records {
+            let failed_data = NetworkAddress::from_record_key(&failed_record);
+            assert!(self_address.distance(&failed_data) > farthest_stored);
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     #[allow(clippy::mutable_key_type)]
     async fn get_records_within_bucket_range() -> eyre::Result<()> {
@@ -1355,8 +2001,47 @@ mod tests {
         Ok(())
     }
 
+    /// The outcome of `run_address_distribution_sim` after its last iteration.
+    #[derive(Debug)]
+    struct AddressDistributionSimResult {
+        empty_earned_nodes: usize,
+        min_store_cost: u64,
+        max_store_cost: u64,
+        min_earned: u64,
+        max_earned: u64,
+    }
+
     #[test]
     fn address_distribution_sim() {
+        let default_result = run_address_distribution_sim(&DefaultPricingStrategy);
+        assert_eq!(
+            0, default_result.empty_earned_nodes,
+            "every node has earnt _something_"
+        );
+        assert!(
+            (default_result.max_store_cost / default_result.min_store_cost) < 100,
+            "store cost is balanced"
+        );
+        assert!(
+            (default_result.max_earned / default_result.min_earned) < 1000,
+            "earning distribution is well balanced"
+        );
+
+        // Compare against a curve that ignores payments received, which leaves the balancing
+        // purely to the number of records stored: the nodes that got paid less often are not
+        // quoted any cheaper, hence some of them never get paid at all.
+        let linear_result = run_address_distribution_sim(&LinearPricingStrategy::new(10, 10_000));
+        println!("default pricing strategy: {default_result:?}");
+        println!("linear pricing strategy: {linear_result:?}");
+        assert!(
+            default_result.empty_earned_nodes < linear_result.empty_earned_nodes,
+            "the default strategy spreads the earnings wider than the linear one"
+        );
+    }
+
+    fn run_address_distribution_sim(
+        strategy: &dyn PricingStrategy,
+    ) -> AddressDistributionSimResult {
         // Map of peers and correspondent stats of `(num_of_records, Nano_earned, received_payment_count)`.
         let mut peers: HashMap<PeerId, (usize, u64, usize)> = Default::default();
         let mut peers_vec = vec![];
@@ -1400,17 +2085,18 @@ mod tests {
                             }
                         };
 
-                        let payee = pick_cheapest_payee(&peers_in_close, &peers);
+                        let payee = pick_cheapest_payee(&peers_in_close, &peers, strategy);
 
                         for peer in peers_in_replicate_range.iter() {
                             let (close_records_stored, nanos_earnt, received_payment_count) =
                                 peers.entry(*peer).or_insert((0, 0, 0));
                             if *peer == payee {
-                                let cost = calculate_cost_for_records(&QuotingMetrics {
+                                let cost = strategy.cost(&QuotingMetrics {
                                     close_records_stored: *close_records_stored,
                                     max_records: MAX_RECORDS_COUNT,
                                     received_payment_count: *received_payment_count,
                                     live_time: 0,
+                                    ..Default::default()
                                 });
                                 *nanos_earnt += cost;
                                 *received_payment_count += 1;
@@ -1433,11 +2119,12 @@ mod tests {
             let mut max_store_cost = 0;
 
             for (_peer_id, (close_records_stored, nanos_earnt, times_paid)) in peers.iter() {
-                let cost = calculate_cost_for_records(&QuotingMetrics {
+                let cost = strategy.cost(&QuotingMetrics {
                     close_records_stored: *close_records_stored,
                     max_records: MAX_RECORDS_COUNT,
                     received_payment_count: *times_paid,
                     live_time: 0,
+                    ..Default::default()
                 });
                 // println!("{peer_id:?}:{stats:?} with storecost to be {cost}");
                 received_payment_count += times_paid;
@@ -1469,20 +2156,15 @@ mod tests {
 
             // Execute for 50 iterations, which allows the test can be executed in normal CI runs.
             if iteration == 50 {
-                assert_eq!(0, empty_earned_nodes, "every node has earnt _something_");
-                assert!(
-                    (max_store_cost / min_store_cost) < 100,
-                    "store cost is balanced"
-                );
-                assert!(
-                    (max_earned / min_earned) < 1000,
-                    "earning distribution is well balanced"
-                );
-                break;
+                return AddressDistributionSimResult {
+                    empty_earned_nodes,
+                    min_store_cost,
+                    max_store_cost,
+                    min_earned,
+                    max_earned,
+                };
             }
         }
-
-        // log_chunks_distribution(&peers);
     }
 
     // Split nodes into groups based on its kBucketKey's leading byte of hashed_bytes.
@@ -1523,17 +2205,19 @@ mod tests {
     fn pick_cheapest_payee(
         peers_in_close: &Vec<PeerId>,
         peers: &HashMap<PeerId, (usize, u64, usize)>,
+        strategy: &dyn PricingStrategy,
     ) -> PeerId {
         let mut payee = None;
         let mut cheapest_cost = u64::MAX;
 
         for peer in peers_in_close {
             if let Some(stats) = peers.get(peer) {
-                let store_cost = calculate_cost_for_records(&QuotingMetrics {
+                let store_cost = strategy.cost(&QuotingMetrics {
                     close_records_stored: stats.0,
                     max_records: MAX_RECORDS_COUNT,
                     received_payment_count: stats.2,
                     live_time: 0,
+                    ..Default::default()
                 });
                 if store_cost < cheapest_cost {
                     cheapest_cost = store_cost;
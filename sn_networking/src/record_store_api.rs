@@ -7,12 +7,15 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for the Bytes in NetworkAddress
 
-use crate::record_store::{ClientRecordStore, NodeRecordStore};
+use crate::{
+    record_cache::CachedRecord,
+    record_store::{ClientRecordStore, NodeRecordStore},
+};
 use libp2p::kad::{
     store::{RecordStore, Result},
     ProviderRecord, Record, RecordKey,
 };
-use sn_protocol::{storage::RecordType, NetworkAddress};
+use sn_protocol::{messages::RecordMetadata, storage::RecordType, NetworkAddress};
 use sn_transfers::{NanoTokens, QuotingMetrics};
 use std::{borrow::Cow, collections::HashMap};
 
@@ -131,6 +134,57 @@ impl UnifiedRecordStore {
         }
     }
 
+    pub(crate) fn record_metadata(&self, key: &RecordKey) -> Option<RecordMetadata> {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling record_metadata at Client. This should not happen");
+                None
+            }
+            Self::Node(store) => store.record_metadata(key),
+        }
+    }
+
+    /// The fetched record cached by a client, if any.
+    pub(crate) fn get_cached(&mut self, key: &RecordKey) -> Option<CachedRecord> {
+        match self {
+            Self::Client(store) => store.get_cached(key),
+            // Nodes hold the records they are responsible for, and do not cache the others.
+            Self::Node(_store) => None,
+        }
+    }
+
+    /// Take in the outcome of reading a chunk the client cached on disk.
+    pub(crate) fn on_cached_chunk_read(&mut self, key: &RecordKey, record: Option<Record>) {
+        match self {
+            Self::Client(store) => store.on_cached_chunk_read(key, record),
+            Self::Node(_store) => {}
+        }
+    }
+
+    pub(crate) fn cache_fetched(&mut self, record: Record) {
+        match self {
+            Self::Client(store) => store.cache_fetched(record),
+            Self::Node(_store) => {}
+        }
+    }
+
+    /// Drop the record cached by a client, as it is putting a newer version of it.
+    pub(crate) fn invalidate_cached(&mut self, key: &RecordKey) {
+        match self {
+            Self::Client(store) => store.invalidate_cached(key),
+            Self::Node(_store) => {}
+        }
+    }
+
+    pub(crate) fn scrub_records(&mut self, count: usize) {
+        match self {
+            Self::Client(_store) => {
+                warn!("Calling scrub_records at Client. This should not happen");
+            }
+            Self::Node(store) => store.scrub_records(count),
+        }
+    }
+
     pub(crate) fn get_farthest_replication_distance_bucket(&self) -> Option<u32> {
         match self {
             Self::Client(_store) => {
@@ -0,0 +1,934 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+#![allow(clippy::mutable_key_type)] // for the Bytes in RecordKey
+
+use libp2p::kad::RecordKey as Key;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use strum::{Display, EnumString};
+use walkdir::WalkDir;
+
+/// Segments are rotated once they would grow beyond this size.
+const MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// With a storage quota, segments are kept to this fraction of it at most, so the active
+/// segment, which is not compacted, doesn't take up most of the quota.
+const SEGMENTS_PER_QUOTA: u64 = 8;
+
+/// The size of the filesystem blocks record files are assumed to be stored in.
+const FILE_BLOCK_SIZE: u64 = 4096;
+
+/// A sealed segment is compacted once at least this percentage of its bytes are dead.
+const COMPACTION_THRESHOLD_PERCENT: u64 = 50;
+
+/// Prefix of the segment file names, followed by the segment id.
+const SEGMENT_FILENAME_PREFIX: &str = "segment_";
+
+/// Extension of the temporary files records are written to before being renamed over.
+const TEMP_FILE_EXTENSION: &str = "tmp";
+
+const ENTRY_KIND_PUT: u8 = 0;
+const ENTRY_KIND_TOMBSTONE: u8 = 1;
+
+/// Entry layout: kind (1 byte) + key_len (4 bytes) + value_len (4 bytes) + stored_at (8 bytes,
+/// milliseconds since the unix epoch), then key and value.
+const ENTRY_HEADER_LEN: u64 = 17;
+
+/// The on-disk layout used by the `NodeRecordStore` to hold record bytes.
+///
+/// The backend only deals with the already prepared (e.g. encrypted) bytes of a record;
+/// validation, pruning and bookkeeping of the stored keys stay with the record store.
+pub trait RecordStorageBackend: Send + Sync {
+    /// Persist the bytes of the record under `key`, replacing any existing value.
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()>;
+
+    /// Read back the bytes stored under `key`.
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>>;
+
+    /// Remove the bytes stored under `key`.
+    fn remove(&self, key: &Key) -> io::Result<()>;
+
+    /// Returns the keys of all the records currently held on disk.
+    fn keys(&self) -> Vec<Key>;
+
+    /// When the bytes stored under `key` were written to disk. Backends that cannot tell
+    /// exactly shall err on the late side, so the record is never claimed to be held for
+    /// longer than it actually was.
+    fn stored_at(&self, key: &Key) -> io::Result<SystemTime>;
+
+    /// The bytes writing `len` bytes under `key` takes on disk.
+    fn size_on_disk(&self, key: &Key, len: usize) -> u64;
+
+    /// The most bytes the backend may take on disk while holding records taking `live_bytes`
+    /// bytes (as per `size_on_disk`), including the bytes of overwritten and removed records
+    /// not reclaimed yet.
+    fn max_disk_usage(&self, live_bytes: u64) -> u64;
+}
+
+/// The available `RecordStorageBackend` implementations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RecordStorageBackendKind {
+    /// One file per record in a flat directory.
+    #[default]
+    FlatFile,
+    /// Records packed into append-only segment files. Segments are compacted inline, by the
+    /// write or removal leaving enough of them dead, while the store is locked.
+    Segmented,
+}
+
+impl RecordStorageBackendKind {
+    /// Open the backend of this kind over the provided directory, sized for the given storage
+    /// quota if any.
+    pub(crate) fn open(
+        &self,
+        storage_dir: &Path,
+        max_storage_bytes: Option<u64>,
+    ) -> Arc<dyn RecordStorageBackend> {
+        match self {
+            Self::FlatFile => Arc::new(FlatFileBackend::new(storage_dir.to_path_buf())),
+            Self::Segmented => {
+                let max_segment_size = max_storage_bytes.map_or(MAX_SEGMENT_SIZE, |max_bytes| {
+                    (max_bytes / SEGMENTS_PER_QUOTA).clamp(1, MAX_SEGMENT_SIZE)
+                });
+                Arc::new(SegmentedBackend::open(
+                    storage_dir.to_path_buf(),
+                    max_segment_size,
+                ))
+            }
+        }
+    }
+}
+
+// Converts a Key into a Hex string.
+pub(crate) fn generate_filename(key: &Key) -> String {
+    hex::encode(key.as_ref())
+}
+
+// Converts a Hex string back into a Key.
+fn get_data_from_filename(hex_str: &str) -> Option<Key> {
+    match hex::decode(hex_str) {
+        Ok(bytes) => Some(Key::from(bytes)),
+        Err(error) => {
+            error!("Error decoding hex string: {:?}", error);
+            None
+        }
+    }
+}
+
+/// Stores each record in its own file, named after the hex encoded record key.
+///
+/// Records are written to a temporary file first then renamed over, so a concurrent read
+/// (e.g. by the scrubber) never sees a half written record.
+pub struct FlatFileBackend {
+    storage_dir: PathBuf,
+    /// Makes each write's temporary file name unique, so concurrent writes of one record
+    /// don't write into the same temporary file.
+    temp_file_counter: AtomicU64,
+}
+
+impl FlatFileBackend {
+    pub fn new(storage_dir: PathBuf) -> Self {
+        Self {
+            storage_dir,
+            temp_file_counter: AtomicU64::new(0),
+        }
+    }
+}
+
+impl RecordStorageBackend for FlatFileBackend {
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()> {
+        let path = self.storage_dir.join(generate_filename(key));
+        // Temporary files left over by a crash are removed by `keys`.
+        let temp_path = path.with_extension(format!(
+            "{}.{TEMP_FILE_EXTENSION}",
+            self.temp_file_counter.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        // A record being rewritten keeps the time it was first stored at.
+        if let Ok(stored_at) = fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+            file.set_modified(stored_at)?;
+        }
+        drop(file);
+        fs::rename(temp_path, path)
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>> {
+        fs::read(self.storage_dir.join(generate_filename(key)))
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        fs::remove_file(self.storage_dir.join(generate_filename(key)))
+    }
+
+    /// The modification time of the file, which is carried over when the record is rewritten.
+    fn stored_at(&self, key: &Key) -> io::Result<SystemTime> {
+        fs::metadata(self.storage_dir.join(generate_filename(key)))?.modified()
+    }
+
+    /// Files take up whole filesystem blocks.
+    fn size_on_disk(&self, _key: &Key, len: usize) -> u64 {
+        (len as u64).div_ceil(FILE_BLOCK_SIZE) * FILE_BLOCK_SIZE
+    }
+
+    /// Files are removed right away, nothing is left to reclaim.
+    fn max_disk_usage(&self, live_bytes: u64) -> u64 {
+        live_bytes
+    }
+
+    fn keys(&self) -> Vec<Key> {
+        WalkDir::new(&self.storage_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if !path.is_file() {
+                    return None;
+                }
+                if path
+                    .extension()
+                    .is_some_and(|ext| ext == TEMP_FILE_EXTENSION)
+                {
+                    debug!("Removing temporary record file left over by a crash: {path:?}");
+                    if let Err(e) = fs::remove_file(path) {
+                        warn!("Failed to remove temporary record file {path:?}: {e:?}");
+                    }
+                    return None;
+                }
+                trace!("Existing record found: {path:?}");
+                match path.file_name().and_then(|n| n.to_str()) {
+                    Some(file_name) => get_data_from_filename(file_name),
+                    None => {
+                        // warn and remove this file as it's not a valid record
+                        warn!(
+                            "Found a file in the storage dir that is not a valid record: {:?}",
+                            path
+                        );
+                        if let Err(e) = fs::remove_file(path) {
+                            warn!(
+                                "Failed to remove invalid record file from storage dir: {:?}",
+                                e
+                            );
+                        }
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where the value of a live record sits within the segments.
+#[derive(Debug, Clone, Copy)]
+struct EntryLocation {
+    segment: u64,
+    /// Offset of the entry header within the segment.
+    offset: u64,
+    value_len: u32,
+    /// Full length of the entry, header included.
+    entry_len: u64,
+    /// When the record was first written, in milliseconds since the unix epoch.
+    stored_at: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SegmentStats {
+    size: u64,
+    dead_bytes: u64,
+    /// Compacting the segment failed, it is not tried again until the storage is reopened.
+    compaction_failed: bool,
+}
+
+struct SegmentedState {
+    storage_dir: PathBuf,
+    index: HashMap<Key, EntryLocation>,
+    segments: BTreeMap<u64, SegmentStats>,
+    active_segment: u64,
+    max_segment_size: u64,
+}
+
+/// Packs records into append-only segment files.
+///
+/// Overwritten and removed records leave dead bytes behind (removals append a tombstone);
+/// once enough of a sealed segment is dead, its live entries are copied into the active
+/// segment and the old segment file is deleted.
+pub struct SegmentedBackend {
+    state: Mutex<SegmentedState>,
+}
+
+impl SegmentedBackend {
+    /// Open the segments under `storage_dir`, rebuilding the index of live records.
+    /// Segments are rotated once they would grow beyond `max_segment_size`.
+    pub fn open(storage_dir: PathBuf, max_segment_size: u64) -> Self {
+        let mut segment_ids: Vec<u64> = match fs::read_dir(&storage_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter_map(|entry| {
+                    entry
+                        .file_name()
+                        .to_str()
+                        .and_then(|name| name.strip_prefix(SEGMENT_FILENAME_PREFIX))
+                        .and_then(|id| id.parse().ok())
+                })
+                .collect(),
+            Err(err) => {
+                error!("Failed to list segments in {storage_dir:?}: {err:?}");
+                vec![]
+            }
+        };
+        segment_ids.sort();
+
+        let mut state = SegmentedState {
+            storage_dir,
+            index: HashMap::new(),
+            segments: BTreeMap::new(),
+            active_segment: segment_ids.last().copied().unwrap_or(0),
+            max_segment_size,
+        };
+
+        for segment in segment_ids {
+            if let Err(err) = state.load_segment(segment) {
+                error!("Failed to load segment {segment}: {err:?}");
+            }
+        }
+        let _ = state.segments.entry(state.active_segment).or_default();
+
+        info!(
+            "Opened segmented record storage with {} records in {} segments",
+            state.index.len(),
+            state.segments.len()
+        );
+
+        // Catch up with any compaction interrupted by a restart.
+        let sealed_segments: Vec<u64> = state
+            .segments
+            .keys()
+            .copied()
+            .filter(|segment| *segment != state.active_segment)
+            .collect();
+        for segment in sealed_segments {
+            state.compact_if_needed(segment);
+        }
+
+        Self {
+            state: Mutex::new(state),
+        }
+    }
+
+    fn lock_state(&self) -> io::Result<MutexGuard<'_, SegmentedState>> {
+        self.state
+            .lock()
+            .map_err(|_| io::Error::other("segment state lock poisoned"))
+    }
+}
+
+impl SegmentedState {
+    fn segment_path(&self, segment: u64) -> PathBuf {
+        self.storage_dir
+            .join(format!("{SEGMENT_FILENAME_PREFIX}{segment}"))
+    }
+
+    /// Replay the entries of a segment into the index.
+    /// A partially written trailing entry (e.g. from a crash) is truncated away.
+    fn load_segment(&mut self, segment: u64) -> io::Result<()> {
+        let path = self.segment_path(segment);
+        let file = fs::File::open(&path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut offset = 0;
+
+        while offset < file_len {
+            let (kind, key, value_len, stored_at) = match read_entry_header(&mut reader) {
+                Ok(header) => header,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            };
+            let entry_len = ENTRY_HEADER_LEN + key.as_ref().len() as u64 + value_len as u64;
+            if offset + entry_len > file_len {
+                break;
+            }
+            reader.seek_relative(value_len as i64)?;
+
+            match kind {
+                ENTRY_KIND_PUT => {
+                    let location = EntryLocation {
+                        segment,
+                        offset,
+                        value_len,
+                        entry_len,
+                        stored_at,
+                    };
+                    if let Some(old) = self.index.insert(key, location) {
+                        self.mark_dead(old.segment, old.entry_len);
+                    }
+                    self.segments.entry(segment).or_default().size += entry_len;
+                }
+                ENTRY_KIND_TOMBSTONE => {
+                    if let Some(old) = self.index.remove(&key) {
+                        self.mark_dead(old.segment, old.entry_len);
+                    }
+                    let stats = self.segments.entry(segment).or_default();
+                    stats.size += entry_len;
+                    stats.dead_bytes += entry_len;
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown entry kind {kind} in segment {segment}"),
+                    ));
+                }
+            }
+            offset += entry_len;
+        }
+
+        if offset < file_len {
+            warn!("Truncating partially written entry at the end of segment {segment}");
+            fs::OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_len(offset)?;
+        }
+        let _ = self.segments.entry(segment).or_default();
+
+        Ok(())
+    }
+
+    fn mark_dead(&mut self, segment: u64, len: u64) {
+        if let Some(stats) = self.segments.get_mut(&segment) {
+            stats.dead_bytes += len;
+        }
+    }
+
+    /// Append an entry to the active segment, rotating it first if it would grow too large.
+    /// Returns the location of the appended entry.
+    fn append(
+        &mut self,
+        kind: u8,
+        key: &Key,
+        value: &[u8],
+        stored_at: u64,
+    ) -> io::Result<EntryLocation> {
+        let entry_len = ENTRY_HEADER_LEN + key.as_ref().len() as u64 + value.len() as u64;
+        let active_size = self
+            .segments
+            .get(&self.active_segment)
+            .map(|stats| stats.size)
+            .unwrap_or(0);
+        if active_size > 0 && active_size + entry_len > self.max_segment_size {
+            self.active_segment += 1;
+            debug!("Rotating to new segment {}", self.active_segment);
+        }
+
+        let mut entry = Vec::with_capacity(entry_len as usize);
+        entry.push(kind);
+        entry.extend_from_slice(&(key.as_ref().len() as u32).to_le_bytes());
+        entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        entry.extend_from_slice(&stored_at.to_le_bytes());
+        entry.extend_from_slice(key.as_ref());
+        entry.extend_from_slice(value);
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.segment_path(self.active_segment))?;
+        file.write_all(&entry)?;
+
+        let stats = self.segments.entry(self.active_segment).or_default();
+        let offset = stats.size;
+        stats.size += entry_len;
+
+        Ok(EntryLocation {
+            segment: self.active_segment,
+            offset,
+            value_len: value.len() as u32,
+            entry_len,
+            stored_at,
+        })
+    }
+
+    fn read_value(&self, location: &EntryLocation, key: &Key) -> io::Result<Vec<u8>> {
+        let mut file = fs::File::open(self.segment_path(location.segment))?;
+        let value_offset = location.offset + ENTRY_HEADER_LEN + key.as_ref().len() as u64;
+        let _ = file.seek(SeekFrom::Start(value_offset))?;
+        let mut value = vec![0; location.value_len as usize];
+        file.read_exact(&mut value)?;
+        Ok(value)
+    }
+
+    /// Compact a sealed segment if enough of it is dead.
+    fn compact_if_needed(&mut self, segment: u64) {
+        if segment == self.active_segment {
+            return;
+        }
+        let Some(stats) = self.segments.get(&segment) else {
+            return;
+        };
+        if stats.compaction_failed
+            || stats.dead_bytes * 100 < stats.size * COMPACTION_THRESHOLD_PERCENT
+        {
+            return;
+        }
+
+        if let Err(err) = self.compact(segment) {
+            error!("Failed to compact segment {segment}, leaving it until restart: {err:?}");
+            if let Some(stats) = self.segments.get_mut(&segment) {
+                stats.compaction_failed = true;
+            }
+        }
+    }
+
+    /// Copy the live entries of a sealed segment into the active one, then delete it.
+    /// The segment is kept if reading it fails, or if any live record still points into it.
+    ///
+    /// Tombstones are carried over as long as an older segment could still hold the entry they
+    /// cancel, and the key has not been written again since.
+    fn compact(&mut self, segment: u64) -> io::Result<()> {
+        let path = self.segment_path(segment);
+        let has_older_segments = self.segments.keys().any(|id| *id < segment);
+        let mut reader = BufReader::new(fs::File::open(&path)?);
+        let mut offset = 0;
+        let mut moved = 0;
+
+        loop {
+            let (kind, key, value_len, stored_at) = match read_entry_header(&mut reader) {
+                Ok(header) => header,
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            };
+            let entry_len = ENTRY_HEADER_LEN + key.as_ref().len() as u64 + value_len as u64;
+            let mut value = vec![0; value_len as usize];
+            reader.read_exact(&mut value)?;
+
+            match kind {
+                ENTRY_KIND_PUT => {
+                    let is_live = self
+                        .index
+                        .get(&key)
+                        .is_some_and(|loc| loc.segment == segment && loc.offset == offset);
+                    if is_live {
+                        let location = self.append(ENTRY_KIND_PUT, &key, &value, stored_at)?;
+                        let _ = self.index.insert(key, location);
+                        moved += 1;
+                    }
+                }
+                ENTRY_KIND_TOMBSTONE if has_older_segments && !self.index.contains_key(&key) => {
+                    let _ = self.append(ENTRY_KIND_TOMBSTONE, &key, &[], stored_at)?;
+                    self.mark_dead(self.active_segment, entry_len);
+                }
+                _ => {}
+            }
+            offset += entry_len;
+        }
+
+        if self.index.values().any(|loc| loc.segment == segment) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("live records of segment {segment} were not all copied out"),
+            ));
+        }
+        fs::remove_file(&path)?;
+        let _ = self.segments.remove(&segment);
+        info!("Compacted segment {segment}, moved {moved} live records");
+        Ok(())
+    }
+}
+
+/// Read the header of an entry along with its key, returning its kind, key, value length and
+/// stored at time.
+fn read_entry_header(reader: &mut impl Read) -> io::Result<(u8, Key, u32, u64)> {
+    let mut header = [0u8; ENTRY_HEADER_LEN as usize];
+    reader.read_exact(&mut header)?;
+    let kind = header[0];
+    let key_len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+    let value_len = u32::from_le_bytes([header[5], header[6], header[7], header[8]]);
+    let mut stored_at = [0u8; 8];
+    stored_at.copy_from_slice(&header[9..17]);
+
+    let mut key = vec![0; key_len as usize];
+    reader.read_exact(&mut key)?;
+
+    Ok((
+        kind,
+        Key::from(key),
+        value_len,
+        u64::from_le_bytes(stored_at),
+    ))
+}
+
+// The current time, in milliseconds since the unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+impl RecordStorageBackend for SegmentedBackend {
+    fn write(&self, key: &Key, bytes: &[u8]) -> io::Result<()> {
+        let mut state = self.lock_state()?;
+        // A record being rewritten keeps the time it was first stored at.
+        let stored_at = state
+            .index
+            .get(key)
+            .map_or_else(now_millis, |location| location.stored_at);
+        let location = state.append(ENTRY_KIND_PUT, key, bytes, stored_at)?;
+        if let Some(old) = state.index.insert(key.clone(), location) {
+            state.mark_dead(old.segment, old.entry_len);
+            state.compact_if_needed(old.segment);
+        }
+        Ok(())
+    }
+
+    fn read(&self, key: &Key) -> io::Result<Vec<u8>> {
+        let state = self.lock_state()?;
+        match state.index.get(key) {
+            Some(location) => state.read_value(location, key),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "record not found in segments",
+            )),
+        }
+    }
+
+    fn remove(&self, key: &Key) -> io::Result<()> {
+        let mut state = self.lock_state()?;
+        let Some(old) = state.index.remove(key) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "record not found in segments",
+            ));
+        };
+        let tombstone = state.append(ENTRY_KIND_TOMBSTONE, key, &[], now_millis())?;
+        state.mark_dead(tombstone.segment, tombstone.entry_len);
+        state.mark_dead(old.segment, old.entry_len);
+        state.compact_if_needed(old.segment);
+        Ok(())
+    }
+
+    fn keys(&self) -> Vec<Key> {
+        match self.lock_state() {
+            Ok(state) => state.index.keys().cloned().collect(),
+            Err(err) => {
+                error!("Failed to list the keys of the segmented storage: {err:?}");
+                vec![]
+            }
+        }
+    }
+
+    fn size_on_disk(&self, key: &Key, len: usize) -> u64 {
+        ENTRY_HEADER_LEN + key.as_ref().len() as u64 + len as u64
+    }
+
+    /// Sealed segments are compacted before half of them is dead, while the active one may be
+    /// dead all over, e.g. taken up by tombstones. Segments failing to be compacted are counted
+    /// in full.
+    fn max_disk_usage(&self, live_bytes: u64) -> u64 {
+        let (max_segment_size, failed_segments_size) =
+            self.lock_state().map_or((MAX_SEGMENT_SIZE, 0), |state| {
+                let failed_segments_size = state
+                    .segments
+                    .values()
+                    .filter(|stats| stats.compaction_failed)
+                    .map(|stats| stats.size)
+                    .sum();
+                (state.max_segment_size, failed_segments_size)
+            });
+        live_bytes * 100 / (100 - COMPACTION_THRESHOLD_PERCENT)
+            + max_segment_size
+            + failed_segments_size
+    }
+
+    /// Entries carry the time the record was first written, kept when copied over by a
+    /// compaction.
+    fn stored_at(&self, key: &Key) -> io::Result<SystemTime> {
+        let state = self.lock_state()?;
+        match state.index.get(key) {
+            Some(location) => Ok(UNIX_EPOCH + Duration::from_millis(location.stored_at)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "record not found in segments",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::NetworkAddress;
+
+    fn temp_storage_dir() -> PathBuf {
+        let storage_dir = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        fs::create_dir_all(&storage_dir).expect("Failed to create directory");
+        storage_dir
+    }
+
+    fn random_key() -> Key {
+        NetworkAddress::from_peer(libp2p::PeerId::random()).to_record_key()
+    }
+
+    #[test]
+    fn segmented_write_read_remove_and_reopen() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let backend = SegmentedBackend::open(storage_dir.clone(), MAX_SEGMENT_SIZE);
+
+        let kept = random_key();
+        let overwritten = random_key();
+        let removed = random_key();
+
+        backend.write(&kept, b"kept")?;
+        backend.write(&overwritten, b"first")?;
+        backend.write(&overwritten, b"second")?;
+        backend.write(&removed, b"removed")?;
+        backend.remove(&removed)?;
+
+        assert_eq!(backend.read(&kept)?, b"kept");
+        assert_eq!(backend.read(&overwritten)?, b"second");
+        assert!(backend.read(&removed).is_err());
+
+        // Reopening replays the segments to the same state.
+        drop(backend);
+        let backend = SegmentedBackend::open(storage_dir, MAX_SEGMENT_SIZE);
+        assert_eq!(backend.keys().len(), 2);
+        assert_eq!(backend.read(&kept)?, b"kept");
+        assert_eq!(backend.read(&overwritten)?, b"second");
+        assert!(backend.read(&removed).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn segmented_truncates_partial_trailing_entry() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let key = random_key();
+        {
+            let backend = SegmentedBackend::open(storage_dir.clone(), MAX_SEGMENT_SIZE);
+            backend.write(&key, b"value")?;
+        }
+
+        // Simulate a crash in the middle of writing the next entry.
+        let segment_path = storage_dir.join(format!("{SEGMENT_FILENAME_PREFIX}0"));
+        let mut file = fs::OpenOptions::new().append(true).open(&segment_path)?;
+        file.write_all(&[ENTRY_KIND_PUT, 32, 0])?;
+        drop(file);
+
+        let backend = SegmentedBackend::open(storage_dir, MAX_SEGMENT_SIZE);
+        assert_eq!(backend.read(&key)?, b"value");
+
+        let another_key = random_key();
+        backend.write(&another_key, b"another")?;
+        assert_eq!(backend.read(&another_key)?, b"another");
+
+        Ok(())
+    }
+
+    #[test]
+    fn segmented_compacts_sealed_segments() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let backend = SegmentedBackend::open(storage_dir.clone(), MAX_SEGMENT_SIZE);
+
+        // Fill the first segment, so the following writes go into a new one.
+        let value = vec![7u8; 1024 * 1024];
+        let keys: Vec<Key> = (0..(MAX_SEGMENT_SIZE / value.len() as u64))
+            .map(|_| random_key())
+            .collect();
+        for key in &keys {
+            backend.write(key, &value)?;
+        }
+        assert!(storage_dir
+            .join(format!("{SEGMENT_FILENAME_PREFIX}1"))
+            .exists());
+
+        // Removing most of the first segment's records triggers its compaction.
+        let (removed, kept) = keys.split_at(keys.len() * 3 / 4);
+        for key in removed {
+            backend.remove(key)?;
+        }
+        assert!(!storage_dir
+            .join(format!("{SEGMENT_FILENAME_PREFIX}0"))
+            .exists());
+
+        for key in kept {
+            assert_eq!(backend.read(key)?, value);
+        }
+
+        // Removed records must not be resurrected on reopen.
+        drop(backend);
+        let backend = SegmentedBackend::open(storage_dir, MAX_SEGMENT_SIZE);
+        assert_eq!(backend.keys().len(), kept.len());
+        for key in removed {
+            assert!(backend.read(key).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn segmented_compaction_keeps_segment_failing_to_be_read() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let backend = SegmentedBackend::open(storage_dir.clone(), MAX_SEGMENT_SIZE);
+        let (first, second) = (random_key(), random_key());
+        backend.write(&first, b"first")?;
+        backend.write(&second, b"second")?;
+
+        // The segment gets damaged after being sealed, cutting the value of the second record.
+        let segment_path = storage_dir.join(format!("{SEGMENT_FILENAME_PREFIX}0"));
+        let segment = fs::read(&segment_path)?;
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&segment_path)?
+            .set_len(segment.len() as u64 - 2)?;
+
+        let mut state = backend.lock_state()?;
+        state.active_segment = 1;
+        assert!(state.compact(0).is_err());
+        drop(state);
+
+        assert!(segment_path.exists());
+        assert_eq!(backend.read(&first)?, b"first");
+        assert_eq!(backend.keys().len(), 2);
+
+        // Once failed, the segment is not compacted again on every touch, even if it could be.
+        let mut state = backend.lock_state()?;
+        state.mark_dead(0, segment.len() as u64);
+        state.compact_if_needed(0);
+        assert!(state.segments[&0].compaction_failed);
+        fs::write(&segment_path, &segment)?;
+        state.compact_if_needed(0);
+        drop(state);
+        assert!(segment_path.exists());
+        assert!(backend.max_disk_usage(0) >= segment.len() as u64);
+
+        Ok(())
+    }
+
+    #[test]
+    fn disk_usage_stays_within_the_reported_bound() -> eyre::Result<()> {
+        let backends: Vec<(PathBuf, Box<dyn RecordStorageBackend>)> = vec![
+            {
+                let storage_dir = temp_storage_dir();
+                let backend = FlatFileBackend::new(storage_dir.clone());
+                (storage_dir, Box::new(backend))
+            },
+            {
+                let storage_dir = temp_storage_dir();
+                let backend = SegmentedBackend::open(storage_dir.clone(), 16 * 1024);
+                (storage_dir, Box::new(backend))
+            },
+        ];
+
+        for (storage_dir, backend) in backends {
+            let keys: Vec<Key> = (0..20).map(|_| random_key()).collect();
+            let mut live = HashMap::new();
+            // Overwrite and remove records over and over, leaving dead bytes behind.
+            for round in 0..10 {
+                for (i, key) in keys.iter().enumerate() {
+                    if (i + round) % 3 == 0 && live.contains_key(key) {
+                        backend.remove(key)?;
+                        let _ = live.remove(key);
+                    } else {
+                        let value = vec![round as u8; 100 + 37 * i];
+                        backend.write(key, &value)?;
+                        let _ = live.insert(key.clone(), backend.size_on_disk(key, value.len()));
+                    }
+
+                    let disk_usage: u64 = fs::read_dir(&storage_dir)?
+                        .map(|entry| Ok(entry?.metadata()?.len()))
+                        .sum::<io::Result<u64>>()?;
+                    let bound = backend.max_disk_usage(live.values().sum());
+                    assert!(
+                        disk_usage <= bound,
+                        "disk usage {disk_usage} exceeds the bound {bound}"
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stored_at_is_kept_when_rewritten_compacted_or_reopened() -> eyre::Result<()> {
+        let flat_file_dir = temp_storage_dir();
+        let segmented_dir = temp_storage_dir();
+        let reopen = |storage_dir: &PathBuf, segmented: bool| -> Box<dyn RecordStorageBackend> {
+            if segmented {
+                Box::new(SegmentedBackend::open(storage_dir.clone(), 1024))
+            } else {
+                Box::new(FlatFileBackend::new(storage_dir.clone()))
+            }
+        };
+
+        for (storage_dir, segmented) in [(flat_file_dir, false), (segmented_dir, true)] {
+            let backend = reopen(&storage_dir, segmented);
+            let key = random_key();
+            backend.write(&key, b"first")?;
+            let stored_at = backend.stored_at(&key)?;
+
+            std::thread::sleep(Duration::from_millis(20));
+            // Rewriting the record, and writing others filling up segments to be compacted.
+            backend.write(&key, b"second")?;
+            for _ in 0..10 {
+                let other = random_key();
+                backend.write(&other, &[0; 300])?;
+                backend.remove(&other)?;
+            }
+            assert_eq!(backend.read(&key)?, b"second");
+            assert_eq!(backend.stored_at(&key)?, stored_at);
+
+            drop(backend);
+            let backend = reopen(&storage_dir, segmented);
+            assert_eq!(backend.stored_at(&key)?, stored_at);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn flat_file_lists_written_keys() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let backend = FlatFileBackend::new(storage_dir.clone());
+        let key = random_key();
+        backend.write(&key, b"old value")?;
+        backend.write(&key, b"value")?;
+
+        // Overwritten in place, no temporary file left behind.
+        assert_eq!(fs::read_dir(&storage_dir)?.count(), 1);
+        assert_eq!(backend.keys(), vec![key.clone()]);
+        assert_eq!(backend.read(&key)?, b"value");
+        backend.remove(&key)?;
+        assert!(backend.keys().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn flat_file_removes_temporary_files_left_over() -> eyre::Result<()> {
+        let storage_dir = temp_storage_dir();
+        let backend = FlatFileBackend::new(storage_dir.clone());
+        let key = random_key();
+        backend.write(&key, b"value")?;
+        let leftover = storage_dir.join(format!("{}.3.tmp", generate_filename(&key)));
+        fs::write(&leftover, b"half written")?;
+
+        assert_eq!(backend.keys(), vec![key.clone()]);
+        assert!(!leftover.exists());
+        assert_eq!(backend.read(&key)?, b"value");
+
+        Ok(())
+    }
+}
@@ -13,8 +13,11 @@ use libp2p::{
     kad::{KBucketDistance as Distance, RecordKey, K_VALUE},
     PeerId,
 };
-use sn_protocol::{storage::RecordType, NetworkAddress, PrettyPrintRecordKey};
+use sn_protocol::{
+    messages::ReplicationSketch, storage::RecordType, NetworkAddress, PrettyPrintRecordKey,
+};
 use std::collections::{hash_map::Entry, BTreeSet, HashMap};
+use strum::{Display, EnumString};
 use tokio::{sync::mpsc, time::Duration};
 
 // Max parallel fetches that can be undertaken at the same time.
@@ -33,6 +36,18 @@ const PENDING_TIMEOUT: Duration = Duration::from_secs(900);
 // The time the entry will be considered as `time out` and to be cleared.
 type ReplicationTimeout = Instant;
 
+/// How a node tells its close peers about the records it holds during periodic replication.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum ReplicationMode {
+    /// Send the full list of held keys, peers fetch the ones they are missing.
+    #[default]
+    FullKeyList,
+    /// Send a `ReplicationSketch` of the held keys, peers reply with the keys missing from it.
+    /// Peers running a version without sketch support will not understand it.
+    Sketch,
+}
+
 #[derive(Debug)]
 pub(crate) struct ReplicationFetcher {
     self_peer_id: PeerId,
@@ -375,12 +390,31 @@ impl ReplicationFetcher {
     }
 }
 
+/// Select, among the locally held keys, those missing from the `sketch` of the `holder` and
+/// within the holder's `distance_range`. These are to be sent back to the holder as a
+/// replication list, for its `ReplicationFetcher` to fetch them from us.
+pub(crate) fn keys_missing_from_sketch<'a>(
+    holder: &NetworkAddress,
+    sketch: &ReplicationSketch,
+    distance_range: Option<u32>,
+    local_keys: impl Iterator<Item = &'a (NetworkAddress, RecordType)>,
+) -> Vec<(NetworkAddress, RecordType)> {
+    local_keys
+        .filter(|(addr, _record_type)| match distance_range {
+            Some(distance_range) => holder.distance(addr).ilog2().unwrap_or(0) <= distance_range,
+            None => true,
+        })
+        .filter(|(addr, record_type)| !sketch.contains(addr, record_type))
+        .cloned()
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
+    use super::{keys_missing_from_sketch, ReplicationFetcher, FETCH_TIMEOUT, MAX_PARALLEL_FETCH};
     use eyre::Result;
     use libp2p::{kad::RecordKey, PeerId};
-    use sn_protocol::{storage::RecordType, NetworkAddress};
+    use sn_protocol::{messages::ReplicationSketch, storage::RecordType, NetworkAddress};
     use std::{collections::HashMap, time::Duration};
     use tokio::{sync::mpsc, time::sleep};
 
@@ -475,4 +509,65 @@ mod tests {
             keys_to_fetch.len() + replication_fetcher.to_be_fetched.len()
         );
     }
+
+    #[test]
+    fn sketch_reconciliation_feeds_missing_keys_to_fetcher() {
+        let holder_peer_id = PeerId::random();
+        let holder = NetworkAddress::from_peer(holder_peer_id);
+        let (event_sender, _event_receiver) = mpsc::channel(4);
+        let mut holder_fetcher = ReplicationFetcher::new(holder_peer_id, event_sender);
+
+        // We hold 1000 keys, of which the holder already holds the first 900.
+        let our_keys: Vec<_> = (0..1000)
+            .map(|_| {
+                let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+                (
+                    NetworkAddress::from_record_key(&RecordKey::from(random_data)),
+                    RecordType::Chunk,
+                )
+            })
+            .collect();
+        let (held_keys, missing_keys) = our_keys.split_at(900);
+        let sketch = ReplicationSketch::from_keys(held_keys.iter(), rand::random());
+
+        let diff = keys_missing_from_sketch(&holder, &sketch, None, our_keys.iter());
+        // No false negatives: nothing the holder has is sent back.
+        assert!(diff.iter().all(|key| !held_keys.contains(key)));
+        // False positives only hide a few of the missing keys.
+        assert!(diff.len() > missing_keys.len() * 9 / 10);
+
+        let keys_to_fetch =
+            holder_fetcher.add_keys(PeerId::random(), diff.clone(), &Default::default());
+        assert_eq!(
+            diff.len(),
+            keys_to_fetch.len() + holder_fetcher.to_be_fetched.len()
+        );
+    }
+
+    #[test]
+    fn sketch_reconciliation_respects_holder_range() {
+        let holder = NetworkAddress::from_peer(PeerId::random());
+        let distance_target = NetworkAddress::from_peer(PeerId::random());
+        let distance_range = holder.distance(&distance_target).ilog2().unwrap_or(1);
+
+        let our_keys: Vec<_> = (0..100)
+            .map(|_| {
+                let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+                (
+                    NetworkAddress::from_record_key(&RecordKey::from(random_data)),
+                    RecordType::Chunk,
+                )
+            })
+            .collect();
+        let in_range_keys = our_keys
+            .iter()
+            .filter(|(addr, _)| holder.distance(addr).ilog2().unwrap_or(0) <= distance_range)
+            .count();
+
+        // An empty sketch, the holder is missing everything.
+        let sketch = ReplicationSketch::from_keys([].iter(), 0);
+        let diff =
+            keys_missing_from_sketch(&holder, &sketch, Some(distance_range), our_keys.iter());
+        assert_eq!(diff.len(), in_range_keys);
+    }
 }
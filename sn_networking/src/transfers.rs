@@ -7,7 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    close_group_majority, driver::GetRecordCfg, GetRecordError, Network, NetworkError, Result,
+    close_group_majority, driver::GetRecordCfg, GetRecordError, Network, NetworkError,
+    RecordFetchStrategy, Result,
 };
 use libp2p::kad::{Quorum, Record};
 use sn_protocol::{
@@ -35,6 +36,7 @@ impl Network {
             retry_strategy: None,
             target_record: None,
             expected_holders: Default::default(),
+            fetch_strategy: RecordFetchStrategy::Kademlia,
         };
         let record = self.get_record_from_network(key.clone(), &get_cfg).await?;
         debug!(
@@ -55,6 +57,7 @@ impl Network {
             retry_strategy: Some(RetryStrategy::Quick),
             target_record: None,
             expected_holders: Default::default(),
+            fetch_strategy: RecordFetchStrategy::Kademlia,
         };
         let record = match self.get_record_from_network(key.clone(), &get_cfg).await {
             Ok(record) => record,
@@ -17,6 +17,9 @@ use libp2p::{identity::Keypair, PeerId};
 #[cfg(feature = "metrics")]
 use sn_logging::metrics::init_metrics;
 use sn_logging::{Level, LogFormat, LogOutputDest, ReloadHandle};
+use sn_networking::{
+    RecordStorageBackendKind, ReplicationMode, DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL,
+};
 use sn_node::{Marker, NodeBuilder, NodeEvent, NodeEventsReceiver};
 use sn_peers_acquisition::{get_peers_from_args, PeersArgs};
 use sn_protocol::{node::get_safenode_root_dir, node_rpc::NodeCtrl};
@@ -35,6 +38,9 @@ use tokio::{
 };
 use tracing_appender::non_blocking::WorkerGuard;
 
+/// Environment variable that sets the storage quota, when `--max-storage-bytes` is not provided.
+const MAX_STORAGE_BYTES_ENV: &str = "SN_MAX_STORAGE_BYTES";
+
 #[derive(Debug, Clone)]
 pub enum LogOutputDestArg {
     Stdout,
@@ -159,6 +165,40 @@ struct Opt {
     #[clap(long)]
     owner: Option<String>,
 
+    /// Specify how records are laid out on disk.
+    ///
+    /// Valid values are "flat-file" (one file per record) or "segmented" (records packed into
+    /// append-only segment files, which avoids the per-file overhead when running many nodes).
+    ///
+    /// Changing this for an existing node will not migrate the records already stored.
+    #[clap(long, default_value_t = RecordStorageBackendKind::FlatFile, verbatim_doc_comment)]
+    record_storage_backend: RecordStorageBackendKind,
+
+    /// Specify the maximum number of bytes the node will use to store records.
+    ///
+    /// Once reached, the records farthest from the node are pruned to make room for closer ones,
+    /// and the store cost rises as the quota fills up. The quotes then carry the bytes stored,
+    /// which clients and nodes predating the quota cannot verify.
+    ///
+    /// If not provided, the value of the `SN_MAX_STORAGE_BYTES` environment variable is used, if set.
+    #[clap(long, verbatim_doc_comment)]
+    max_storage_bytes: Option<u64>,
+
+    /// Specify how the held records are advertised to close peers during periodic replication.
+    ///
+    /// Valid values are "full-key-list" (send every held key) or "sketch" (send a compact sketch
+    /// of the held keys, peers reply with the keys missing from it).
+    ///
+    /// Only use "sketch" once all the close peers run a version that supports it.
+    #[clap(long, default_value_t = ReplicationMode::FullKeyList, verbatim_doc_comment)]
+    replication_mode: ReplicationMode,
+
+    /// Specify how many stored records are re-verified against their address every minute.
+    ///
+    /// Corrupt records are dropped and fetched again from the close peers. Zero disables it.
+    #[clap(long, default_value_t = DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL, verbatim_doc_comment)]
+    record_scrub_rate: usize,
+
     #[cfg(feature = "open-metrics")]
     /// Specify the port for the OpenMetrics server.
     ///
@@ -210,6 +250,16 @@ fn main() -> Result<()> {
     #[cfg(feature = "metrics")]
     rt.spawn(init_metrics(std::process::id()));
     debug!("Node's owner set to: {:?}", opt.owner);
+    let max_storage_bytes = match opt.max_storage_bytes {
+        Some(max_storage_bytes) => Some(max_storage_bytes),
+        None => match env::var(MAX_STORAGE_BYTES_ENV) {
+            Ok(value) => Some(value.parse().map_err(|err| {
+                eyre!("Failed to parse {MAX_STORAGE_BYTES_ENV} value {value:?}: {err}")
+            })?),
+            Err(_) => None,
+        },
+    };
+    info!("Node's max storage bytes set to: {max_storage_bytes:?}");
     let restart_options = rt.block_on(async move {
         let mut node_builder = NodeBuilder::new(
             keypair,
@@ -222,6 +272,10 @@ fn main() -> Result<()> {
             opt.upnp,
         );
         node_builder.is_behind_home_network = opt.home_network;
+        node_builder.record_storage_backend(opt.record_storage_backend);
+        node_builder.max_storage_bytes(max_storage_bytes);
+        node_builder.replication_mode(opt.replication_mode);
+        node_builder.record_scrub_rate(opt.record_scrub_rate);
         #[cfg(feature = "open-metrics")]
         let mut node_builder = node_builder;
         // if enable flag is provided or only if the port is specified then enable the server by setting Some()
@@ -63,6 +63,8 @@ pub enum Marker<'a> {
     ValidPaidRegisterPutFromClient(&'a PrettyPrintRecordKey<'a>),
     /// Valid spend stored
     ValidSpendPutFromClient(&'a PrettyPrintRecordKey<'a>),
+    /// Valid Register ops, not requiring a payment, applied to a stored register
+    ValidRegisterOpsPutFromClient(&'a PrettyPrintRecordKey<'a>),
 
     /// Record rejected
     RecordRejected(&'a PrettyPrintRecordKey<'a>, &'a Error),
@@ -24,11 +24,15 @@ use prometheus_client::registry::Registry;
 use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use sn_networking::{
     close_group_majority, Network, NetworkBuilder, NetworkError, NetworkEvent, NodeIssue,
-    SwarmDriver, CLOSE_GROUP_SIZE,
+    RecordStorageBackendKind, ReplicationMode, SwarmDriver, CLOSE_GROUP_SIZE,
+    DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL,
 };
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::{ChunkProof, Cmd, CmdResponse, Query, QueryResponse, Request, Response},
+    messages::{
+        ChunkProof, Cmd, CmdResponse, Query, QueryResponse, Request, Response,
+        MAX_STORE_COSTS_BATCH_SIZE,
+    },
     NetworkAddress, PrettyPrintRecordKey,
 };
 use sn_transfers::{HotWallet, MainPubkey, MainSecretKey, NanoTokens, PAYMENT_FORWARD_PK};
@@ -83,6 +87,10 @@ pub struct NodeBuilder {
     owner: Option<String>,
     #[cfg(feature = "upnp")]
     upnp: bool,
+    record_storage_backend: RecordStorageBackendKind,
+    max_storage_bytes: Option<u64>,
+    replication_mode: ReplicationMode,
+    record_scrub_rate: usize,
 }
 
 impl NodeBuilder {
@@ -108,9 +116,34 @@ impl NodeBuilder {
             owner,
             #[cfg(feature = "upnp")]
             upnp,
+            record_storage_backend: Default::default(),
+            max_storage_bytes: None,
+            replication_mode: Default::default(),
+            record_scrub_rate: DEFAULT_RECORDS_SCRUBBED_PER_INTERVAL,
         }
     }
 
+    /// Set the on-disk layout used to store records. Defaults to one file per record.
+    pub fn record_storage_backend(&mut self, record_storage_backend: RecordStorageBackendKind) {
+        self.record_storage_backend = record_storage_backend;
+    }
+
+    /// Set the maximum number of bytes the stored records may occupy. Unlimited if not set.
+    pub fn max_storage_bytes(&mut self, max_storage_bytes: Option<u64>) {
+        self.max_storage_bytes = max_storage_bytes;
+    }
+
+    /// Set how the held records are advertised to close peers. Defaults to the full key list.
+    pub fn replication_mode(&mut self, replication_mode: ReplicationMode) {
+        self.replication_mode = replication_mode;
+    }
+
+    /// Set how many stored records get re-verified against their address every minute.
+    /// Zero disables the integrity scrubbing.
+    pub fn record_scrub_rate(&mut self, records_per_minute: usize) {
+        self.record_scrub_rate = records_per_minute;
+    }
+
     #[cfg(feature = "open-metrics")]
     /// Set the port for the OpenMetrics server. Defaults to a random port if not set
     pub fn metrics_server_port(&mut self, port: Option<u16>) {
@@ -162,6 +195,10 @@ impl NodeBuilder {
         network_builder.metrics_server_port(self.metrics_server_port);
         network_builder.initial_peers(self.initial_peers.clone());
         network_builder.is_behind_home_network(self.is_behind_home_network);
+        network_builder.record_storage_backend(self.record_storage_backend);
+        network_builder.max_storage_bytes(self.max_storage_bytes);
+        network_builder.replication_mode(self.replication_mode);
+        network_builder.record_scrub_rate(self.record_scrub_rate);
 
         #[cfg(feature = "upnp")]
         network_builder.upnp(self.upnp);
@@ -174,7 +211,6 @@ impl NodeBuilder {
             network: network.clone(),
             events_channel: node_events_channel.clone(),
             node_cmds: node_cmds.clone(),
-            initial_peers: Arc::new(self.initial_peers),
             reward_address: Arc::new(reward_address),
             #[cfg(feature = "open-metrics")]
             node_metrics,
@@ -206,8 +242,6 @@ pub(crate) struct Node {
     pub(crate) events_channel: NodeEventsChannel,
     // We keep a copy of the Sender which is clonable and we can obtain a receiver from.
     node_cmds: broadcast::Sender<NodeCmd>,
-    // Peers that are dialed at startup of node.
-    initial_peers: Arc<Vec<Multiaddr>>,
     reward_address: Arc<MainPubkey>,
     #[cfg(feature = "open-metrics")]
     pub(crate) node_metrics: Option<NodeMetrics>,
@@ -436,18 +470,9 @@ impl Node {
                 });
             }
             NetworkEvent::NewListenAddr(_) => {
+                // The initial peers are dialed by the network once listening, unless the node
+                // rejoins from the peers it has cached.
                 event_header = "NewListenAddr";
-                if !cfg!(feature = "local-discovery") {
-                    let network = self.network.clone();
-                    let peers = self.initial_peers.clone();
-                    let _handle = spawn(async move {
-                        for addr in &*peers {
-                            if let Err(err) = network.dial(addr.clone()).await {
-                                tracing::error!("Failed to dial {addr}: {err:?}");
-                            };
-                        }
-                    });
-                }
             }
             NetworkEvent::ResponseReceived { res } => {
                 event_header = "ResponseReceived";
@@ -683,6 +708,69 @@ impl Node {
                     },
                 }
             }
+            Query::GetStoreCosts(mut addresses) => {
+                trace!(
+                    "Got GetStoreCosts request for {} addresses",
+                    addresses.len()
+                );
+                let self_id = *network.peer_id;
+                if addresses.len() > MAX_STORE_COSTS_BATCH_SIZE {
+                    warn!(
+                        "GetStoreCosts request of {} addresses exceeds the batch size, only quoting the first {MAX_STORE_COSTS_BATCH_SIZE}",
+                        addresses.len()
+                    );
+                    addresses.truncate(MAX_STORE_COSTS_BATCH_SIZE);
+                }
+                // Only the addresses this node is close to are quoted, as it could not be paid
+                // for the others.
+                let mut not_close = vec![];
+                let mut close = Vec::with_capacity(addresses.len());
+                for address in addresses {
+                    if Self::is_in_close_group(network, &address).await {
+                        close.push(address);
+                    } else {
+                        not_close.push(address);
+                    }
+                }
+                let addresses = close;
+                let record_keys = addresses
+                    .iter()
+                    .map(|address| address.to_record_key())
+                    .collect();
+
+                let mut quotes = match network.get_local_storecosts(record_keys).await {
+                    Ok(store_costs) => Self::create_quotes_for_storecosts(
+                        network,
+                        addresses
+                            .into_iter()
+                            .zip(store_costs)
+                            .map(|(address, (cost, quoting_metrics))| {
+                                (address, cost, quoting_metrics)
+                            })
+                            .collect(),
+                    ),
+                    Err(_) => addresses
+                        .into_iter()
+                        .map(|address| (address, Err(ProtocolError::GetStoreCostFailed)))
+                        .collect(),
+                };
+                if !not_close.is_empty() {
+                    debug!(
+                        "Not quoting {} addresses of a GetStoreCosts request, as not among their close group",
+                        not_close.len()
+                    );
+                }
+                quotes.extend(not_close.into_iter().map(|address| {
+                    let err = ProtocolError::NotInCloseGroup(address.clone());
+                    (address, Err(err))
+                }));
+
+                QueryResponse::GetStoreCosts {
+                    quotes,
+                    payment_address,
+                    peer_address: NetworkAddress::from_peer(self_id),
+                }
+            }
             Query::GetReplicatedRecord { requester, key } => {
                 trace!("Got GetReplicatedRecord from {requester:?} regarding {key:?}");
 
@@ -717,6 +805,24 @@ impl Node {
 
                 QueryResponse::GetChunkExistenceProof(result)
             }
+            Query::GetRecordMetadata { key } => {
+                trace!("Got GetRecordMetadata for {key:?}");
+
+                let our_address = NetworkAddress::from_peer(*network.peer_id);
+                let mut result = Err(ProtocolError::ReplicatedRecordNotFound {
+                    holder: Box::new(our_address.clone()),
+                    key: Box::new(key.clone()),
+                });
+
+                if let Ok(Some(metadata)) = network
+                    .get_local_record_metadata(&key.to_record_key())
+                    .await
+                {
+                    result = Ok((our_address, metadata));
+                }
+
+                QueryResponse::GetRecordMetadata(result)
+            }
             Query::CheckNodeInProblem(target_address) => {
                 trace!("Got CheckNodeInProblem for peer {target_address:?}");
 
@@ -17,7 +17,7 @@ use sn_protocol::{
     },
     NetworkAddress, PrettyPrintRecordKey,
 };
-use sn_registers::SignedRegister;
+use sn_registers::{RegisterAddress, RegisterOp, SignedRegister};
 use sn_transfers::{
     calculate_royalties_fee, CashNote, CashNoteRedemption, HotWallet, NanoTokens, Payment,
     SignedSpend, Transfer, UniquePubkey, WalletError, NETWORK_ROYALTIES_PK,
@@ -158,6 +158,31 @@ impl Node {
 
                 self.validate_and_store_register(register, true).await
             }
+            RecordKind::RegisterOps => {
+                let (address, ops) =
+                    try_deserialize_record::<(RegisterAddress, BTreeSet<RegisterOp>)>(&record)?;
+
+                // the ops can only be applied to a register we already have
+                let net_addr = NetworkAddress::from_register_address(address);
+                let key = net_addr.to_record_key();
+                let pretty_key = PrettyPrintRecordKey::from(&key);
+                if !self
+                    .validate_key_and_existence(&net_addr, &record.key)
+                    .await?
+                {
+                    trace!("Ignore ops for register at {pretty_key:?} we don't have");
+                    return Err(Error::InvalidPutWithoutPayment(
+                        PrettyPrintRecordKey::from(&record.key).into_owned(),
+                    ));
+                }
+
+                let result = self.validate_and_store_register_ops(address, ops).await;
+                if result.is_ok() {
+                    Marker::ValidRegisterOpsPutFromClient(&pretty_key).log();
+                    // as for a Register update, keeping the replicas in sync is left up to the client
+                }
+                result
+            }
         }
     }
 
@@ -173,6 +198,14 @@ impl Node {
                     PrettyPrintRecordKey::from(&record.key).into_owned(),
                 ))
             }
+            // Only clients send Register ops, the full Register being replicated
+            RecordKind::RegisterOps => {
+                warn!("Prepaid record came with Register ops, which are never replicated");
+                Err(Error::InvalidRequest(format!(
+                    "Register ops at {:?} are not to be replicated",
+                    PrettyPrintRecordKey::from(&record.key)
+                )))
+            }
             RecordKind::Chunk => {
                 let chunk = try_deserialize_record::<Chunk>(&record)?;
 
@@ -288,7 +321,6 @@ impl Node {
         // check if the Register is present locally
         let key = NetworkAddress::from_register_address(*reg_addr).to_record_key();
         let present_locally = self.network.is_record_key_present_locally(&key).await?;
-        let pretty_key = PrettyPrintRecordKey::from(&key);
 
         // check register and merge if needed
         let updated_register = match self.register_validation(&register, present_locally).await? {
@@ -300,10 +332,61 @@ impl Node {
             }
         };
 
+        self.store_register(&updated_register, with_payment)
+    }
+
+    /// Validate and apply `RegisterOp`s sent as a delta over the `Register` we hold, storing the
+    /// updated `Register` to the RecordStore.
+    /// The ops are refused if any of them was written atop an entry we don't hold, in which case
+    /// the client is to send the whole `Register` to be merged instead.
+    pub(crate) async fn validate_and_store_register_ops(
+        &self,
+        reg_addr: RegisterAddress,
+        ops: BTreeSet<RegisterOp>,
+    ) -> Result<CmdOk> {
+        debug!(
+            "Validating and storing {} ops for register {reg_addr:?}",
+            ops.len()
+        );
+        let key = NetworkAddress::from_register_address(reg_addr).to_record_key();
+        let local_register = match self.network.get_local_record(&key).await? {
+            Some(record) => try_deserialize_record::<SignedRegister>(&record)?,
+            None => {
+                error!("Register with addr {reg_addr:?} already exists locally, but not found in local storage");
+                return Err(Error::InvalidRequest(format!(
+                    "Register with addr {reg_addr:?} claimed to be existing locally was not found"
+                )));
+            }
+        };
+
+        let mut updated_register = local_register.clone();
+        if let Err(err) = updated_register.add_delta_ops(ops) {
+            warn!("Refused ops for Register with addr {reg_addr:?}: {err:?}");
+            return Err(err.into());
+        }
+        // the ops we didn't have yet which were written by a revoked writer are not stored
+        let refused = updated_register.remove_unpermitted_ops(Some(&local_register))?;
+        if refused > 0 {
+            warn!("Refused {refused} ops of revoked writers for Register with addr {reg_addr:?}");
+        }
+        if updated_register == local_register {
+            trace!("Ops for Register with addr {reg_addr:?} were already applied");
+            return Ok(CmdOk::DataAlreadyPresent);
+        }
+
+        self.store_register(&updated_register, false)
+    }
+
+    /// Store a validated `Register` to the RecordStore, replicating it if it was paid for.
+    fn store_register(&self, register: &SignedRegister, with_payment: bool) -> Result<CmdOk> {
+        let reg_addr = register.address();
+        let key = NetworkAddress::from_register_address(*reg_addr).to_record_key();
+        let pretty_key = PrettyPrintRecordKey::from(&key);
+
         // store in kad
         let record = Record {
             key: key.clone(),
-            value: try_serialize_record(&updated_register, RecordKind::Register)?.to_vec(),
+            value: try_serialize_record(register, RecordKind::Register)?.to_vec(),
             publisher: None,
             expires: None,
         };
@@ -552,14 +635,22 @@ impl Node {
         register: &SignedRegister,
         present_locally: bool,
     ) -> Result<Option<SignedRegister>> {
-        // check if register is valid
+        // check if register is valid, including its permissions changes and checkpoint,
+        // which must all be signed by its owner
         let reg_addr = register.address();
         register.verify()?;
 
-        // if we don't have it locally return it
+        // if we don't have it locally return it, without the ops of any revoked writer
         if !present_locally {
             debug!("Register with addr {reg_addr:?} is valid and doesn't exist locally");
-            return Ok(Some(register.to_owned()));
+            let mut register = register.to_owned();
+            let refused = register.remove_unpermitted_ops(None)?;
+            if refused > 0 {
+                warn!(
+                    "Refused {refused} ops of revoked writers for Register with addr {reg_addr:?}"
+                );
+            }
+            return Ok(Some(register));
         }
         trace!("Register with addr {reg_addr:?} exists locally, comparing with local version");
 
@@ -581,6 +672,11 @@ impl Node {
         // merge the two registers
         let mut merged_register = local_register.clone();
         merged_register.verified_merge(register)?;
+        // the ops we didn't have yet which were written by a revoked writer are not stored
+        let refused = merged_register.remove_unpermitted_ops(Some(&local_register))?;
+        if refused > 0 {
+            warn!("Refused {refused} ops of revoked writers for Register with addr {reg_addr:?}");
+        }
         if merged_register == local_register {
             trace!("Register with addr {reg_addr:?} is the same as the local version");
             Ok(None)
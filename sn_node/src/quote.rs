@@ -8,10 +8,12 @@
 
 use crate::{node::Node, Error, Result};
 use libp2p::PeerId;
-use sn_networking::{calculate_cost_for_records, Network, NodeIssue};
-use sn_protocol::{error::Error as ProtocolError, storage::ChunkAddress, NetworkAddress};
+use sn_networking::{calculate_cost_for_records, Network, NodeIssue, CLOSE_GROUP_SIZE};
+use sn_protocol::{
+    error::Error as ProtocolError, storage::ChunkAddress, NetworkAddress, PrettyPrintRecordKey,
+};
 use sn_transfers::{NanoTokens, PaymentQuote, QuotingMetrics};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 impl Node {
     pub(crate) fn create_quote_for_storecost(
@@ -20,8 +22,71 @@ impl Node {
         address: &NetworkAddress,
         quoting_metrics: &QuotingMetrics,
     ) -> Result<PaymentQuote, ProtocolError> {
-        let content = address.as_xorname().unwrap_or_default();
         let timestamp = std::time::SystemTime::now();
+        let pub_key = network.get_pub_key();
+        Self::sign_quote(network, cost, address, quoting_metrics, timestamp, &pub_key)
+    }
+
+    /// Create the quotes of a `Query::GetStoreCosts` batch in one go, all sharing the same
+    /// timestamp. An address already stored gets a `RecordExists` error instead of a quote.
+    pub(crate) fn create_quotes_for_storecosts(
+        network: &Network,
+        store_costs: Vec<(NetworkAddress, NanoTokens, QuotingMetrics)>,
+    ) -> Vec<(NetworkAddress, Result<PaymentQuote, ProtocolError>)> {
+        let timestamp = std::time::SystemTime::now();
+        let pub_key = network.get_pub_key();
+
+        store_costs
+            .into_iter()
+            .map(|(address, cost, quoting_metrics)| {
+                let quote = if cost == NanoTokens::zero() {
+                    Err(ProtocolError::RecordExists(
+                        PrettyPrintRecordKey::from(&address.to_record_key()).into_owned(),
+                    ))
+                } else {
+                    Self::sign_quote(
+                        network,
+                        cost,
+                        &address,
+                        &quoting_metrics,
+                        timestamp,
+                        &pub_key,
+                    )
+                };
+                (address, quote)
+            })
+            .collect()
+    }
+
+    /// Whether this node is among the close group of the address, as far as its routing table
+    /// knows. Only then could it be paid to store the record at that address.
+    pub(crate) async fn is_in_close_group(network: &Network, address: &NetworkAddress) -> bool {
+        let close_peers = match network.get_close_group_local_peers(address).await {
+            Ok(close_peers) => close_peers,
+            Err(err) => {
+                warn!("Failed to get the local close group of {address:?}: {err:?}");
+                return false;
+            }
+        };
+        if close_peers.len() < CLOSE_GROUP_SIZE {
+            return true;
+        }
+
+        let self_distance = NetworkAddress::from_peer(*network.peer_id).distance(address);
+        close_peers.last().is_some_and(|farthest| {
+            self_distance < NetworkAddress::from_peer(*farthest).distance(address)
+        })
+    }
+
+    fn sign_quote(
+        network: &Network,
+        cost: NanoTokens,
+        address: &NetworkAddress,
+        quoting_metrics: &QuotingMetrics,
+        timestamp: SystemTime,
+        pub_key: &[u8],
+    ) -> Result<PaymentQuote, ProtocolError> {
+        let content = address.as_xorname().unwrap_or_default();
         let bytes = PaymentQuote::bytes_for_signing(content, cost, timestamp, quoting_metrics);
 
         let Ok(signature) = network.sign(&bytes) else {
@@ -33,7 +98,7 @@ impl Node {
             cost,
             timestamp,
             quoting_metrics: quoting_metrics.clone(),
-            pub_key: network.get_pub_key(),
+            pub_key: pub_key.to_vec(),
             signature,
         };
 
@@ -11,7 +11,9 @@ use libp2p::{
     kad::{Quorum, Record, RecordKey},
     PeerId,
 };
-use sn_networking::{sort_peers_by_address, GetRecordCfg, Network, REPLICATION_PEERS_COUNT};
+use sn_networking::{
+    sort_peers_by_address, GetRecordCfg, Network, RecordFetchStrategy, REPLICATION_PEERS_COUNT,
+};
 use sn_protocol::{
     messages::{Cmd, Query, QueryResponse, Request, Response},
     storage::RecordType,
@@ -70,6 +72,7 @@ impl Node {
                         retry_strategy: None,
                         target_record: None,
                         expected_holders: Default::default(),
+                        fetch_strategy: RecordFetchStrategy::Kademlia,
                     };
                     node.network.get_record_from_network(key, &get_cfg).await?
                 };
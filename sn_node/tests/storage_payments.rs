@@ -13,7 +13,9 @@ use assert_fs::TempDir;
 use eyre::{eyre, Result};
 use libp2p::PeerId;
 use rand::Rng;
-use sn_client::{Error as ClientError, FilesDownload, Uploader, WalletClient};
+use sn_client::{
+    Error as ClientError, FilesApi, FilesDownload, Uploader, WalletClient, BATCH_SIZE,
+};
 use sn_logging::LogBuilder;
 use sn_networking::{GetRecordError, NetworkError};
 use sn_protocol::{
@@ -23,8 +25,11 @@ use sn_protocol::{
 };
 use sn_registers::Permissions;
 use sn_transfers::{MainPubkey, NanoTokens, PaymentQuote};
-use std::collections::BTreeMap;
-use tokio::time::{sleep, Duration};
+use std::{collections::BTreeMap, io::SeekFrom};
+use tokio::{
+    io::{AsyncReadExt, AsyncSeekExt},
+    time::{sleep, Duration},
+};
 use tracing::info;
 use xor_name::XorName;
 
@@ -209,6 +214,158 @@ async fn storage_payment_chunk_upload_succeeds() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn storage_payment_chunks_fetched_with_hedged_requests() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments");
+
+    let paying_wallet_dir = TempDir::new()?;
+    let chunks_dir = TempDir::new()?;
+
+    let (client, paying_wallet) = get_client_and_funded_wallet(paying_wallet_dir.path()).await?;
+
+    let (files_api, content_bytes, file_addr, chunks) =
+        random_content(&client, paying_wallet_dir.to_path_buf(), chunks_dir.path())?;
+
+    let mut uploader = Uploader::new(client.clone(), paying_wallet_dir.to_path_buf());
+    uploader.insert_chunk_paths(chunks.clone());
+    let _upload_stats = uploader.start_upload().await?;
+
+    // `get_chunk` fetches with `RecordFetchStrategy::Hedged`, directly from the holders ranked by
+    // their response latency, which gets learnt along the way.
+    for (name, path) in chunks {
+        let chunk = client
+            .get_chunk(ChunkAddress::new(name), false, None)
+            .await?;
+        assert_eq!(chunk.value().as_ref(), std::fs::read(path)?.as_slice());
+    }
+
+    let mut files_download = FilesDownload::new(files_api);
+    let downloaded = files_download.download_file(file_addr, None).await?;
+    assert_eq!(downloaded, content_bytes);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_payment_file_read_by_range() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments");
+
+    let paying_wallet_dir = TempDir::new()?;
+    let chunks_dir = TempDir::new()?;
+
+    let (client, paying_wallet) = get_client_and_funded_wallet(paying_wallet_dir.path()).await?;
+
+    let (files_api, content_bytes, file_addr, chunks) =
+        random_content(&client, paying_wallet_dir.to_path_buf(), chunks_dir.path())?;
+
+    let mut uploader = Uploader::new(client.clone(), paying_wallet_dir.to_path_buf());
+    uploader.insert_chunk_paths(chunks);
+    let _upload_stats = uploader.start_upload().await?;
+
+    let mut files_download = FilesDownload::new(files_api);
+    let mut reader = files_download.open_reader(file_addr).await?;
+    assert_eq!(reader.file_size(), content_bytes.len());
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..5 {
+        let start = rng.gen_range(0..content_bytes.len());
+        let len = rng.gen_range(1..=content_bytes.len() - start);
+
+        let _ = reader.seek(SeekFrom::Start(start as u64)).await?;
+        let mut read = vec![0; len];
+        reader.read_exact(&mut read).await?;
+        assert_eq!(read.as_slice(), &content_bytes[start..start + len]);
+    }
+
+    let _ = reader.seek(SeekFrom::Start(0)).await?;
+    let mut read = vec![];
+    let _ = reader.read_to_end(&mut read).await?;
+    assert_eq!(read.as_slice(), content_bytes.as_ref());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_payment_private_file_shared_with_recipient() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments");
+
+    let paying_wallet_dir = TempDir::new()?;
+    let recipient_wallet_dir = TempDir::new()?;
+    let chunks_dir = TempDir::new()?;
+
+    let (client, _paying_wallet) = get_client_and_funded_wallet(paying_wallet_dir.path()).await?;
+    let (recipient_client, _recipient_wallet) =
+        get_client_and_funded_wallet(recipient_wallet_dir.path()).await?;
+
+    // upload the file privately, keeping its data map local.
+    let mut rng = rand::thread_rng();
+    let content: Vec<u8> = (0..3 * 512 * 1024 + 123).map(|_| rng.gen()).collect();
+    let file_path = chunks_dir.join("private_file");
+    std::fs::write(&file_path, &content)?;
+    let (file_addr, data_map, _file_size, chunks) =
+        FilesApi::chunk_file(&file_path, chunks_dir.path(), false)?;
+
+    let mut uploader = Uploader::new(client.clone(), paying_wallet_dir.to_path_buf());
+    uploader.insert_chunk_paths(chunks);
+    let _upload_stats = uploader.start_upload().await?;
+
+    let files_api = FilesApi::new(client.clone(), paying_wallet_dir.to_path_buf());
+    let share_addr = files_api
+        .share_file(
+            "private_file".into(),
+            data_map,
+            recipient_client.signer_pk(),
+            true,
+        )
+        .await?;
+
+    // only the recipient can decrypt the share.
+    assert!(matches!(
+        files_api.receive_shared_file(share_addr).await,
+        Err(ClientError::SharedFileDecryption(_))
+    ));
+
+    let recipient_files_api = FilesApi::new(recipient_client, recipient_wallet_dir.to_path_buf());
+    let shared_file = recipient_files_api.receive_shared_file(share_addr).await?;
+    assert_eq!(*shared_file.address(), file_addr);
+
+    let download_dir = TempDir::new()?;
+    let mut files_download = FilesDownload::new(recipient_files_api);
+    let downloaded_path = files_download
+        .download_shared_file(share_addr, download_dir.path())
+        .await?;
+    assert_eq!(downloaded_path, download_dir.join("private_file"));
+    assert_eq!(std::fs::read(downloaded_path)?, content);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_payment_upload_from_reader_succeeds() -> Result<()> {
+    let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments");
+
+    let paying_wallet_dir = TempDir::new()?;
+
+    let (client, _paying_wallet) = get_client_and_funded_wallet(paying_wallet_dir.path()).await?;
+    let files_api = FilesApi::new(client.clone(), paying_wallet_dir.to_path_buf());
+
+    // Spans several upload batches, with a partial last chunk.
+    let mut rng = rand::thread_rng();
+    let content: Vec<u8> = (0..(BATCH_SIZE * 2 + 3) * 512 * 1024 + 123)
+        .map(|_| rng.gen())
+        .collect();
+
+    let file_addr = files_api
+        .upload_from_reader(std::io::Cursor::new(content.clone()), true, None)
+        .await?;
+
+    let mut files_download = FilesDownload::new(files_api);
+    let downloaded = files_download.download_file(file_addr, None).await?;
+    assert_eq!(downloaded.as_ref(), content.as_slice());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn storage_payment_chunk_upload_fails_if_no_tokens_sent() -> Result<()> {
     let _log_guards = LogBuilder::init_single_threaded_tokio_test("storage_payments");
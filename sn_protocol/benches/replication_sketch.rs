@@ -0,0 +1,115 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#![allow(clippy::unwrap_used)]
+
+use bls::rand::thread_rng;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use libp2p::PeerId;
+use sn_protocol::{
+    messages::{Cmd, ReplicationSketch},
+    storage::{ChunkAddress, RecordType},
+    NetworkAddress,
+};
+use xor_name::XorName;
+
+// Number of keys held by the sender, from a young node to a well filled one.
+const KEY_COUNTS: [usize; 4] = [1_000, 10_000, 50_000, 100_000];
+
+fn random_keys(count: usize) -> Vec<(NetworkAddress, RecordType)> {
+    let mut rng = thread_rng();
+    (0..count)
+        .map(|i| {
+            let address =
+                NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng)));
+            // Roughly one spend or register for every chunk.
+            let record_type = if i % 2 == 0 {
+                RecordType::Chunk
+            } else {
+                RecordType::NonChunk(XorName::random(&mut rng))
+            };
+            (address, record_type)
+        })
+        .collect()
+}
+
+fn replicate_cmd_len(holder: &NetworkAddress, keys: &[(NetworkAddress, RecordType)]) -> usize {
+    let cmd = Cmd::Replicate {
+        holder: holder.clone(),
+        keys: keys.to_vec(),
+    };
+    rmp_serde::to_vec(&cmd).unwrap().len()
+}
+
+fn replicate_sketch_cmd_len(holder: &NetworkAddress, sketch: &ReplicationSketch) -> usize {
+    let cmd = Cmd::ReplicateSketch {
+        holder: holder.clone(),
+        sketch: sketch.clone(),
+        distance_range: Some(250),
+    };
+    rmp_serde::to_vec(&cmd).unwrap().len()
+}
+
+/// Prints the serialised size of a replication round in both modes. In sketch mode, a round is
+/// the sketch plus the reply listing the keys the sender is missing, here 1% of them.
+fn print_size_comparison() {
+    let holder = NetworkAddress::from_peer(PeerId::random());
+    println!(
+        "{:>8} | {:>14} | {:>14} | {:>14} | {:>8}",
+        "keys", "key list bytes", "sketch bytes", "+ diff bytes", "ratio"
+    );
+    for count in KEY_COUNTS {
+        let keys = random_keys(count);
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 0);
+        let key_list_len = replicate_cmd_len(&holder, &keys);
+        let sketch_len = replicate_sketch_cmd_len(&holder, &sketch);
+        let diff_len = replicate_cmd_len(&holder, &keys[..count / 100]);
+        println!(
+            "{count:>8} | {key_list_len:>14} | {sketch_len:>14} | {:>14} | {:>7.1}x",
+            sketch_len + diff_len,
+            key_list_len as f64 / (sketch_len + diff_len) as f64
+        );
+    }
+}
+
+fn bench_replication_sketch(c: &mut Criterion) {
+    print_size_comparison();
+
+    let mut group = c.benchmark_group("replication sketch");
+    for count in KEY_COUNTS {
+        let keys = random_keys(count);
+        group.bench_with_input(BenchmarkId::new("build", count), &keys, |b, keys| {
+            b.iter(|| black_box(ReplicationSketch::from_keys(keys.iter(), 0)))
+        });
+
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 0);
+        let others = random_keys(count);
+        group.bench_with_input(
+            BenchmarkId::new("diff", count),
+            &(sketch, others),
+            |b, (sketch, others)| {
+                b.iter(|| {
+                    black_box(
+                        others
+                            .iter()
+                            .filter(|(address, record_type)| !sketch.contains(address, record_type))
+                            .count(),
+                    )
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_replication_sketch
+}
+criterion_main!(benches);
@@ -63,4 +63,7 @@ pub enum Error {
     // The record already exists at this node
     #[error("The record already exists, so do not charge for it: {0:?}")]
     RecordExists(PrettyPrintRecordKey<'static>),
+    // The node is not among the peers closest to the address, thus won't be paid to store it
+    #[error("Not among the close group of {0:?}, so not quoting for it")]
+    NotInCloseGroup(NetworkAddress),
 }
@@ -11,15 +11,19 @@ mod chunk_proof;
 mod cmd;
 mod node_id;
 mod query;
+mod record_metadata;
 mod register;
+mod replication_sketch;
 mod response;
 
 pub use self::{
     chunk_proof::{ChunkProof, Nonce},
     cmd::{Cmd, Hash},
     node_id::NodeId,
-    query::Query,
+    query::{Query, MAX_STORE_COSTS_BATCH_SIZE},
+    record_metadata::RecordMetadata,
     register::RegisterCmd,
+    replication_sketch::{ReplicationSketch, DEFAULT_SKETCH_FALSE_POSITIVE_RATE},
     response::{CmdOk, CmdResponse, QueryResponse},
 };
 
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 #![allow(clippy::mutable_key_type)] // for Bytes in NetworkAddress
 
+use super::ReplicationSketch;
 use crate::{storage::RecordType, NetworkAddress};
 use serde::{Deserialize, Serialize};
 // TODO: remove this dependency and define these types herein.
@@ -30,6 +31,20 @@ pub enum Cmd {
         /// Keys of copy that shall be replicated.
         keys: Vec<(NetworkAddress, RecordType)>,
     },
+    /// Write operation to notify peer of the keys held by the holder, as a compact sketch.
+    ///
+    /// The peer shall reply with a [`Replicate`] of the keys it holds that are missing from
+    /// the sketch, and within the `distance_range` of the holder.
+    ///
+    /// [`Replicate`]: Cmd::Replicate
+    ReplicateSketch {
+        /// Holder of the sketched keys.
+        holder: NetworkAddress,
+        /// Sketch of the keys held by the holder.
+        sketch: ReplicationSketch,
+        /// The ilog2 distance from the holder within which it is responsible for records.
+        distance_range: Option<u32>,
+    },
     /// Write operation to notify nodes a list of PaymentQuote collected.
     QuoteVerification {
         target: NetworkAddress,
@@ -54,6 +69,16 @@ impl std::fmt::Debug for Cmd {
                     .field("first_ten_keys", &first_ten_keys)
                     .finish()
             }
+            Cmd::ReplicateSketch {
+                holder,
+                sketch,
+                distance_range,
+            } => f
+                .debug_struct("Cmd::ReplicateSketch")
+                .field("holder", holder)
+                .field("sketch", sketch)
+                .field("distance_range", distance_range)
+                .finish(),
             Cmd::QuoteVerification { target, quotes } => f
                 .debug_struct("Cmd::QuoteVerification")
                 .field("target", target)
@@ -77,7 +102,7 @@ impl Cmd {
     /// Used to send a cmd to the close group of the address.
     pub fn dst(&self) -> NetworkAddress {
         match self {
-            Cmd::Replicate { holder, .. } => holder.clone(),
+            Cmd::Replicate { holder, .. } | Cmd::ReplicateSketch { holder, .. } => holder.clone(),
             Cmd::QuoteVerification { target, .. } => target.clone(),
             Cmd::PeerConsideredAsBad { bad_peer, .. } => bad_peer.clone(),
         }
@@ -95,6 +120,15 @@ impl std::fmt::Display for Cmd {
                     keys.len()
                 )
             }
+            Cmd::ReplicateSketch { holder, sketch, .. } => {
+                write!(
+                    f,
+                    "Cmd::ReplicateSketch({:?} has {} keys, sketched in {} bytes)",
+                    holder.as_peer_id(),
+                    sketch.num_keys(),
+                    sketch.size_in_bytes()
+                )
+            }
             Cmd::QuoteVerification { target, quotes } => {
                 write!(
                     f,
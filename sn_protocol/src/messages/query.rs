@@ -9,6 +9,10 @@
 use crate::{messages::Nonce, NetworkAddress};
 use serde::{Deserialize, Serialize};
 
+/// The maximum number of addresses a single [`Query::GetStoreCosts`] shall carry.
+/// Nodes only quote the first `MAX_STORE_COSTS_BATCH_SIZE` addresses of a larger batch.
+pub const MAX_STORE_COSTS_BATCH_SIZE: usize = 256;
+
 /// Data queries - retrieving data and inspecting their structure.
 ///
 /// See the [`protocol`] module documentation for more details of the types supported by the Safe
@@ -20,6 +24,13 @@ use serde::{Deserialize, Serialize};
 pub enum Query {
     /// Retrieve the cost of storing a record at the given address.
     GetStoreCost(NetworkAddress),
+    /// Retrieve the costs of storing records at each of the given addresses.
+    ///
+    /// The addresses are expected to have overlapping close groups.
+    /// This should eventually lead to a [`GetStoreCosts`] response.
+    ///
+    /// [`GetStoreCosts`]: super::QueryResponse::GetStoreCosts
+    GetStoreCosts(Vec<NetworkAddress>),
     /// Retrieve a specific record from a specific peer.
     ///
     /// This should eventually lead to a [`GetReplicatedRecord`] response.
@@ -40,6 +51,15 @@ pub enum Query {
     },
     /// Queries close_group peers whether the target peer is a bad_node
     CheckNodeInProblem(NetworkAddress),
+    /// Retrieve the metadata of a record from a specific peer, without fetching its value.
+    ///
+    /// This should eventually lead to a [`GetRecordMetadata`] response.
+    ///
+    /// [`GetRecordMetadata`]: super::QueryResponse::GetRecordMetadata
+    GetRecordMetadata {
+        /// Key of the record to be described
+        key: NetworkAddress,
+    },
 }
 
 impl Query {
@@ -47,10 +67,17 @@ impl Query {
     pub fn dst(&self) -> NetworkAddress {
         match self {
             Query::GetStoreCost(address) | Query::CheckNodeInProblem(address) => address.clone(),
+            // The batch shares a close group, hence any of the addresses will do.
+            Query::GetStoreCosts(addresses) => addresses
+                .first()
+                .cloned()
+                .unwrap_or_else(|| NetworkAddress::RecordKey(Default::default())),
             // Shall not be called for this, as this is a `one-to-one` message,
             // and the destination shall be decided by the requester already.
             Query::GetReplicatedRecord { key, .. } => key.clone(),
-            Query::GetChunkExistenceProof { key, .. } => key.clone(),
+            Query::GetChunkExistenceProof { key, .. } | Query::GetRecordMetadata { key } => {
+                key.clone()
+            }
         }
     }
 }
@@ -61,6 +88,9 @@ impl std::fmt::Display for Query {
             Query::GetStoreCost(address) => {
                 write!(f, "Query::GetStoreCost({address:?})")
             }
+            Query::GetStoreCosts(addresses) => {
+                write!(f, "Query::GetStoreCosts({} addresses)", addresses.len())
+            }
             Query::GetReplicatedRecord { key, requester } => {
                 write!(f, "Query::GetStoreCost({requester:?} {key:?})")
             }
@@ -70,6 +100,9 @@ impl std::fmt::Display for Query {
             Query::CheckNodeInProblem(address) => {
                 write!(f, "Query::CheckNodeInProblem({address:?})")
             }
+            Query::GetRecordMetadata { key } => {
+                write!(f, "Query::GetRecordMetadata({key:?})")
+            }
         }
     }
 }
@@ -0,0 +1,49 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    error::Error,
+    storage::{RecordHeader, RecordKind},
+};
+use libp2p::kad::Record;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use xor_name::XorName;
+
+/// What a node knows about a record it holds, without the record value itself.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct RecordMetadata {
+    /// The kind of the record, as read from its header.
+    pub kind: RecordKind,
+    /// The length of the serialised record value, header included.
+    pub size: usize,
+    /// The hash of the serialised record value, header included.
+    pub content_hash: XorName,
+    /// How long the node has been holding the record for.
+    pub held_for: Duration,
+}
+
+impl RecordMetadata {
+    /// Describe a record that has been held for `held_for`.
+    pub fn from_record(record: &Record, held_for: Duration) -> Result<Self, Box<Error>> {
+        let header = RecordHeader::from_record(record).map_err(Box::new)?;
+        Ok(Self {
+            kind: header.kind,
+            size: record.value.len(),
+            content_hash: XorName::from_content(&record.value),
+            held_for,
+        })
+    }
+
+    /// Whether two holders are holding the same copy of a record.
+    pub fn same_content(&self, other: &RecordMetadata) -> bool {
+        self.kind == other.kind
+            && self.size == other.size
+            && self.content_hash == other.content_hash
+    }
+}
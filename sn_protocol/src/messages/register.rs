@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_registers::{Register, RegisterAddress, RegisterOp};
+use sn_registers::{CheckpointOp, PermissionsOp, Register, RegisterAddress, RegisterOp};
 
 use serde::{Deserialize, Serialize};
 
@@ -23,6 +23,10 @@ pub enum RegisterCmd {
     },
     /// Edit the register
     Edit(RegisterOp),
+    /// Change the permissions of the register
+    EditPermissions(PermissionsOp),
+    /// Collapse the history of the register
+    Checkpoint(CheckpointOp),
 }
 
 /// Custom debug implementation to avoid printing the whole register
@@ -34,6 +38,10 @@ impl std::fmt::Debug for RegisterCmd {
                 write!(f, "RegisterCmd::Create({:?})", register.address())
             }
             RegisterCmd::Edit(op) => write!(f, "RegisterCmd::Edit({:?})", op.address()),
+            RegisterCmd::EditPermissions(op) => {
+                write!(f, "RegisterCmd::EditPermissions({:?})", op.address())
+            }
+            RegisterCmd::Checkpoint(op) => write!(f, "RegisterCmd::Checkpoint({:?})", op.address()),
         }
     }
 }
@@ -44,6 +52,8 @@ impl RegisterCmd {
         match self {
             Self::Create { register, .. } => *register.address(),
             Self::Edit(op) => op.address(),
+            Self::EditPermissions(op) => op.address(),
+            Self::Checkpoint(op) => op.address(),
         }
     }
 }
@@ -0,0 +1,298 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{storage::RecordType, NetworkAddress};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The false positive rate a `ReplicationSketch` is sized for by default.
+/// A false positive hides a missing key from the peer for one replication round only,
+/// as every round is built with a fresh seed.
+pub const DEFAULT_SKETCH_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// Upper bound of the number of hash functions, to bound the cost of a lookup.
+const MAX_SKETCH_HASHES: u32 = 16;
+
+/// Upper bound of the size of the bit array, in 64 bit words (5MB, the max packet size).
+/// Larger key sets are sketched at a higher false positive rate.
+const MAX_SKETCH_WORDS: usize = 5 * 1024 * 1024 / 8;
+
+/// A compact summary (a bloom filter) of the record keys held by a node.
+///
+/// Sent in place of the full key list during periodic replication. The receiver replies with
+/// the keys it holds that are not in the sketch, i.e. the keys the sender is missing.
+/// As a bloom filter has no false negatives, no key held by the sender is ever sent back.
+///
+/// Sketches received from peers are checked on deserialization.
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "UncheckedSketch")]
+pub struct ReplicationSketch {
+    seed: u64,
+    num_hashes: u32,
+    num_keys: u32,
+    bits: Vec<u64>,
+}
+
+/// The serialized form of a `ReplicationSketch`, as received from a peer.
+#[derive(Deserialize)]
+struct UncheckedSketch {
+    seed: u64,
+    num_hashes: u32,
+    num_keys: u32,
+    bits: Vec<u64>,
+}
+
+impl TryFrom<UncheckedSketch> for ReplicationSketch {
+    type Error = String;
+
+    fn try_from(sketch: UncheckedSketch) -> Result<Self, Self::Error> {
+        if !(1..=MAX_SKETCH_HASHES).contains(&sketch.num_hashes) {
+            return Err(format!(
+                "sketch uses {} hashes, outside of 1..={MAX_SKETCH_HASHES}",
+                sketch.num_hashes
+            ));
+        }
+        if sketch.bits.is_empty() || sketch.bits.len() > MAX_SKETCH_WORDS {
+            return Err(format!(
+                "sketch holds {} words, outside of 1..={MAX_SKETCH_WORDS}",
+                sketch.bits.len()
+            ));
+        }
+        Ok(Self {
+            seed: sketch.seed,
+            num_hashes: sketch.num_hashes,
+            num_keys: sketch.num_keys,
+            bits: sketch.bits,
+        })
+    }
+}
+
+impl ReplicationSketch {
+    /// Build a sketch of the keys, sized for the `DEFAULT_SKETCH_FALSE_POSITIVE_RATE`.
+    pub fn from_keys<'a>(
+        keys: impl ExactSizeIterator<Item = &'a (NetworkAddress, RecordType)>,
+        seed: u64,
+    ) -> Self {
+        Self::with_false_positive_rate(keys, DEFAULT_SKETCH_FALSE_POSITIVE_RATE, seed)
+    }
+
+    /// Build a sketch of the keys, sized for the given false positive rate.
+    pub fn with_false_positive_rate<'a>(
+        keys: impl ExactSizeIterator<Item = &'a (NetworkAddress, RecordType)>,
+        false_positive_rate: f64,
+        seed: u64,
+    ) -> Self {
+        let num_keys = keys.len().max(1);
+        let false_positive_rate = false_positive_rate.clamp(f64::EPSILON, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_bits = (-(num_keys as f64) * false_positive_rate.ln() / (ln2 * ln2)).ceil();
+        let num_words = (num_bits as usize).div_ceil(64).clamp(1, MAX_SKETCH_WORDS);
+        let num_hashes = ((num_words * 64) as f64 / num_keys as f64 * ln2).round() as u32;
+
+        let mut sketch = Self {
+            seed,
+            num_hashes: num_hashes.clamp(1, MAX_SKETCH_HASHES),
+            num_keys: 0,
+            bits: vec![0; num_words],
+        };
+        for (address, record_type) in keys {
+            sketch.insert(address, record_type);
+        }
+        sketch
+    }
+
+    /// Whether the key may be held by the sketched node. False positives are possible,
+    /// false negatives are not.
+    pub fn contains(&self, address: &NetworkAddress, record_type: &RecordType) -> bool {
+        let num_bits = self.bits.len() as u64 * 64;
+        if num_bits == 0 {
+            return false;
+        }
+        self.bit_indexes(address, record_type, num_bits)
+            .all(|index| self.bits[(index / 64) as usize] & (1 << (index % 64)) != 0)
+    }
+
+    /// The number of keys that were sketched.
+    pub fn num_keys(&self) -> usize {
+        self.num_keys as usize
+    }
+
+    /// The size of the bit array, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.len() * std::mem::size_of::<u64>()
+    }
+
+    fn insert(&mut self, address: &NetworkAddress, record_type: &RecordType) {
+        let num_bits = self.bits.len() as u64 * 64;
+        let indexes: Vec<u64> = self.bit_indexes(address, record_type, num_bits).collect();
+        for index in indexes {
+            self.bits[(index / 64) as usize] |= 1 << (index % 64);
+        }
+        self.num_keys = self.num_keys.saturating_add(1);
+    }
+
+    /// Double hashing over a single sha3 of the seeded key, see Kirsch and Mitzenmacher,
+    /// "Less Hashing, Same Performance: Building a Better Bloom Filter".
+    fn bit_indexes(
+        &self,
+        address: &NetworkAddress,
+        record_type: &RecordType,
+        num_bits: u64,
+    ) -> impl Iterator<Item = u64> {
+        use tiny_keccak::{Hasher, Sha3};
+
+        let mut sha3 = Sha3::v256();
+        let mut hash = [0; 32];
+        sha3.update(&self.seed.to_be_bytes());
+        sha3.update(address.to_record_key().as_ref());
+        match record_type {
+            RecordType::Chunk => sha3.update(&[0]),
+            RecordType::NonChunk(content_hash) => {
+                sha3.update(&[1]);
+                sha3.update(&content_hash.0);
+            }
+        }
+        sha3.finalize(&mut hash);
+
+        let mut first = [0; 8];
+        let mut second = [0; 8];
+        first.copy_from_slice(&hash[..8]);
+        second.copy_from_slice(&hash[8..16]);
+        let first = u64::from_be_bytes(first);
+        // An odd step is never a multiple of the (even) number of bits,
+        // hence the indexes never all collapse onto `first`.
+        let second = u64::from_be_bytes(second) | 1;
+
+        (0..self.num_hashes as u64)
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % num_bits)
+    }
+}
+
+impl fmt::Debug for ReplicationSketch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplicationSketch")
+            .field("num_keys", &self.num_keys)
+            .field("num_hashes", &self.num_hashes)
+            .field("size_in_bytes", &self.size_in_bytes())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ChunkAddress;
+    use bls::rand::thread_rng;
+    use xor_name::XorName;
+
+    fn random_keys(count: usize) -> Vec<(NetworkAddress, RecordType)> {
+        let mut rng = thread_rng();
+        (0..count)
+            .map(|i| {
+                let address = NetworkAddress::from_chunk_address(ChunkAddress::new(
+                    XorName::random(&mut rng),
+                ));
+                let record_type = if i % 2 == 0 {
+                    RecordType::Chunk
+                } else {
+                    RecordType::NonChunk(XorName::random(&mut rng))
+                };
+                (address, record_type)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sketch_has_no_false_negatives() {
+        let keys = random_keys(5_000);
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 42);
+
+        assert_eq!(sketch.num_keys(), keys.len());
+        assert!(keys
+            .iter()
+            .all(|(address, record_type)| sketch.contains(address, record_type)));
+    }
+
+    #[test]
+    fn sketch_false_positive_rate_is_bounded() {
+        let keys = random_keys(5_000);
+        let others = random_keys(5_000);
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 7);
+
+        let false_positives = others
+            .iter()
+            .filter(|(address, record_type)| sketch.contains(address, record_type))
+            .count();
+        // Sized for 1%, allow some slack for the randomness of the keys.
+        assert!(
+            false_positives < others.len() * 3 / 100,
+            "{false_positives} false positives"
+        );
+    }
+
+    #[test]
+    fn sketch_tells_apart_record_versions() {
+        let keys = random_keys(100);
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 1);
+
+        // The same addresses holding other versions of their non-chunk records.
+        let mut rng = thread_rng();
+        let newer_versions_contained = keys
+            .iter()
+            .filter(|(address, record_type)| {
+                matches!(record_type, RecordType::NonChunk(_))
+                    && sketch.contains(address, &RecordType::NonChunk(XorName::random(&mut rng)))
+            })
+            .count();
+        assert!(newer_versions_contained < 5);
+    }
+
+    #[test]
+    fn hostile_sketch_is_rejected() -> color_eyre::Result<()> {
+        let sketch = ReplicationSketch::from_keys(random_keys(10).iter(), 3);
+        let round_tripped: ReplicationSketch = rmp_serde::from_slice(&rmp_serde::to_vec(&sketch)?)?;
+        assert_eq!(round_tripped, sketch);
+
+        for (num_hashes, bits) in [
+            (0, vec![u64::MAX]),
+            (u32::MAX, vec![u64::MAX]),
+            (MAX_SKETCH_HASHES + 1, vec![u64::MAX]),
+            (1, vec![]),
+            (1, vec![0; MAX_SKETCH_WORDS + 1]),
+        ] {
+            let hostile = ReplicationSketch {
+                num_hashes,
+                bits,
+                ..sketch.clone()
+            };
+            assert!(
+                rmp_serde::from_slice::<ReplicationSketch>(&rmp_serde::to_vec(&hostile)?).is_err(),
+                "sketch with {num_hashes} hashes and {} words was accepted",
+                hostile.bits.len()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn sketch_is_much_smaller_than_the_key_list() -> color_eyre::Result<()> {
+        let keys = random_keys(10_000);
+        let sketch = ReplicationSketch::from_keys(keys.iter(), 0);
+
+        let key_list_len = rmp_serde::to_vec(&keys)?.len();
+        let sketch_len = rmp_serde::to_vec(&sketch)?.len();
+        assert!(
+            sketch_len * 20 < key_list_len,
+            "{sketch_len} vs {key_list_len}"
+        );
+
+        Ok(())
+    }
+}
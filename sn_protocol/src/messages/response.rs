@@ -8,7 +8,7 @@
 
 use crate::{error::Result, NetworkAddress};
 
-use super::ChunkProof;
+use super::{ChunkProof, RecordMetadata};
 use bytes::Bytes;
 use core::fmt;
 use serde::{Deserialize, Serialize};
@@ -32,6 +32,17 @@ pub enum QueryResponse {
         /// Node's Peer Address
         peer_address: NetworkAddress,
     },
+    /// Response to [`GetStoreCosts`]
+    ///
+    /// [`GetStoreCosts`]: crate::messages::Query::GetStoreCosts
+    GetStoreCosts {
+        /// The store cost quote for each of the queried addresses, in the order queried.
+        quotes: Vec<(NetworkAddress, Result<PaymentQuote>)>,
+        /// The cash_note MainPubkey to pay this node's store costs to.
+        payment_address: MainPubkey,
+        /// Node's Peer Address
+        peer_address: NetworkAddress,
+    },
     CheckNodeInProblem {
         /// Address of the peer that queried
         reporter_address: NetworkAddress,
@@ -52,6 +63,12 @@ pub enum QueryResponse {
     ///
     /// [`GetChunkExistenceProof`]: crate::messages::Query::GetChunkExistenceProof
     GetChunkExistenceProof(Result<ChunkProof>),
+    // ===== RecordMetadata =====
+    //
+    /// Response to [`GetRecordMetadata`], carrying the holder's address along with the metadata.
+    ///
+    /// [`GetRecordMetadata`]: crate::messages::Query::GetRecordMetadata
+    GetRecordMetadata(Result<(NetworkAddress, RecordMetadata)>),
 }
 
 // Debug implementation for QueryResponse, to avoid printing Vec<u8>
@@ -68,6 +85,17 @@ impl Debug for QueryResponse {
                     "GetStoreCost(quote: {quote:?}, from {peer_address:?} w/ payment_address: {payment_address:?})"
                 )
             }
+            QueryResponse::GetStoreCosts {
+                quotes,
+                payment_address,
+                peer_address,
+            } => {
+                write!(
+                    f,
+                    "GetStoreCosts({} quotes, from {peer_address:?} w/ payment_address: {payment_address:?})",
+                    quotes.len()
+                )
+            }
             QueryResponse::CheckNodeInProblem {
                 reporter_address,
                 target_address,
@@ -94,6 +122,9 @@ impl Debug for QueryResponse {
             QueryResponse::GetChunkExistenceProof(proof) => {
                 write!(f, "GetChunkExistenceProof(proof: {proof:?})")
             }
+            QueryResponse::GetRecordMetadata(result) => {
+                write!(f, "GetRecordMetadata({result:?})")
+            }
         }
     }
 }
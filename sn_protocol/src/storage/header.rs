@@ -36,6 +36,8 @@ pub enum RecordKind {
     Spend,
     Register,
     RegisterWithPayment,
+    /// Ops to apply to a Register already held, which is never stored as is.
+    RegisterOps,
 }
 
 impl Serialize for RecordKind {
@@ -49,6 +51,7 @@ impl Serialize for RecordKind {
             Self::Spend => serializer.serialize_u32(2),
             Self::Register => serializer.serialize_u32(3),
             Self::RegisterWithPayment => serializer.serialize_u32(4),
+            Self::RegisterOps => serializer.serialize_u32(5),
         }
     }
 }
@@ -65,6 +68,7 @@ impl<'de> Deserialize<'de> for RecordKind {
             2 => Ok(Self::Spend),
             3 => Ok(Self::Register),
             4 => Ok(Self::RegisterWithPayment),
+            5 => Ok(Self::RegisterOps),
             _ => Err(serde::de::Error::custom(
                 "Unexpected integer for RecordKind variant",
             )),
@@ -185,6 +189,12 @@ mod tests {
         .try_serialize()?;
         assert_eq!(register.len(), RecordHeader::SIZE);
 
+        let register_ops = RecordHeader {
+            kind: RecordKind::RegisterOps,
+        }
+        .try_serialize()?;
+        assert_eq!(register_ops.len(), RecordHeader::SIZE);
+
         Ok(())
     }
 }
@@ -0,0 +1,109 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Entry, Error, RegisterAddress};
+
+use bls::{PublicKey, SecretKey, Signature};
+use crdts::merkle_reg::Node as MerkleDagEntry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Checkpoint operation to apply to all the replicas of a Register, signed by its owner.
+///
+/// It collapses the history of the Register below its latest entries at the time, the snapshot,
+/// so that only the snapshot and the entries written atop it are kept. Any other entry, e.g. one
+/// written concurrently atop an entry older than the snapshot, is dropped.
+/// It is bound to the permissions changes known when it was made, and only honoured if made by
+/// the owner in effect then, hence a previous owner can no longer make one once it transferred
+/// the ownership. Only the latest checkpoint, in order of the permissions changes they were
+/// made after and then of their `seq`, is kept.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CheckpointOp {
+    /// Address of a Register object on the network.
+    pub(crate) address: RegisterAddress,
+    /// The `seq` of the next permissions change of the Register when the checkpoint was made.
+    pub(crate) permissions_seq: u64,
+    /// Position of the checkpoint among all the checkpoints of the Register.
+    pub(crate) seq: u64,
+    /// The latest entries of the Register when the checkpoint was made.
+    pub(crate) snapshot: BTreeSet<MerkleDagEntry<Entry>>,
+    /// The PublicKey of the entity that generated the operation
+    pub(crate) source: PublicKey,
+    /// The signature of source on (address, permissions_seq, seq, snapshot, source) required to apply the op
+    pub(crate) signature: Signature,
+}
+
+impl CheckpointOp {
+    /// Create a new CheckpointOp
+    pub(crate) fn new(
+        address: RegisterAddress,
+        permissions_seq: u64,
+        seq: u64,
+        snapshot: BTreeSet<MerkleDagEntry<Entry>>,
+        signer: &SecretKey,
+    ) -> Result<Self> {
+        let source = signer.public_key();
+        let bytes = Self::bytes_for_signing(&address, permissions_seq, seq, &snapshot, &source)?;
+        let signature = signer.sign(bytes);
+        Ok(Self {
+            address,
+            permissions_seq,
+            seq,
+            snapshot,
+            source,
+            signature,
+        })
+    }
+
+    /// address of the register this op is destined for
+    pub fn address(&self) -> RegisterAddress {
+        self.address
+    }
+
+    /// the seq of the next permissions change of the register when the checkpoint was made
+    pub fn permissions_seq(&self) -> u64 {
+        self.permissions_seq
+    }
+
+    /// position of the checkpoint among all the checkpoints of the register
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// the entity that generated the operation
+    pub fn source(&self) -> PublicKey {
+        self.source
+    }
+
+    /// Check signature of checkpoint Op against its source
+    pub fn verify_signature(&self) -> Result<()> {
+        let bytes = Self::bytes_for_signing(
+            &self.address,
+            self.permissions_seq,
+            self.seq,
+            &self.snapshot,
+            &self.source,
+        )?;
+        if !self.source.verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Returns a bytes version of the CheckpointOp used for signing
+    fn bytes_for_signing(
+        address: &RegisterAddress,
+        permissions_seq: u64,
+        seq: u64,
+        snapshot: &BTreeSet<MerkleDagEntry<Entry>>,
+        source: &PublicKey,
+    ) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(&(address, permissions_seq, seq, snapshot, source))
+            .map_err(|_| Error::SerialisationFailed)
+    }
+}
@@ -66,6 +66,9 @@ pub enum Error {
     /// The provided String can't be deserialized as a RegisterAddress
     #[error("Failed to deserialize hex RegisterAddress")]
     HexDeserializeFailed,
+    /// A checkpoint cannot be made of a Register without entries.
+    #[error("Cannot checkpoint a Register without entries")]
+    EmptyCheckpoint,
 }
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
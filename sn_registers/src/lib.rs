@@ -7,18 +7,22 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod address;
+mod checkpoint_op;
 pub(crate) mod error;
 mod metadata;
 mod permissions;
+mod permissions_op;
 pub(crate) mod reg_crdt;
 pub(crate) mod register;
 mod register_op;
 
 pub use self::{
     address::RegisterAddress,
+    checkpoint_op::CheckpointOp,
     error::Error,
     metadata::{Entry, EntryHash},
     permissions::Permissions,
-    register::{Register, SignedRegister},
+    permissions_op::{PermissionsChange, PermissionsOp},
+    register::{Register, SignedRegister, MAX_REG_ENTRY_SIZE},
     register_op::RegisterOp,
 };
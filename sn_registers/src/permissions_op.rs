@@ -0,0 +1,142 @@
+// Copyright 2024 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, EntryHash, Error, Permissions, RegisterAddress};
+
+use bls::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A change of the permissions of a Register, including who owns it.
+/// The owner is always allowed to write, whatever the change made.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PermissionsChange {
+    /// Allow the user to write to the Register, if it is restricted to a set of users
+    AddWriter(PublicKey),
+    /// Revoke the permission of the user to write to the Register, if it is restricted to a set of users
+    RemoveWriter(PublicKey),
+    /// Allow anyone to write to the Register
+    AnyoneCanWrite,
+    /// Restrict writing to the Register to the given users
+    Writers(BTreeSet<PublicKey>),
+    /// Transfer the ownership of the Register to the given user, who then is the only one
+    /// allowed to change its permissions. The previous owner is no longer allowed to write,
+    /// unless the new owner adds it back as a writer.
+    TransferOwnership(PublicKey),
+}
+
+impl PermissionsChange {
+    /// Apply the change to the permissions of a Register owned by `owner`.
+    pub(crate) fn apply(&self, permissions: &mut Permissions, owner: &mut PublicKey) {
+        match self {
+            Self::AddWriter(user) => permissions.add_writer(*user),
+            Self::RemoveWriter(user) => permissions.remove_writer(user),
+            Self::AnyoneCanWrite => *permissions = Permissions::new_anyone_can_write(),
+            Self::Writers(writers) => *permissions = Permissions::new_with(writers.iter().copied()),
+            Self::TransferOwnership(new_owner) => {
+                permissions.remove_writer(owner);
+                *owner = *new_owner;
+            }
+        }
+        permissions.add_writer(*owner);
+    }
+}
+
+/// Permissions change operation to apply to all the replicas of a Register, signed by its owner.
+///
+/// The changes are applied in order of their `seq`, ignoring the ones signed by a previous owner
+/// once the ownership was transferred, e.g. if the owner concurrently transferred it twice, only
+/// the first transfer in that order takes effect. Along with the change, the operation holds
+/// the latest entries of the Register known to the owner when making it, so that the entries
+/// written by a user before being revoked are kept, while the ones the user writes afterwards
+/// are ignored.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PermissionsOp {
+    /// Address of a Register object on the network.
+    pub(crate) address: RegisterAddress,
+    /// Position of the change among all the changes made to the permissions of the Register.
+    pub(crate) seq: u64,
+    /// The change to apply.
+    pub(crate) change: PermissionsChange,
+    /// The latest entries of the Register when the change was made.
+    pub(crate) heads: BTreeSet<EntryHash>,
+    /// The PublicKey of the entity that generated the operation
+    pub(crate) source: PublicKey,
+    /// The signature of source on (address, seq, change, heads, source) required to apply the op
+    pub(crate) signature: Signature,
+}
+
+impl PermissionsOp {
+    /// Create a new PermissionsOp
+    pub(crate) fn new(
+        address: RegisterAddress,
+        seq: u64,
+        change: PermissionsChange,
+        heads: BTreeSet<EntryHash>,
+        signer: &SecretKey,
+    ) -> Result<Self> {
+        let source = signer.public_key();
+        let bytes = Self::bytes_for_signing(&address, seq, &change, &heads, &source)?;
+        let signature = signer.sign(bytes);
+        Ok(Self {
+            address,
+            seq,
+            change,
+            heads,
+            source,
+            signature,
+        })
+    }
+
+    /// address of the register this op is destined for
+    pub fn address(&self) -> RegisterAddress {
+        self.address
+    }
+
+    /// position of the change among all the changes made to the permissions of the register
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// the change made to the permissions
+    pub fn change(&self) -> &PermissionsChange {
+        &self.change
+    }
+
+    /// the entity that generated the operation
+    pub fn source(&self) -> PublicKey {
+        self.source
+    }
+
+    /// Check signature of permissions Op against its source
+    pub fn verify_signature(&self) -> Result<()> {
+        let bytes = Self::bytes_for_signing(
+            &self.address,
+            self.seq,
+            &self.change,
+            &self.heads,
+            &self.source,
+        )?;
+        if !self.source.verify(&self.signature, bytes) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Returns a bytes version of the PermissionsOp used for signing
+    fn bytes_for_signing(
+        address: &RegisterAddress,
+        seq: u64,
+        change: &PermissionsChange,
+        heads: &BTreeSet<EntryHash>,
+        source: &PublicKey,
+    ) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(&(address, seq, change, heads, source))
+            .map_err(|_| Error::SerialisationFailed)
+    }
+}
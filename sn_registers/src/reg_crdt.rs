@@ -8,11 +8,11 @@
 
 use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress, RegisterOp};
 
-use crdts::merkle_reg::Node as MerkleDagEntry;
+use crdts::merkle_reg::{Hash as MerkleHash, Node as MerkleDagEntry};
 use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
     hash::Hash,
 };
@@ -26,6 +26,17 @@ pub(crate) struct RegisterCrdt {
     data: MerkleReg<Entry>,
 }
 
+/// The inner parts of a `MerkleReg`, used to build one out of a snapshot of entries whose
+/// children were collapsed, which its API doesn't allow for.
+/// They mirror the serialised layout of `MerkleReg` of the crdts version pinned, which
+/// `merkle_reg_parts_match_its_layout` checks.
+#[derive(Serialize, Deserialize)]
+struct MerkleRegParts {
+    roots: BTreeSet<MerkleHash>,
+    dag: BTreeMap<MerkleHash, MerkleDagEntry<Entry>>,
+    orphans: BTreeMap<MerkleHash, MerkleDagEntry<Entry>>,
+}
+
 impl Display for RegisterCrdt {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "(")?;
@@ -127,6 +138,76 @@ impl RegisterCrdt {
     pub(crate) fn merkle_reg(&self) -> &MerkleReg<Entry> {
         &self.data
     }
+
+    /// Collapse the history below the given snapshot of entries, keeping only them and the
+    /// entries written atop them. Any other entry is dropped.
+    pub(crate) fn collapse(&mut self, snapshot: &BTreeSet<MerkleDagEntry<Entry>>) -> Result<()> {
+        // the orphans can only be listed out of the inner parts
+        let nodes: Vec<_> = if self.data.num_orphans() == 0 {
+            self.data.all_nodes().cloned().collect()
+        } else {
+            let parts: MerkleRegParts = reserialise(&self.data)?;
+            parts
+                .dag
+                .into_values()
+                .chain(parts.orphans.into_values())
+                .collect()
+        };
+        let kept = written_atop(snapshot, &nodes);
+
+        let dag: BTreeMap<_, _> = snapshot
+            .iter()
+            .map(|node| (node.hash(), node.clone()))
+            .collect();
+        let roots = dag
+            .keys()
+            .filter(|hash| !snapshot.iter().any(|node| node.children.contains(*hash)))
+            .copied()
+            .collect();
+        let parts = MerkleRegParts {
+            roots,
+            dag,
+            orphans: BTreeMap::new(),
+        };
+        self.data = reserialise(&parts)?;
+        for node in nodes {
+            if kept.contains(&node.hash()) {
+                self.data.apply(node);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Convert a value into another type with the same serialised form.
+fn reserialise<T: Serialize, U: DeserializeOwned>(value: &T) -> Result<U> {
+    let bytes = rmp_serde::to_vec(value).map_err(|_| Error::SerialisationFailed)?;
+    rmp_serde::from_slice(&bytes).map_err(|_| Error::SerialisationFailed)
+}
+
+/// Returns the hashes of the given nodes written atop the snapshot, directly or not, along with
+/// the ones of the snapshot itself.
+pub(crate) fn written_atop(
+    snapshot: &BTreeSet<MerkleDagEntry<Entry>>,
+    nodes: &[MerkleDagEntry<Entry>],
+) -> BTreeSet<MerkleHash> {
+    let mut kept: BTreeSet<_> = snapshot.iter().map(|node| node.hash()).collect();
+    loop {
+        let newly_kept: Vec<_> = nodes
+            .iter()
+            .map(|node| (node.hash(), node))
+            .filter(|(hash, node)| {
+                !kept.contains(hash)
+                    && !node.children.is_empty()
+                    && node.children.iter().all(|child| kept.contains(child))
+            })
+            .map(|(hash, _)| hash)
+            .collect();
+        if newly_kept.is_empty() {
+            return kept;
+        }
+        kept.extend(newly_kept);
+    }
 }
 
 #[cfg(test)]
@@ -226,4 +307,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn merkle_reg_parts_match_its_layout() -> Result<()> {
+        let mut reg = MerkleReg::new();
+        let root = reg.write(vec![0x1], BTreeSet::new());
+        reg.apply(root.clone());
+        let child = reg.write(vec![0x2], [root.hash()].into());
+        reg.apply(child.clone());
+        let orphan = reg.write(vec![0x3], [[0xff; 32]].into());
+        reg.apply(orphan.clone());
+
+        let parts: MerkleRegParts = reserialise(&reg)?;
+        assert_eq!(parts.roots, reg.read().hashes());
+        assert_eq!(
+            parts.dag,
+            [(root.hash(), root), (child.hash(), child)].into()
+        );
+        assert_eq!(parts.orphans, [(orphan.hash(), orphan)].into());
+
+        let rebuilt: MerkleReg<Entry> = reserialise(&parts)?;
+        assert_eq!(rebuilt, reg);
+
+        Ok(())
+    }
 }
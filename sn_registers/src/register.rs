@@ -7,18 +7,20 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, Error, Permissions, RegisterAddress,
-    RegisterOp,
+    error::Result,
+    reg_crdt::{written_atop, RegisterCrdt},
+    CheckpointOp, Entry, EntryHash, Error, Permissions, PermissionsChange, PermissionsOp,
+    RegisterAddress, RegisterOp,
 };
 
 use bls::{PublicKey, SecretKey, Signature};
 use crdts::merkle_reg::MerkleReg;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use xor_name::XorName;
 
 /// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = 1024;
+pub const MAX_REG_ENTRY_SIZE: usize = 1024;
 
 /// Maximum number of entries of a register.
 const MAX_REG_NUM_ENTRIES: u16 = 1024;
@@ -32,6 +34,22 @@ pub struct Register {
     /// Depending on the permissions, the owner can allow other users to write to the register
     /// Everyone can always read the Register because all data is public
     permissions: Permissions,
+    /// Changes made by the owner to the above permissions since the Register was created
+    /// Only the last field can be skipped when serialising, as the fields are serialised in
+    /// order, thus this one is always serialised, even if empty.
+    #[serde(default)]
+    permissions_ops: BTreeSet<PermissionsOp>,
+    /// The latest checkpoint made by the owner, the history below which was collapsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checkpoint: Option<CheckpointOp>,
+}
+
+/// The changes made to the permissions of a Register, along with the permissions before each of
+/// them, and the permissions and owner resulting from all of them.
+struct PermissionsHistory<'a> {
+    changes: Vec<(&'a PermissionsOp, Permissions)>,
+    permissions: Permissions,
+    owner: PublicKey,
 }
 
 /// A Signed Register on the SAFE Network
@@ -45,6 +63,14 @@ pub struct SignedRegister {
     /// operations to apply on this register,
     /// they contain a signature of the writer
     ops: BTreeSet<RegisterOp>,
+    /// changes to the permissions of this register, signed by the owner,
+    /// always serialised as the following field may be skipped
+    #[serde(default)]
+    permissions_ops: BTreeSet<PermissionsOp>,
+    /// the latest checkpoint of this register, signed by the owner,
+    /// the operations it collapsed are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checkpoint: Option<CheckpointOp>,
 }
 
 impl SignedRegister {
@@ -54,6 +80,8 @@ impl SignedRegister {
             base_register,
             signature,
             ops: BTreeSet::new(),
+            permissions_ops: BTreeSet::new(),
+            checkpoint: None,
         }
     }
 
@@ -62,14 +90,19 @@ impl SignedRegister {
         let bytes = self.base_register.bytes()?;
         if !self
             .base_register
+            .address()
             .owner()
             .verify(&self.signature, bytes.as_slice())
         {
             return Err(Error::InvalidSignature);
         }
 
+        let register = self.base_register_with_permissions_ops()?;
         for op in &self.ops {
-            self.base_register.check_register_op(op)?;
+            register.check_register_op(op)?;
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            register.check_checkpoint_op(checkpoint)?;
         }
         Ok(())
     }
@@ -84,20 +117,51 @@ impl SignedRegister {
         self.verify()
     }
 
-    /// Return the Register after applying all the operations
+    /// Return the Register after applying all the operations.
+    /// The operations written by users not allowed to write at the time are ignored, see `PermissionsOp`.
     pub fn register(self) -> Result<Register> {
-        let mut register = self.base_register;
-        for op in self.ops {
+        let mut register = self.base_register_with_permissions_ops()?;
+        let permitted_ops: Vec<_> = register
+            .permitted_ops(&self.ops)
+            .into_iter()
+            .cloned()
+            .collect();
+        for op in permitted_ops {
             register.apply_op(op)?;
         }
+        if let Some(checkpoint) = self.checkpoint {
+            register.apply_checkpoint_op(checkpoint)?;
+        }
         Ok(register)
     }
 
+    /// Remove the operations which are ignored when reading the Register as their writer is not
+    /// allowed to write, except for the ones already present in `known`, returning the number of
+    /// operations removed. This is used to refuse the operations written by a revoked user.
+    pub fn remove_unpermitted_ops(&mut self, known: Option<&Self>) -> Result<usize> {
+        let register = self.base_register_with_permissions_ops()?;
+        let permitted_ops: BTreeSet<_> = register
+            .permitted_ops(&self.ops)
+            .into_iter()
+            .cloned()
+            .collect();
+        let len_before = self.ops.len();
+        self.ops.retain(|op| {
+            permitted_ops.contains(op) || known.is_some_and(|known| known.ops.contains(op))
+        });
+        Ok(len_before - self.ops.len())
+    }
+
     /// Merge two SignedRegisters
     pub fn merge(&mut self, other: &Self) -> Result<()> {
         self.base_register
             .verify_is_mergeable(&other.base_register)?;
         self.ops.extend(other.ops.clone());
+        self.permissions_ops.extend(other.permissions_ops.clone());
+        if let Some(checkpoint) = &other.checkpoint {
+            keep_latest_checkpoint(&mut self.checkpoint, checkpoint);
+        }
+        self.collapse_ops();
         Ok(())
     }
 
@@ -108,6 +172,11 @@ impl SignedRegister {
             .verify_is_mergeable(&other.base_register)?;
         other.verify()?;
         self.ops.extend(other.ops.clone());
+        self.permissions_ops.extend(other.permissions_ops.clone());
+        if let Some(checkpoint) = &other.checkpoint {
+            keep_latest_checkpoint(&mut self.checkpoint, checkpoint);
+        }
+        self.collapse_ops();
         Ok(())
     }
 
@@ -116,23 +185,109 @@ impl SignedRegister {
         self.base_register.address()
     }
 
-    /// Return the owner of the data.
+    /// Return the owner of the data, i.e. the last one it was transferred to, if any.
     pub fn owner(&self) -> PublicKey {
-        self.base_register.owner()
+        self.base_register_with_permissions_ops()
+            .map_or_else(|_| self.base_register.owner(), |register| register.owner())
     }
 
     /// Check and add an Op to the SignedRegister
     pub fn add_op(&mut self, op: RegisterOp) -> Result<()> {
-        self.base_register.check_register_op(&op)?;
+        self.base_register_with_permissions_ops()?
+            .check_register_op(&op)?;
         self.ops.insert(op);
         Ok(())
     }
 
+    /// Check and add Ops sent as a delta over the state of this SignedRegister, i.e. which were
+    /// all written atop entries it already holds or atop one another.
+    /// If any of them was written atop an unknown entry, none of them is added and
+    /// `Error::NoSuchEntry` is returned, the whole state having to be merged instead.
+    pub fn add_delta_ops(&mut self, ops: BTreeSet<RegisterOp>) -> Result<()> {
+        let register = self.base_register_with_permissions_ops()?;
+        let mut known: BTreeSet<_> = self.ops.iter().map(|op| op.crdt_op.hash()).collect();
+        known.extend(ops.iter().map(|op| op.crdt_op.hash()));
+        if let Some(checkpoint) = &self.checkpoint {
+            known.extend(checkpoint.snapshot.iter().map(|node| node.hash()));
+        }
+
+        for op in &ops {
+            register.check_op_address(op.address)?;
+            register.check_register_op(op)?;
+            let unknown_child = op.crdt_op.children.iter().find(|child| {
+                !known.contains(*child) && self.base_register.merkle_reg().node(**child).is_none()
+            });
+            if let Some(child) = unknown_child {
+                return Err(Error::NoSuchEntry(EntryHash(*child)));
+            }
+        }
+
+        self.ops.extend(ops);
+        self.collapse_ops();
+        Ok(())
+    }
+
+    /// Returns true if the SignedRegister holds the given Op.
+    pub fn contains_op(&self, op: &RegisterOp) -> bool {
+        self.ops.contains(op)
+    }
+
+    /// Check and add a permissions Op to the SignedRegister
+    pub fn add_permissions_op(&mut self, op: PermissionsOp) -> Result<()> {
+        self.base_register_with_permissions_ops()?
+            .check_permissions_op(&op)?;
+        self.permissions_ops.insert(op);
+        Ok(())
+    }
+
+    /// Check and add a checkpoint Op to the SignedRegister, dropping the operations it collapsed
+    pub fn add_checkpoint_op(&mut self, op: CheckpointOp) -> Result<()> {
+        self.base_register_with_permissions_ops()?
+            .check_checkpoint_op(&op)?;
+        if keep_latest_checkpoint(&mut self.checkpoint, &op) {
+            self.collapse_ops();
+        }
+        Ok(())
+    }
+
     /// Access the underlying MerkleReg (e.g. for access to history)
     /// NOTE: This API is unstable and may be removed in the future
     pub fn merkle_reg(&self) -> &MerkleReg<Entry> {
         self.base_register.merkle_reg()
     }
+
+    // Private helper returning the base register with all the permissions changes applied.
+    fn base_register_with_permissions_ops(&self) -> Result<Register> {
+        let mut register = self.base_register.clone();
+        for op in &self.permissions_ops {
+            register.apply_permissions_op(op.clone())?;
+        }
+        Ok(register)
+    }
+
+    // Private helper dropping the operations collapsed by the latest checkpoint, i.e. all the
+    // ones which were not written atop its snapshot.
+    fn collapse_ops(&mut self) {
+        if let Some(checkpoint) = &self.checkpoint {
+            let nodes: Vec<_> = self.ops.iter().map(|op| op.crdt_op.clone()).collect();
+            let kept = written_atop(&checkpoint.snapshot, &nodes);
+            let snapshot: BTreeSet<_> =
+                checkpoint.snapshot.iter().map(|node| node.hash()).collect();
+            self.ops.retain(|op| {
+                let hash = op.crdt_op.hash();
+                kept.contains(&hash) && !snapshot.contains(&hash)
+            });
+        }
+    }
+}
+
+// Keep the latest of the two checkpoints, returning true if it is `other`.
+fn keep_latest_checkpoint(current: &mut Option<CheckpointOp>, other: &CheckpointOp) -> bool {
+    if current.as_ref().is_some_and(|current| current >= other) {
+        return false;
+    }
+    *current = Some(other.clone());
+    true
 }
 
 impl Register {
@@ -143,12 +298,15 @@ impl Register {
         Self {
             crdt: RegisterCrdt::new(address),
             permissions,
+            permissions_ops: BTreeSet::new(),
+            checkpoint: None,
         }
     }
 
-    /// Sign a Register and return the signature, makes sure the signer is the owner in the process
+    /// Sign a Register and return the signature, makes sure the signer is the owner in the process.
+    /// The owner which signs the Register is the one it was created by, whose key is part of its address.
     pub fn sign(&self, secret_key: &SecretKey) -> Result<Signature> {
-        if self.owner() != secret_key.public_key() {
+        if self.address().owner() != secret_key.public_key() {
             return Err(Error::InvalidSecretKey);
         }
         let bytes = self.bytes()?;
@@ -173,9 +331,9 @@ impl Register {
         self.crdt.address()
     }
 
-    /// Return the owner of the data.
+    /// Return the owner of the data, i.e. the one it was created by, unless the ownership was transferred.
     pub fn owner(&self) -> PublicKey {
-        self.address().owner()
+        self.permissions_history().owner
     }
 
     /// Return the number of items held in the register
@@ -198,11 +356,82 @@ impl Register {
         self.crdt.children(hash)
     }
 
-    /// Return the permission.
+    /// Return the permissions the Register was created with.
+    /// See `current_permissions` for the ones in effect after the changes made to them.
     pub fn permissions(&self) -> &Permissions {
         &self.permissions
     }
 
+    /// Return the current permissions, i.e. the ones the Register was created with after
+    /// applying all the changes made to them.
+    pub fn current_permissions(&self) -> Permissions {
+        self.permissions_history().permissions
+    }
+
+    /// Change the permissions of the Register, or transfer its ownership, returning the generated
+    /// operation so the caller can broadcast it to other replicas.
+    /// Only the owner can change the permissions, and it always remains allowed to write.
+    pub fn change_permissions(
+        &mut self,
+        change: PermissionsChange,
+        signer: &SecretKey,
+    ) -> Result<PermissionsOp> {
+        if signer.public_key() != self.owner() {
+            return Err(Error::AccessDenied(signer.public_key()));
+        }
+        let seq = self.next_permissions_seq();
+        let heads = self.read().into_iter().map(|(hash, _)| hash).collect();
+        let op = PermissionsOp::new(*self.address(), seq, change, heads, signer)?;
+        self.permissions_ops.insert(op.clone());
+        Ok(op)
+    }
+
+    /// Apply a signed permissions change operation.
+    pub fn apply_permissions_op(&mut self, op: PermissionsOp) -> Result<()> {
+        self.check_permissions_op(&op)?;
+        self.permissions_ops.insert(op);
+        Ok(())
+    }
+
+    /// Make a checkpoint of the Register, collapsing its history below its latest entries,
+    /// returning the generated operation so the caller can broadcast it to other replicas.
+    /// Only the owner can make a checkpoint.
+    pub fn checkpoint(&mut self, signer: &SecretKey) -> Result<CheckpointOp> {
+        if signer.public_key() != self.owner() {
+            return Err(Error::AccessDenied(signer.public_key()));
+        }
+        let seq = self
+            .checkpoint
+            .as_ref()
+            .map(|checkpoint| checkpoint.seq + 1)
+            .unwrap_or_default();
+        let snapshot = self.merkle_reg().read().nodes().cloned().collect();
+        let op = CheckpointOp::new(
+            *self.address(),
+            self.next_permissions_seq(),
+            seq,
+            snapshot,
+            signer,
+        )?;
+        self.apply_checkpoint_op(op.clone())?;
+        Ok(op)
+    }
+
+    /// Apply a signed checkpoint operation, if it's the latest one.
+    pub fn apply_checkpoint_op(&mut self, op: CheckpointOp) -> Result<()> {
+        self.check_checkpoint_op(&op)?;
+        if keep_latest_checkpoint(&mut self.checkpoint, &op) {
+            self.crdt.collapse(&op.snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Returns true if the entry of the op was collapsed by the latest checkpoint, hence
+    /// there is no need to send it to other replicas.
+    pub fn is_collapsed(&self, op: &RegisterOp) -> bool {
+        self.checkpoint.is_some() && self.crdt.get(EntryHash(op.crdt_op.hash())).is_none()
+    }
+
     /// Write an entry to the Register, returning the generated
     /// CRDT operation so the caller can sign and broadcast it to other replicas,
     /// along with the hash of the entry just written.
@@ -224,32 +453,115 @@ impl Register {
     pub fn apply_op(&mut self, op: RegisterOp) -> Result<()> {
         self.check_entry_and_reg_sizes(&op.crdt_op.value)?;
         self.check_register_op(&op)?;
-        self.crdt.apply_op(op)
+        // the history only has to be collapsed again if the entry isn't written atop the entries
+        // kept by the latest checkpoint
+        let atop_kept = self.merkle_reg().num_orphans() == 0
+            && !op.crdt_op.children.is_empty()
+            && op
+                .crdt_op
+                .children
+                .iter()
+                .all(|child| self.merkle_reg().node(*child).is_some());
+        self.crdt.apply_op(op)?;
+        if let Some(checkpoint) = &self.checkpoint {
+            if !atop_kept {
+                self.crdt.collapse(&checkpoint.snapshot)?;
+            }
+        }
+        Ok(())
     }
 
     /// Merge another Register into this one.
+    /// The permissions changes and checkpoint of the other Register are checked before being merged.
     pub fn merge(&mut self, other: &Self) -> Result<()> {
         self.verify_is_mergeable(other)?;
+        // they are applied in order, so the changes made by a new owner are checked
+        // once the transfer of the ownership to it was applied
+        for op in other
+            .permissions_ops
+            .difference(&self.permissions_ops.clone())
+        {
+            self.apply_permissions_op(op.clone())?;
+        }
+        if let Some(checkpoint) = &other.checkpoint {
+            self.check_checkpoint_op(checkpoint)?;
+        }
         self.crdt.merge(other.crdt.clone());
+        if let Some(checkpoint) = &other.checkpoint {
+            keep_latest_checkpoint(&mut self.checkpoint, checkpoint);
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            self.crdt.collapse(&checkpoint.snapshot)?;
+        }
         Ok(())
     }
 
-    /// Check if a register op is valid for our current register
+    /// Check if a register op is valid for our current register.
+    /// As an op can be received after its writer was revoked, it is accepted if its writer was
+    /// allowed to write at any point, `SignedRegister::register` then ignoring the ones written
+    /// after the revocation.
     pub fn check_register_op(&self, op: &RegisterOp) -> Result<()> {
-        if self.permissions.can_anyone_write() {
+        let history = self.permissions_history();
+        let mut all_permissions = history
+            .changes
+            .iter()
+            .map(|(_, permissions)| permissions)
+            .chain([&history.permissions]);
+        if all_permissions.clone().all(Permissions::can_anyone_write) {
             return Ok(()); // anyone can write, so no need to check the signature
         }
-        self.check_user_permissions(op.source)?;
+        if !all_permissions.any(|permissions| permissions.can_write(&op.source)) {
+            return Err(Error::AccessDenied(op.source));
+        }
         op.verify_signature(&op.source)
     }
 
+    /// Check if a permissions op is valid for our current register, i.e. made by its owner.
+    /// As an op can be received after the ownership was transferred, it is accepted if made by its
+    /// creator or any user the ownership was transferred to, the ones not made by the owner at the
+    /// time being then ignored.
+    pub fn check_permissions_op(&self, op: &PermissionsOp) -> Result<()> {
+        self.check_op_address(op.address)?;
+        if !self.is_or_was_owner(&op.source) {
+            return Err(Error::AccessDenied(op.source));
+        }
+        op.verify_signature()
+    }
+
+    /// Check if a checkpoint op is valid for our current register, i.e. made by the owner in
+    /// effect after the permissions changes made before it, with a snapshot of valid entries.
+    pub fn check_checkpoint_op(&self, op: &CheckpointOp) -> Result<()> {
+        self.check_op_address(op.address)?;
+        if self.owner_before(op.permissions_seq) != op.source {
+            return Err(Error::AccessDenied(op.source));
+        }
+        if op.snapshot.is_empty() {
+            return Err(Error::EmptyCheckpoint);
+        }
+        if op.snapshot.len() > MAX_REG_NUM_ENTRIES.into() {
+            return Err(Error::TooManyEntries(op.snapshot.len()));
+        }
+        if let Some(size) = op
+            .snapshot
+            .iter()
+            .map(|node| node.value.len())
+            .find(|size| *size > MAX_REG_ENTRY_SIZE)
+        {
+            return Err(Error::EntryTooBig {
+                size,
+                max: MAX_REG_ENTRY_SIZE,
+            });
+        }
+        op.verify_signature()
+    }
+
     /// Helper to check user write permissions for the given requester's public key.
     ///
     /// Returns:
     /// `Ok(())` if the user can write to this register
     /// `Err::AccessDenied` if the user cannot write to this register
     pub fn check_user_permissions(&self, requester: PublicKey) -> Result<()> {
-        if self.permissions.can_write(&requester) {
+        if self.current_permissions().can_write(&requester) {
             Ok(())
         } else {
             Err(Error::AccessDenied(requester))
@@ -281,6 +593,159 @@ impl Register {
         Ok(())
     }
 
+    // Private helper to check an op is destined for this Register.
+    fn check_op_address(&self, address: RegisterAddress) -> Result<()> {
+        if address != *self.address() {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(address),
+                reg_addr: Box::new(*self.address()),
+            });
+        }
+        Ok(())
+    }
+
+    // Private helper returning true if the user created the Register or the ownership of the
+    // Register was transferred to it at some point.
+    fn is_or_was_owner(&self, user: &PublicKey) -> bool {
+        *user == self.address().owner()
+            || self
+                .permissions_ops
+                .iter()
+                .any(|op| op.change == PermissionsChange::TransferOwnership(*user))
+    }
+
+    // Private helper returning the owner in effect before the permissions change of the given seq.
+    fn owner_before(&self, seq: u64) -> PublicKey {
+        let mut owner = self.address().owner();
+        for (op, _) in self.permissions_history().changes {
+            if op.seq >= seq {
+                break;
+            }
+            if let PermissionsChange::TransferOwnership(new_owner) = op.change {
+                owner = new_owner;
+            }
+        }
+        owner
+    }
+
+    // Private helper returning the seq of the next change to be made to the permissions.
+    fn next_permissions_seq(&self) -> u64 {
+        self.permissions_ops
+            .iter()
+            .map(|op| op.seq + 1)
+            .max()
+            .unwrap_or_default()
+    }
+
+    // Private helper applying the changes made to the permissions, in order, ignoring the ones
+    // not made by the owner at the time.
+    fn permissions_history(&self) -> PermissionsHistory<'_> {
+        let mut owner = self.address().owner();
+        let mut permissions = self.permissions.clone();
+        let mut changes = vec![];
+        for op in &self.permissions_ops {
+            if op.source != owner {
+                continue;
+            }
+            let before = permissions.clone();
+            op.change.apply(&mut permissions, &mut owner);
+            changes.push((op, before));
+        }
+        PermissionsHistory {
+            changes,
+            permissions,
+            owner,
+        }
+    }
+
+    // Private helper returning the given ops whose writer was allowed to write.
+    //
+    // An op is judged against the permissions in effect before the first change made once the
+    // owner knew about the op, i.e. once its entry was among the heads of the change or their
+    // ancestors, or against the current permissions if there is no such change. Hence the ops
+    // written by a user before being revoked are kept, while the ones the owner didn't know
+    // about when revoking the user are ignored. The ops written atop any of the ignored ones
+    // are ignored as well, so that the entries of a revoked user don't come back once a
+    // permitted user writes atop them.
+    fn permitted_ops<'a>(&self, ops: &'a BTreeSet<RegisterOp>) -> BTreeSet<&'a RegisterOp> {
+        let mut ops_by_hash: BTreeMap<EntryHash, Vec<&RegisterOp>> = BTreeMap::new();
+        for op in ops {
+            ops_by_hash
+                .entry(EntryHash(op.crdt_op.hash()))
+                .or_default()
+                .push(op);
+        }
+        let ancestors_of = |hashes: &mut Vec<EntryHash>| {
+            let mut ancestors = BTreeSet::new();
+            while let Some(hash) = hashes.pop() {
+                if ancestors.insert(hash) {
+                    hashes.extend(
+                        ops_by_hash
+                            .get(&hash)
+                            .into_iter()
+                            .flatten()
+                            .flat_map(|op| op.crdt_op.children.iter().copied().map(EntryHash)),
+                    );
+                }
+            }
+            ancestors
+        };
+
+        let history = self.permissions_history();
+        let known_at_change: Vec<_> = history
+            .changes
+            .iter()
+            .map(|(op, before)| {
+                (
+                    ancestors_of(&mut op.heads.iter().copied().collect()),
+                    before,
+                )
+            })
+            .collect();
+        let mut permitted_hashes: BTreeSet<_> = ops
+            .iter()
+            .filter(|op| {
+                let hash = EntryHash(op.crdt_op.hash());
+                known_at_change
+                    .iter()
+                    .find(|(known, _)| known.contains(&hash))
+                    .map_or(&history.permissions, |(_, before)| before)
+                    .can_write(&op.source)
+            })
+            .map(|op| EntryHash(op.crdt_op.hash()))
+            .collect();
+        let mut ignored_hashes: BTreeSet<_> = ops_by_hash
+            .keys()
+            .filter(|hash| !permitted_hashes.contains(*hash))
+            .copied()
+            .collect();
+        loop {
+            let atop_ignored: Vec<_> = permitted_hashes
+                .iter()
+                .filter(|hash| {
+                    ops_by_hash[*hash].iter().any(|op| {
+                        op.crdt_op
+                            .children
+                            .iter()
+                            .any(|child| ignored_hashes.contains(&EntryHash(*child)))
+                    })
+                })
+                .copied()
+                .collect();
+            if atop_ignored.is_empty() {
+                break;
+            }
+            for hash in atop_ignored {
+                permitted_hashes.remove(&hash);
+                ignored_hashes.insert(hash);
+            }
+        }
+
+        ops.iter()
+            .filter(|op| permitted_hashes.contains(&EntryHash(op.crdt_op.hash())))
+            .collect()
+    }
+
     // Private helper to check if this Register is mergeable with another
     fn verify_is_mergeable(&self, other: &Self) -> Result<()> {
         if self.address() != other.address() || self.permissions != other.permissions {
@@ -295,16 +760,19 @@ impl Register {
         Register {
             crdt: RegisterCrdt::new(address),
             permissions: Permissions::AnyoneCanWrite,
+            permissions_ops: BTreeSet::new(),
+            checkpoint: None,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::RegisterOp;
+    use crate::{CheckpointOp, PermissionsOp, RegisterOp};
 
     use super::{
-        EntryHash, Error, Permissions, Register, RegisterAddress, Result, MAX_REG_NUM_ENTRIES,
+        Entry, EntryHash, Error, Permissions, PermissionsChange, Register, RegisterAddress, Result,
+        SignedRegister, MAX_REG_NUM_ENTRIES,
     };
 
     use bls::SecretKey;
@@ -441,6 +909,553 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_writer_revocation() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+        let other_user_sk = SecretKey::random();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut owner_replica = Register::new(owner, meta, Permissions::new_with([writer]));
+        let mut writer_replica = owner_replica.clone();
+        let mut signed_reg = owner_replica.clone().into_signed(&owner_sk)?;
+
+        // the writer writes an entry the owner knows about before revoking the writer
+        let (hash1, op1) =
+            writer_replica.write(random_register_entry(), &BTreeSet::new(), &writer_sk)?;
+        owner_replica.apply_op(op1.clone())?;
+        signed_reg.add_op(op1)?;
+
+        // only the owner can change the permissions
+        let res =
+            owner_replica.change_permissions(PermissionsChange::RemoveWriter(writer), &writer_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(writer)),
+            "Unexpected result: {res:?}"
+        );
+        let mut forged_replica = Register::new(writer, meta, Permissions::default());
+        let forged_op =
+            forged_replica.change_permissions(PermissionsChange::AnyoneCanWrite, &writer_sk)?;
+        let res = signed_reg.add_permissions_op(forged_op);
+        assert!(
+            matches!(&res, Err(Error::RegisterAddrMismatch { .. })),
+            "Unexpected result: {res:?}"
+        );
+
+        let revocation =
+            owner_replica.change_permissions(PermissionsChange::RemoveWriter(writer), &owner_sk)?;
+        assert_eq!(
+            owner_replica.current_permissions(),
+            Permissions::new_with([owner])
+        );
+        signed_reg.add_permissions_op(revocation.clone())?;
+
+        // the writer keeps on writing atop its entry, not yet knowing it was revoked
+        let (hash2, op2) =
+            writer_replica.write(random_register_entry(), &[hash1].into(), &writer_sk)?;
+        // ...which is accepted as it was allowed to write at some point, but ignored when reading
+        signed_reg.add_op(op2)?;
+        let register = signed_reg.clone().register()?;
+        assert_eq!(
+            register.current_permissions(),
+            Permissions::new_with([owner])
+        );
+        assert_eq!(hashes(&register.read()), [hash1].into());
+        assert!(register.get(hash2).is_err());
+
+        // once aware of the revocation, the writer can no longer write
+        writer_replica.apply_permissions_op(revocation)?;
+        let res = writer_replica.write(random_register_entry(), &[hash1].into(), &writer_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(writer)),
+            "Unexpected result: {res:?}"
+        );
+
+        // a user who was never allowed to write is still refused
+        let (_, address, crdt_op) = writer_replica
+            .crdt
+            .write(random_register_entry(), &BTreeSet::new())?;
+        let op_by_other_user = RegisterOp::new(address, crdt_op, &other_user_sk);
+        let res = signed_reg.add_op(op_by_other_user);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(other_user_sk.public_key())),
+            "Unexpected result: {res:?}"
+        );
+
+        // the ops written after the revocation can be removed, unless already known
+        let known = signed_reg.clone();
+        assert_eq!(signed_reg.remove_unpermitted_ops(Some(&known))?, 0);
+        assert_eq!(signed_reg.remove_unpermitted_ops(None)?, 1);
+        assert_eq!(signed_reg.register()?, register);
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_entries_atop_revoked_writes_are_ignored() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut owner_replica = Register::new(owner, meta, Permissions::new_with([writer]));
+        let mut writer_replica = owner_replica.clone();
+        let mut signed_reg = owner_replica.clone().into_signed(&owner_sk)?;
+
+        let (hash1, op1) =
+            owner_replica.write(random_register_entry(), &BTreeSet::new(), &owner_sk)?;
+        writer_replica.apply_op(op1.clone())?;
+        signed_reg.add_op(op1)?;
+        let revocation =
+            owner_replica.change_permissions(PermissionsChange::RemoveWriter(writer), &owner_sk)?;
+        signed_reg.add_permissions_op(revocation)?;
+
+        // the revoked writer writes atop the owner's entry, not yet knowing it was revoked
+        let (hash2, op2) =
+            writer_replica.write(random_register_entry(), &[hash1].into(), &writer_sk)?;
+        owner_replica.apply_op(op2.clone())?;
+        signed_reg.add_op(op2)?;
+
+        // ...and the owner then merges the branches, writing atop the revoked writer's entry
+        let (hash3, op3) =
+            owner_replica.write(random_register_entry(), &[hash1, hash2].into(), &owner_sk)?;
+        signed_reg.add_op(op3)?;
+
+        // the revoked writer's entry doesn't come back, nor does the one written atop it
+        let register = signed_reg.clone().register()?;
+        assert_eq!(hashes(&register.read()), [hash1].into());
+        assert!(register.get(hash2).is_err());
+        assert!(register.get(hash3).is_err());
+        assert_eq!(signed_reg.remove_unpermitted_ops(None)?, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_ownership_transfer() -> eyre::Result<()> {
+        let creator_sk = SecretKey::random();
+        let creator = creator_sk.public_key();
+        let new_owner_sk = SecretKey::random();
+        let new_owner = new_owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica = Register::new(creator, meta, Permissions::default());
+        let mut signed_reg = replica.clone().into_signed(&creator_sk)?;
+
+        let transfer = replica
+            .change_permissions(PermissionsChange::TransferOwnership(new_owner), &creator_sk)?;
+        assert_eq!(replica.owner(), new_owner);
+        assert_eq!(*replica.address(), RegisterAddress::new(meta, creator));
+        assert_eq!(
+            replica.current_permissions(),
+            Permissions::new_with([new_owner])
+        );
+
+        // the previous owner can no longer write nor change the permissions
+        let res = replica.write(random_register_entry(), &BTreeSet::new(), &creator_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+        let res = replica.change_permissions(PermissionsChange::AnyoneCanWrite, &creator_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+
+        // ...while the new owner governs the permissions
+        let add_writer =
+            replica.change_permissions(PermissionsChange::AddWriter(writer), &new_owner_sk)?;
+        let (_, op) = replica.write(random_register_entry(), &BTreeSet::new(), &writer_sk)?;
+
+        // the new owner's changes are only accepted once the transfer is known
+        let res = signed_reg.add_permissions_op(add_writer.clone());
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(new_owner)),
+            "Unexpected result: {res:?}"
+        );
+        signed_reg.add_permissions_op(transfer)?;
+        signed_reg.add_permissions_op(add_writer)?;
+        signed_reg.add_op(op)?;
+        signed_reg.verify()?;
+        assert_eq!(signed_reg.owner(), new_owner);
+
+        let register = signed_reg.register()?;
+        assert_eq!(register.owner(), new_owner);
+        assert_eq!(
+            register.current_permissions(),
+            replica.current_permissions()
+        );
+        assert_eq!(register.read(), replica.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_concurrent_ownership_transfers() -> eyre::Result<()> {
+        let creator_sk = SecretKey::random();
+        let creator = creator_sk.public_key();
+        let owner1_sk = SecretKey::random();
+        let owner2_sk = SecretKey::random();
+        let writer = SecretKey::random().public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica1 = Register::new(creator, meta, Permissions::default());
+        let mut replica2 = replica1.clone();
+
+        // the creator transfers the ownership to two different users from two replicas
+        let transfer1 = replica1.change_permissions(
+            PermissionsChange::TransferOwnership(owner1_sk.public_key()),
+            &creator_sk,
+        )?;
+        let transfer2 = replica2.change_permissions(
+            PermissionsChange::TransferOwnership(owner2_sk.public_key()),
+            &creator_sk,
+        )?;
+        assert_eq!(transfer1.seq(), transfer2.seq());
+        let change1 =
+            replica1.change_permissions(PermissionsChange::AddWriter(writer), &owner1_sk)?;
+        let change2 =
+            replica2.change_permissions(PermissionsChange::AddWriter(writer), &owner2_sk)?;
+
+        // ...only the first transfer in order takes effect, whatever the order they are merged in
+        let (winner_sk, winner_change) = if transfer1 < transfer2 {
+            (&owner1_sk, change1)
+        } else {
+            (&owner2_sk, change2)
+        };
+        let mut merged1 = replica1.clone();
+        merged1.merge(&replica2)?;
+        let mut merged2 = replica2.clone();
+        merged2.merge(&replica1)?;
+        assert_eq!(merged1, merged2);
+        assert_eq!(merged1.owner(), winner_sk.public_key());
+        assert_eq!(
+            merged1.current_permissions(),
+            Permissions::new_with([winner_sk.public_key(), writer])
+        );
+
+        // ...the changes made by the other user are ignored
+        let mut winner_only = Register::new(creator, meta, Permissions::default());
+        winner_only.apply_permissions_op(if transfer1 < transfer2 {
+            transfer1
+        } else {
+            transfer2
+        })?;
+        winner_only.apply_permissions_op(winner_change)?;
+        assert_eq!(
+            winner_only.current_permissions(),
+            merged1.current_permissions()
+        );
+        let res = merged1.change_permissions(PermissionsChange::AnyoneCanWrite, &creator_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_checkpoint_collapses_history() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica1 = Register::new(owner, meta, Permissions::new_with([writer]));
+        let mut signed_reg = replica1.clone().into_signed(&owner_sk)?;
+
+        let mut children = BTreeSet::new();
+        let mut ops = vec![];
+        for _ in 0..5 {
+            let (hash, op) = replica1.write(random_register_entry(), &children, &writer_sk)?;
+            children = [hash].into();
+            ops.push(op);
+        }
+        for op in &ops {
+            signed_reg.add_op(op.clone())?;
+        }
+        let mut replica2 = replica1.clone();
+        let latest = replica1.read();
+
+        // only the owner can make a checkpoint
+        let res = replica1.checkpoint(&writer_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(writer)),
+            "Unexpected result: {res:?}"
+        );
+        let checkpoint = replica1.checkpoint(&owner_sk)?;
+        assert_eq!(replica1.size(), 1);
+        assert_eq!(replica1.read(), latest);
+        assert!(ops.iter().take(4).all(|op| replica1.is_collapsed(op)));
+        assert!(!replica2.is_collapsed(&ops[0]));
+
+        // entries written atop the snapshot are kept, the ones written atop older entries dropped
+        let (hash, op_atop) = replica1.write(random_register_entry(), &children, &writer_sk)?;
+        let first = EntryHash(ops[0].crdt_op.hash());
+        let (_, op_concurrent) =
+            replica2.write(random_register_entry(), &[first].into(), &writer_sk)?;
+        replica1.apply_op(op_concurrent.clone())?;
+        assert_eq!(hashes(&replica1.read()), [hash].into());
+
+        // replicas converge whatever the order the checkpoint is received in
+        let mut merged = replica2.clone();
+        merged.merge(&replica1)?;
+        replica1.merge(&replica2)?;
+        assert_eq!(merged, replica1);
+        assert_eq!(replica1.size(), 2);
+
+        // the signed register drops the ops collapsed by the checkpoint
+        signed_reg.add_op(op_concurrent)?;
+        let size_before = rmp_serde::to_vec(&signed_reg)?.len();
+        signed_reg.add_checkpoint_op(checkpoint)?;
+        signed_reg.add_op(op_atop)?;
+        signed_reg.verify()?;
+        assert!(rmp_serde::to_vec(&signed_reg)?.len() < size_before);
+        let register = signed_reg.register()?;
+        assert_eq!(register.read(), replica1.read());
+        assert_eq!(register.size(), 2);
+
+        // a checkpoint made by another user is refused
+        let mut forged_replica = Register::new(writer, meta, Permissions::default());
+        let _ = forged_replica.write(random_register_entry(), &BTreeSet::new(), &writer_sk)?;
+        let forged_checkpoint = forged_replica.checkpoint(&writer_sk)?;
+        assert!(replica1.apply_checkpoint_op(forged_checkpoint).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_serialisation_round_trips() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let writer = SecretKey::random().public_key();
+        let meta: XorName = xor_name::rand::random();
+
+        for (with_permissions_op, with_checkpoint) in
+            [(false, false), (true, false), (false, true), (true, true)]
+        {
+            let mut replica = Register::new(owner_sk.public_key(), meta, Permissions::default());
+            let mut signed_reg = replica.clone().into_signed(&owner_sk)?;
+            let (_, op) = replica.write(random_register_entry(), &BTreeSet::new(), &owner_sk)?;
+            signed_reg.add_op(op)?;
+            if with_permissions_op {
+                let op =
+                    replica.change_permissions(PermissionsChange::AddWriter(writer), &owner_sk)?;
+                signed_reg.add_permissions_op(op)?;
+            }
+            if with_checkpoint {
+                let op = replica.checkpoint(&owner_sk)?;
+                signed_reg.add_checkpoint_op(op)?;
+            }
+
+            let deserialised: Register = rmp_serde::from_slice(&rmp_serde::to_vec(&replica)?)?;
+            assert_eq!(deserialised, replica);
+            let deserialised: SignedRegister =
+                rmp_serde::from_slice(&rmp_serde::to_vec(&signed_reg)?)?;
+            assert_eq!(deserialised, signed_reg);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_checkpoint_by_previous_owner_is_refused() -> eyre::Result<()> {
+        let creator_sk = SecretKey::random();
+        let creator = creator_sk.public_key();
+        // concurrent transfers have the same seq, and only the first one in order takes effect,
+        // hence the transfer to the new owner takes effect over the one to the loser
+        let (new_owner_sk, loser_sk) = {
+            let (sk1, sk2) = (SecretKey::random(), SecretKey::random());
+            if sk1.public_key() < sk2.public_key() {
+                (sk1, sk2)
+            } else {
+                (sk2, sk1)
+            }
+        };
+        let loser = loser_sk.public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica = Register::new(creator, meta, Permissions::default());
+        let _ = replica.write(random_register_entry(), &BTreeSet::new(), &creator_sk)?;
+        let mut signed_reg = replica.clone().into_signed(&creator_sk)?;
+
+        // a checkpoint made by the creator before transferring the ownership remains valid
+        let checkpoint = replica.checkpoint(&creator_sk)?;
+        let mut losing_replica = replica.clone();
+        let transfer = replica.change_permissions(
+            PermissionsChange::TransferOwnership(new_owner_sk.public_key()),
+            &creator_sk,
+        )?;
+        signed_reg.add_checkpoint_op(checkpoint.clone())?;
+        signed_reg.add_permissions_op(transfer.clone())?;
+        signed_reg.verify()?;
+
+        // ...while the creator can no longer make one after the transfer
+        let res = replica.checkpoint(&creator_sk);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+        let (hash, _) = replica.write(random_register_entry(), &BTreeSet::new(), &new_owner_sk)?;
+        let snapshot = [replica
+            .merkle_reg()
+            .node(hash.0)
+            .cloned()
+            .ok_or_else(|| eyre::eyre!("entry not found"))?]
+        .into();
+        let late_checkpoint = CheckpointOp::new(
+            *replica.address(),
+            transfer.seq() + 1,
+            checkpoint.seq() + 1,
+            snapshot,
+            &creator_sk,
+        )?;
+        let res = replica.apply_checkpoint_op(late_checkpoint.clone());
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+        let res = signed_reg.add_checkpoint_op(late_checkpoint);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(creator)),
+            "Unexpected result: {res:?}"
+        );
+
+        // a user the ownership was concurrently transferred to, losing to the other transfer,
+        // can't make one either, even from a replica where it is the owner
+        let losing_transfer = losing_replica
+            .change_permissions(PermissionsChange::TransferOwnership(loser), &creator_sk)?;
+        assert!(transfer < losing_transfer);
+        let losing_checkpoint = losing_replica.checkpoint(&loser_sk)?;
+        replica.apply_permissions_op(losing_transfer)?;
+        let res = replica.apply_checkpoint_op(losing_checkpoint);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(loser)),
+            "Unexpected result: {res:?}"
+        );
+        let res = replica.merge(&losing_replica);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(loser)),
+            "Unexpected result: {res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_delta_ops() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica = Register::new(owner, meta, Permissions::default());
+        let mut signed_reg = replica.clone().into_signed(&owner_sk)?;
+        let (hash1, op1) = replica.write(random_register_entry(), &BTreeSet::new(), &owner_sk)?;
+        signed_reg.add_op(op1.clone())?;
+
+        // ops written atop known entries, or atop one another, are added as a delta
+        let (hash2, op2) = replica.write(random_register_entry(), &[hash1].into(), &owner_sk)?;
+        let (hash3, op3) = replica.write(random_register_entry(), &[hash2].into(), &owner_sk)?;
+        let mut delta_reg = signed_reg.clone();
+        delta_reg.add_delta_ops([op3.clone(), op2.clone()].into())?;
+        assert!(delta_reg.contains_op(&op2) && delta_reg.contains_op(&op3));
+        let mut expected_reg = signed_reg.clone();
+        expected_reg.add_op(op2.clone())?;
+        expected_reg.add_op(op3.clone())?;
+        assert_eq!(delta_reg, expected_reg);
+        assert_eq!(hashes(&delta_reg.register()?.read()), [hash3].into());
+
+        // a delta over entries which are not held is refused as a whole
+        let (_, op4) = replica.write(random_register_entry(), &[hash3].into(), &owner_sk)?;
+        let (_, op5) = replica.write(random_register_entry(), &BTreeSet::new(), &owner_sk)?;
+        let res = signed_reg.add_delta_ops([op4, op5.clone()].into());
+        assert!(
+            matches!(&res, Err(err) if err == &Error::NoSuchEntry(hash3)),
+            "Unexpected result: {res:?}"
+        );
+        assert!(!signed_reg.contains_op(&op5));
+
+        // as are ops from a user not allowed to write
+        let (_, op6) = replica.write(random_register_entry(), &BTreeSet::new(), &owner_sk)?;
+        let forged_op = RegisterOp::new(*signed_reg.address(), op6.crdt_op, &writer_sk);
+        let res = signed_reg.add_delta_ops([forged_op].into());
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(writer_sk.public_key())),
+            "Unexpected result: {res:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_permissions_changes_converge() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = owner_sk.public_key();
+        let writer_sk = SecretKey::random();
+        let writer = writer_sk.public_key();
+
+        let meta: XorName = xor_name::rand::random();
+        let mut replica1 = Register::new(owner, meta, Permissions::default());
+        let mut replica2 = replica1.clone();
+
+        let op1 = replica1.change_permissions(PermissionsChange::AnyoneCanWrite, &owner_sk)?;
+        assert!(replica1.current_permissions().can_anyone_write());
+        let op2 =
+            replica1.change_permissions(PermissionsChange::Writers([writer].into()), &owner_sk)?;
+        assert_eq!(op2.seq(), op1.seq() + 1);
+        assert_eq!(
+            replica1.current_permissions(),
+            Permissions::new_with([owner, writer])
+        );
+        let op3 = replica1.change_permissions(PermissionsChange::AddWriter(writer), &owner_sk)?;
+
+        // the changes are applied in order whatever the order they are received in
+        replica2.apply_permissions_op(op3)?;
+        replica2.apply_permissions_op(op2)?;
+        replica2.apply_permissions_op(op1)?;
+        assert_eq!(
+            replica1.current_permissions(),
+            replica2.current_permissions()
+        );
+        assert_eq!(replica1, replica2);
+
+        // the permissions changes are merged along with the entries
+        let mut replica3 = Register::new(owner, meta, Permissions::default());
+        replica3.merge(&replica1)?;
+        assert_eq!(
+            replica3.current_permissions(),
+            Permissions::new_with([owner, writer])
+        );
+        let _ = replica3.write(random_register_entry(), &BTreeSet::new(), &writer_sk)?;
+
+        // ...after checking they were made by the owner
+        let mut forged_replica = replica1.clone();
+        let forged_op = PermissionsOp::new(
+            *forged_replica.address(),
+            3,
+            PermissionsChange::AnyoneCanWrite,
+            BTreeSet::new(),
+            &writer_sk,
+        )?;
+        let _ = forged_replica.permissions_ops.insert(forged_op);
+        let res = replica3.merge(&forged_replica);
+        assert!(
+            matches!(&res, Err(err) if err == &Error::AccessDenied(writer)),
+            "Unexpected result: {res:?}"
+        );
+        assert!(!replica3.current_permissions().can_anyone_write());
+
+        Ok(())
+    }
+
     #[test]
     fn register_concurrent_write_ops() -> eyre::Result<()> {
         let authority_sk1 = SecretKey::random();
@@ -1043,6 +2058,10 @@ mod tests {
         }
     }
 
+    fn hashes(entries: &BTreeSet<(EntryHash, Entry)>) -> BTreeSet<EntryHash> {
+        entries.iter().map(|(hash, _)| *hash).collect()
+    }
+
     fn random_register_entry() -> Vec<u8> {
         let random_bytes = thread_rng().gen::<[u8; 32]>();
         random_bytes.to_vec()
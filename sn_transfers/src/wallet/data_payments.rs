@@ -8,7 +8,7 @@
 
 use crate::{MainPubkey, NanoTokens, Transfer};
 use libp2p::{identity::PublicKey, PeerId};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use std::time::SystemTime;
 use xor_name::XorName;
 
@@ -58,9 +58,10 @@ impl PaymentDetails {
 pub type QuoteSignature = Vec<u8>;
 
 /// Quoting metrics that got used to generate a quote, or to track peer's status.
-#[derive(
-    Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize, custom_debug::Debug,
-)]
+///
+/// The byte metrics are only serialized (and hence signed) when `max_storage_bytes` is set,
+/// so the quotes of nodes without a byte quota keep the layout older peers understand.
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Deserialize, custom_debug::Debug)]
 pub struct QuotingMetrics {
     /// the records stored
     pub close_records_stored: usize,
@@ -71,6 +72,12 @@ pub struct QuotingMetrics {
     /// the duration that node keeps connected to the network, measured in hours
     /// TODO: take `restart` into accout
     pub live_time: u64,
+    /// the total bytes of the records stored, only carried along with `max_storage_bytes`
+    #[serde(default)]
+    pub stored_bytes: u64,
+    /// the max_storage_bytes configured, None if the node has no byte quota
+    #[serde(default)]
+    pub max_storage_bytes: Option<u64>,
 }
 
 impl QuotingMetrics {
@@ -81,10 +88,32 @@ impl QuotingMetrics {
             max_records: 0,
             received_payment_count: 0,
             live_time: 0,
+            stored_bytes: 0,
+            max_storage_bytes: None,
         }
     }
 }
 
+impl Serialize for QuotingMetrics {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let with_bytes = self.max_storage_bytes.is_some();
+        let mut state =
+            serializer.serialize_struct("QuotingMetrics", if with_bytes { 6 } else { 4 })?;
+        state.serialize_field("close_records_stored", &self.close_records_stored)?;
+        state.serialize_field("max_records", &self.max_records)?;
+        state.serialize_field("received_payment_count", &self.received_payment_count)?;
+        state.serialize_field("live_time", &self.live_time)?;
+        if with_bytes {
+            state.serialize_field("stored_bytes", &self.stored_bytes)?;
+            state.serialize_field("max_storage_bytes", &self.max_storage_bytes)?;
+        } else {
+            state.skip_field("stored_bytes")?;
+            state.skip_field("max_storage_bytes")?;
+        }
+        state.end()
+    }
+}
+
 impl Default for QuotingMetrics {
     fn default() -> Self {
         Self::new()
@@ -325,6 +354,48 @@ mod tests {
         assert!(!quote.check_is_signed_by_claimed_peer(false_peer));
     }
 
+    #[test]
+    fn test_quoting_metrics_keep_legacy_layout_without_byte_quota() -> eyre::Result<()> {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct LegacyQuotingMetrics {
+            close_records_stored: usize,
+            max_records: usize,
+            received_payment_count: usize,
+            live_time: u64,
+        }
+        let legacy = LegacyQuotingMetrics {
+            close_records_stored: 10,
+            max_records: 100,
+            received_payment_count: 3,
+            live_time: 60,
+        };
+        let mut quoting_metrics = QuotingMetrics {
+            close_records_stored: 10,
+            max_records: 100,
+            received_payment_count: 3,
+            live_time: 60,
+            stored_bytes: 4096,
+            max_storage_bytes: None,
+        };
+
+        // Without a byte quota the metrics, and hence the signed bytes, match the legacy ones.
+        assert_eq!(
+            rmp_serde::to_vec(&quoting_metrics)?,
+            rmp_serde::to_vec(&legacy)?
+        );
+        let from_legacy: QuotingMetrics = rmp_serde::from_slice(&rmp_serde::to_vec(&legacy)?)?;
+        assert_eq!(from_legacy.stored_bytes, 0);
+        assert_eq!(from_legacy.max_storage_bytes, None);
+
+        // With a byte quota set, the byte metrics are carried along.
+        quoting_metrics.max_storage_bytes = Some(8192);
+        let round_tripped: QuotingMetrics =
+            rmp_serde::from_slice(&rmp_serde::to_vec(&quoting_metrics)?)?;
+        assert_eq!(round_tripped, quoting_metrics);
+
+        Ok(())
+    }
+
     #[test]
     fn test_historical_verify() {
         let mut old_quote = PaymentQuote::zero();